@@ -0,0 +1,19 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The bundled protoc means building needs no protobuf toolchain installed.
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+
+    tonic_build::configure().compile_protos_with_config(
+        config,
+        &["proto/todo.proto"],
+        &[
+            std::path::PathBuf::from("proto"),
+            protoc_bin_vendored::include_path()?,
+        ],
+    )?;
+
+    println!("cargo:rerun-if-changed=proto");
+    // `sqlx::migrate!` embeds the migrations, so new files need a rebuild.
+    println!("cargo:rerun-if-changed=migrations");
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! A command-line client for the gRPC service.
+//!
+//! ```sh
+//! TOKEN=aitodo_... cargo run --example grpc_client -- create "Buy milk"
+//! TOKEN=aitodo_... cargo run --example grpc_client -- list
+//! TOKEN=aitodo_... cargo run --example grpc_client -- done <id>
+//! TOKEN=aitodo_... cargo run --example grpc_client -- delete <id>
+//! TOKEN=aitodo_... cargo run --example grpc_client -- suggest memo.mp3 audio/mpeg
+//! ```
+//!
+//! `GRPC_URL` defaults to `http://127.0.0.1:50051`.
+
+use std::env;
+
+use tonic::{Request, metadata::MetadataValue};
+
+pub mod pb {
+    tonic::include_proto!("aitodo.v1");
+}
+
+use pb::todo_service_client::TodoServiceClient;
+
+/// Audio is uploaded in chunks of this size.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+fn authorized<T>(message: T, token: &MetadataValue<tonic::metadata::Ascii>) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert("authorization", token.clone());
+    request
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let url = env::var("GRPC_URL").unwrap_or_else(|_| "http://127.0.0.1:50051".to_string());
+    let token: MetadataValue<_> = format!("Bearer {}", env::var("TOKEN")?).parse()?;
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut client = TodoServiceClient::connect(url).await?;
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["create", title] => {
+            let request = pb::CreateTodoRequest {
+                title: title.to_string(),
+                ..Default::default()
+            };
+            let todo = client.create_todo(authorized(request, &token)).await?;
+            println!("{:#?}", todo.into_inner());
+        }
+        ["list"] => {
+            let request = authorized(pb::ListTodosRequest::default(), &token);
+            let mut stream = client.list_todos(request).await?.into_inner();
+            while let Some(todo) = stream.message().await? {
+                println!("{}  {:?}  {}", todo.id, todo.status(), todo.title);
+            }
+        }
+        ["done", id] => {
+            let request = pb::UpdateTodoRequest {
+                id: id.to_string(),
+                status: pb::TodoStatus::Done.into(),
+                ..Default::default()
+            };
+            let todo = client.update_todo(authorized(request, &token)).await?;
+            println!("{:#?}", todo.into_inner());
+        }
+        ["delete", id] => {
+            let request = pb::DeleteTodoRequest { id: id.to_string() };
+            let todo = client.delete_todo(authorized(request, &token)).await?;
+            println!("deleted {}", todo.into_inner().title);
+        }
+        ["suggest", path, mime_type] => {
+            let audio = std::fs::read(path)?;
+            let chunks: Vec<_> = audio
+                .chunks(CHUNK_SIZE)
+                .enumerate()
+                .map(|(i, data)| pb::AudioChunk {
+                    mime_type: if i == 0 {
+                        mime_type.to_string()
+                    } else {
+                        String::new()
+                    },
+                    data: data.to_vec(),
+                })
+                .collect();
+            let request = authorized(futures::stream::iter(chunks), &token);
+            let response = client.suggest_tasks(request).await?;
+            for task in response.into_inner().tasks {
+                println!("{:?}  {}", task.priority(), task.title);
+            }
+        }
+        _ => {
+            eprintln!(
+                "usage: grpc_client create <title> | list | done <id> | delete <id> | suggest <file> <mime-type>"
+            );
+            std::process::exit(2);
+        }
+    }
+
+    Ok(())
+}
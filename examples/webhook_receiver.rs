@@ -0,0 +1,123 @@
+//! A local endpoint for exercising outbound webhooks.
+//!
+//! It verifies each delivery's signature and prints the event:
+//!
+//! ```sh
+//! WEBHOOK_SECRET=whsec_... cargo run --example webhook_receiver
+//!
+//! # The server must be started with WEBHOOKS_ALLOW_LOOPBACK=true.
+//! curl -s -X POST http://127.0.0.1:5000/webhooks \
+//!   -H "Authorization: Bearer $TOKEN" -H 'Content-Type: application/json' \
+//!   -d '{"url": "http://127.0.0.1:9100/hook", "events": ["todo.created"], "secret": "whsec_..."}'
+//! ```
+//!
+//! Set `WEBHOOK_FAIL_FIRST=n` to answer the first `n` deliveries with a 500 and
+//! watch the server retry them.
+
+use std::{
+    env,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Deliveries signed longer ago than this are rejected as replays.
+const TOLERANCE_SECS: i64 = 5 * 60;
+
+struct Receiver {
+    secret: String,
+    fail_first: u32,
+    received: AtomicU32,
+}
+
+#[tokio::main]
+async fn main() {
+    let receiver = Receiver {
+        secret: env::var("WEBHOOK_SECRET").expect("WEBHOOK_SECRET must be set"),
+        fail_first: env::var("WEBHOOK_FAIL_FIRST")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0),
+        received: AtomicU32::new(0),
+    };
+
+    let app = Router::new()
+        .route("/hook", post(hook))
+        .with_state(Arc::new(receiver));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:9100")
+        .await
+        .unwrap();
+    println!("Webhook receiver listening on http://127.0.0.1:9100/hook");
+    axum::serve(listener, app).await.unwrap();
+}
+
+async fn hook(
+    State(receiver): State<Arc<Receiver>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+    };
+    let event = header("x-webhook-event");
+    let delivery = header("x-webhook-delivery");
+    let body = String::from_utf8_lossy(&body);
+
+    if let Err(reason) = verify(&receiver.secret, header("x-webhook-signature"), &body) {
+        println!("REJECTED {event} delivery={delivery}: {reason}");
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let n = receiver.received.fetch_add(1, Ordering::SeqCst) + 1;
+    if n <= receiver.fail_first {
+        println!(
+            "FAILING  {event} delivery={delivery} ({n}/{})",
+            receiver.fail_first
+        );
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    println!("OK       {event} delivery={delivery} {body}");
+    StatusCode::NO_CONTENT
+}
+
+/// Checks a `t=<unix time>,v1=<hex hmac>` signature over `"{t}.{body}"`.
+fn verify(secret: &str, signature: &str, body: &str) -> Result<(), &'static str> {
+    let mut timestamp = None;
+    let mut expected = None;
+    for part in signature.split(',') {
+        match part.split_once('=') {
+            Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+            Some(("v1", v)) => expected = hex::decode(v).ok(),
+            _ => {}
+        }
+    }
+    let (Some(timestamp), Some(expected)) = (timestamp, expected) else {
+        return Err("malformed signature header");
+    };
+
+    if (chrono::Utc::now().timestamp() - timestamp).abs() > TOLERANCE_SECS {
+        return Err("signature timestamp outside tolerance");
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+    // `verify_slice` compares in constant time.
+    mac.verify_slice(&expected)
+        .map_err(|_| "signature mismatch")
+}
@@ -0,0 +1,7 @@
+ALTER TABLE api_tokens
+    DROP COLUMN IF EXISTS kind,
+    DROP COLUMN IF EXISTS user_id;
+
+DROP TABLE IF EXISTS oidc_login_states;
+DROP TABLE IF EXISTS user_identities;
+DROP TABLE IF EXISTS users;
@@ -0,0 +1,13 @@
+DROP FUNCTION IF EXISTS todo_role(UUID, UUID);
+DROP FUNCTION IF EXISTS project_role(UUID, UUID);
+DROP FUNCTION IF EXISTS role_rank(TEXT);
+
+DROP TABLE IF EXISTS invitations;
+DROP TABLE IF EXISTS shares;
+
+DROP INDEX IF EXISTS todos_project_id_idx;
+ALTER TABLE todos
+    DROP COLUMN IF EXISTS project_id,
+    DROP COLUMN IF EXISTS owner_id;
+
+DROP TABLE IF EXISTS projects;
@@ -0,0 +1,75 @@
+CREATE TABLE IF NOT EXISTS projects (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    owner_id UUID REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+ALTER TABLE todos
+    ADD COLUMN IF NOT EXISTS owner_id UUID REFERENCES users (id) ON DELETE CASCADE,
+    ADD COLUMN IF NOT EXISTS project_id UUID REFERENCES projects (id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS todos_project_id_idx ON todos (project_id);
+
+-- A share grants a user a role on exactly one project or todo.
+CREATE TABLE IF NOT EXISTS shares (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    project_id UUID REFERENCES projects (id) ON DELETE CASCADE,
+    todo_id UUID REFERENCES todos (id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK (role IN ('Viewer', 'Editor', 'Owner')),
+    created_at TIMESTAMPTZ NOT NULL,
+    CHECK ((project_id IS NULL) <> (todo_id IS NULL))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS shares_project_user_idx ON shares (project_id, user_id) WHERE project_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS shares_todo_user_idx ON shares (todo_id, user_id) WHERE todo_id IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS invitations (
+    id UUID PRIMARY KEY,
+    project_id UUID REFERENCES projects (id) ON DELETE CASCADE,
+    todo_id UUID REFERENCES todos (id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('Viewer', 'Editor', 'Owner')),
+    invited_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    accepted_at TIMESTAMPTZ,
+    CHECK ((project_id IS NULL) <> (todo_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS invitations_email_idx ON invitations (email);
+
+CREATE OR REPLACE FUNCTION role_rank(role TEXT) RETURNS INT
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT CASE role WHEN 'Owner' THEN 3 WHEN 'Editor' THEN 2 WHEN 'Viewer' THEN 1 ELSE 0 END
+$$;
+
+-- Highest role a user holds on a project, or NULL for no access.
+CREATE OR REPLACE FUNCTION project_role(p_project_id UUID, p_user_id UUID) RETURNS TEXT
+LANGUAGE sql STABLE AS $$
+    SELECT role FROM (
+        SELECT 'Owner' AS role FROM projects WHERE id = p_project_id AND owner_id = p_user_id
+        UNION ALL
+        SELECT role FROM shares WHERE project_id = p_project_id AND user_id = p_user_id
+    ) r
+    ORDER BY role_rank(role) DESC
+    LIMIT 1
+$$;
+
+-- Highest role a user holds on a todo, directly or through its project.
+CREATE OR REPLACE FUNCTION todo_role(p_todo_id UUID, p_user_id UUID) RETURNS TEXT
+LANGUAGE sql STABLE AS $$
+    SELECT role FROM (
+        SELECT 'Owner' AS role FROM todos WHERE id = p_todo_id AND owner_id = p_user_id
+        UNION ALL
+        SELECT role FROM shares WHERE todo_id = p_todo_id AND user_id = p_user_id
+        UNION ALL
+        SELECT project_role(t.project_id, p_user_id) FROM todos t
+        WHERE t.id = p_todo_id AND t.project_id IS NOT NULL
+    ) r
+    WHERE role IS NOT NULL
+    ORDER BY role_rank(role) DESC
+    LIMIT 1
+$$;
@@ -0,0 +1,35 @@
+-- Rows from every workspace end up back in the same tables, as they were
+-- before workspaces existed.
+DROP POLICY IF EXISTS workspace_isolation ON invitations;
+DROP POLICY IF EXISTS workspace_isolation ON shares;
+DROP POLICY IF EXISTS workspace_isolation ON projects;
+DROP POLICY IF EXISTS workspace_isolation ON todos;
+
+ALTER TABLE invitations NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE invitations DISABLE ROW LEVEL SECURITY;
+ALTER TABLE shares NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE shares DISABLE ROW LEVEL SECURITY;
+ALTER TABLE projects NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE projects DISABLE ROW LEVEL SECURITY;
+ALTER TABLE todos NO FORCE ROW LEVEL SECURITY;
+ALTER TABLE todos DISABLE ROW LEVEL SECURITY;
+
+-- The role is shared by every database in the cluster, so it stays; only
+-- what this database granted it is taken back.
+ALTER DEFAULT PRIVILEGES IN SCHEMA public REVOKE USAGE, SELECT ON SEQUENCES FROM ai_todo_tenant;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public REVOKE SELECT, INSERT, UPDATE, DELETE ON TABLES FROM ai_todo_tenant;
+REVOKE USAGE, SELECT ON ALL SEQUENCES IN SCHEMA public FROM ai_todo_tenant;
+REVOKE SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA public FROM ai_todo_tenant;
+REVOKE USAGE ON SCHEMA public FROM ai_todo_tenant;
+
+DROP INDEX IF EXISTS projects_workspace_id_idx;
+DROP INDEX IF EXISTS todos_workspace_id_idx;
+
+ALTER TABLE api_tokens DROP COLUMN IF EXISTS workspace_id;
+ALTER TABLE invitations DROP COLUMN IF EXISTS workspace_id;
+ALTER TABLE shares DROP COLUMN IF EXISTS workspace_id;
+ALTER TABLE projects DROP COLUMN IF EXISTS workspace_id;
+ALTER TABLE todos DROP COLUMN IF EXISTS workspace_id;
+
+DROP TABLE IF EXISTS workspace_members;
+DROP TABLE IF EXISTS workspaces;
@@ -0,0 +1,99 @@
+-- Workspaces are the tenant boundary. Tenant data (todos, projects, shares,
+-- invitations) is isolated with row-level security keyed by the
+-- `app.workspace_id` setting, which the application sets per transaction.
+-- Identity tables (users, user_identities, recovery_codes, mfa_challenges,
+-- oidc_login_states) are global: a user may belong to several workspaces.
+
+CREATE TABLE IF NOT EXISTS workspaces (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS workspace_members (
+    workspace_id UUID NOT NULL REFERENCES workspaces (id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK (role IN ('Member', 'Admin')),
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (workspace_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS workspace_members_user_id_idx ON workspace_members (user_id);
+
+-- Existing data moves into a default workspace that every existing user joins.
+INSERT INTO workspaces (id, name, created_at)
+VALUES ('00000000-0000-0000-0000-000000000001', 'Default', now())
+ON CONFLICT DO NOTHING;
+
+INSERT INTO workspace_members (workspace_id, user_id, role, created_at)
+SELECT '00000000-0000-0000-0000-000000000001', id, 'Member', now() FROM users
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE todos ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces (id) ON DELETE CASCADE;
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces (id) ON DELETE CASCADE;
+ALTER TABLE shares ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces (id) ON DELETE CASCADE;
+ALTER TABLE invitations ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces (id) ON DELETE CASCADE;
+ALTER TABLE api_tokens ADD COLUMN IF NOT EXISTS workspace_id UUID REFERENCES workspaces (id) ON DELETE CASCADE;
+
+UPDATE todos SET workspace_id = '00000000-0000-0000-0000-000000000001' WHERE workspace_id IS NULL;
+UPDATE projects SET workspace_id = '00000000-0000-0000-0000-000000000001' WHERE workspace_id IS NULL;
+UPDATE shares SET workspace_id = '00000000-0000-0000-0000-000000000001' WHERE workspace_id IS NULL;
+UPDATE invitations SET workspace_id = '00000000-0000-0000-0000-000000000001' WHERE workspace_id IS NULL;
+UPDATE api_tokens SET workspace_id = '00000000-0000-0000-0000-000000000001' WHERE workspace_id IS NULL;
+
+-- Rows inherit the workspace of the current transaction unless set explicitly.
+ALTER TABLE todos
+    ALTER COLUMN workspace_id SET DEFAULT NULLIF(current_setting('app.workspace_id', true), '')::uuid,
+    ALTER COLUMN workspace_id SET NOT NULL;
+ALTER TABLE projects
+    ALTER COLUMN workspace_id SET DEFAULT NULLIF(current_setting('app.workspace_id', true), '')::uuid,
+    ALTER COLUMN workspace_id SET NOT NULL;
+ALTER TABLE shares
+    ALTER COLUMN workspace_id SET DEFAULT NULLIF(current_setting('app.workspace_id', true), '')::uuid,
+    ALTER COLUMN workspace_id SET NOT NULL;
+ALTER TABLE invitations
+    ALTER COLUMN workspace_id SET DEFAULT NULLIF(current_setting('app.workspace_id', true), '')::uuid,
+    ALTER COLUMN workspace_id SET NOT NULL;
+ALTER TABLE api_tokens ALTER COLUMN workspace_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS todos_workspace_id_idx ON todos (workspace_id);
+CREATE INDEX IF NOT EXISTS projects_workspace_id_idx ON projects (workspace_id);
+
+-- Superusers bypass row-level security even when it is forced, so request
+-- transactions switch to this unprivileged role with `SET LOCAL ROLE`.
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = 'ai_todo_tenant') THEN
+        CREATE ROLE ai_todo_tenant NOLOGIN;
+    END IF;
+END
+$$;
+
+GRANT ai_todo_tenant TO CURRENT_USER;
+GRANT USAGE ON SCHEMA public TO ai_todo_tenant;
+GRANT SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA public TO ai_todo_tenant;
+GRANT USAGE, SELECT ON ALL SEQUENCES IN SCHEMA public TO ai_todo_tenant;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT SELECT, INSERT, UPDATE, DELETE ON TABLES TO ai_todo_tenant;
+ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT USAGE, SELECT ON SEQUENCES TO ai_todo_tenant;
+
+ALTER TABLE todos ENABLE ROW LEVEL SECURITY;
+ALTER TABLE todos FORCE ROW LEVEL SECURITY;
+ALTER TABLE projects ENABLE ROW LEVEL SECURITY;
+ALTER TABLE projects FORCE ROW LEVEL SECURITY;
+ALTER TABLE shares ENABLE ROW LEVEL SECURITY;
+ALTER TABLE shares FORCE ROW LEVEL SECURITY;
+ALTER TABLE invitations ENABLE ROW LEVEL SECURITY;
+ALTER TABLE invitations FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY workspace_isolation ON todos
+    USING (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid)
+    WITH CHECK (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid);
+CREATE POLICY workspace_isolation ON projects
+    USING (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid)
+    WITH CHECK (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid);
+CREATE POLICY workspace_isolation ON shares
+    USING (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid)
+    WITH CHECK (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid);
+CREATE POLICY workspace_isolation ON invitations
+    USING (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid)
+    WITH CHECK (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid);
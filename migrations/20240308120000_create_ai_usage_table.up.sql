@@ -0,0 +1,8 @@
+-- Daily AI-call counters backing the per-client quota on /audio/suggest.
+-- `client` is the rate-limit key: `user:<id>`, `token:<id>` or `admin`.
+CREATE TABLE IF NOT EXISTS ai_usage (
+    client TEXT NOT NULL,
+    day DATE NOT NULL,
+    calls INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (client, day)
+);
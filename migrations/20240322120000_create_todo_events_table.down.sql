@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS todo_events;
+DROP FUNCTION IF EXISTS todo_viewers(UUID);
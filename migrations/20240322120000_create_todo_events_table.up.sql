@@ -0,0 +1,41 @@
+-- Append-only log of todo changes. `seq` orders events within a workspace and
+-- is the SSE event ID clients resume from with `Last-Event-ID`.
+CREATE TABLE IF NOT EXISTS todo_events (
+    seq BIGSERIAL PRIMARY KEY,
+    workspace_id UUID NOT NULL DEFAULT NULLIF(current_setting('app.workspace_id', true), '')::uuid
+        REFERENCES workspaces (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('todo.created', 'todo.updated', 'todo.deleted')),
+    todo_id UUID NOT NULL,
+    project_id UUID,
+    status TEXT NOT NULL,
+    todo JSONB NOT NULL,
+    -- Users who could see the todo when the event happened. Recorded up front
+    -- because a deleted todo's shares are gone by the time the event is read.
+    visible_to UUID[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS todo_events_workspace_seq_idx ON todo_events (workspace_id, seq);
+
+-- Everyone who holds a role on the todo: its owner, direct shares, and the
+-- owner and members of its project.
+CREATE OR REPLACE FUNCTION todo_viewers(p_todo_id UUID) RETURNS UUID[] AS $$
+    SELECT COALESCE(array_agg(DISTINCT user_id), '{}')
+    FROM (
+        SELECT owner_id AS user_id FROM todos WHERE id = p_todo_id
+        UNION ALL
+        SELECT user_id FROM shares WHERE todo_id = p_todo_id
+        UNION ALL
+        SELECT p.owner_id FROM todos t JOIN projects p ON p.id = t.project_id WHERE t.id = p_todo_id
+        UNION ALL
+        SELECT s.user_id FROM todos t JOIN shares s ON s.project_id = t.project_id WHERE t.id = p_todo_id
+    ) viewers
+    WHERE user_id IS NOT NULL
+$$ LANGUAGE sql STABLE;
+
+ALTER TABLE todo_events ENABLE ROW LEVEL SECURITY;
+ALTER TABLE todo_events FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY workspace_isolation ON todo_events
+    USING (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid)
+    WITH CHECK (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid);
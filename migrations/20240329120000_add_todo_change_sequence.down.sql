@@ -0,0 +1,10 @@
+DROP TRIGGER IF EXISTS todos_tombstone ON todos;
+DROP TRIGGER IF EXISTS todos_change_seq ON todos;
+DROP FUNCTION IF EXISTS todos_write_tombstone();
+DROP FUNCTION IF EXISTS todos_bump_change_seq();
+
+DROP TABLE IF EXISTS todo_tombstones;
+
+DROP INDEX IF EXISTS todos_workspace_change_seq_idx;
+ALTER TABLE todos DROP COLUMN IF EXISTS change_seq;
+DROP SEQUENCE IF EXISTS todo_change_seq;
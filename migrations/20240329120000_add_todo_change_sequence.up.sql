@@ -0,0 +1,66 @@
+-- Delta sync: every write to a todo gives it a new `change_seq`, and deleting
+-- one leaves a tombstone carrying the sequence number of the delete. Clients
+-- pull everything with a `change_seq` above the last one they saw.
+CREATE SEQUENCE IF NOT EXISTS todo_change_seq;
+
+ALTER TABLE todos ADD COLUMN IF NOT EXISTS change_seq BIGINT NOT NULL DEFAULT nextval('todo_change_seq');
+
+CREATE INDEX IF NOT EXISTS todos_workspace_change_seq_idx ON todos (workspace_id, change_seq);
+
+CREATE TABLE IF NOT EXISTS todo_tombstones (
+    todo_id UUID PRIMARY KEY,
+    workspace_id UUID NOT NULL REFERENCES workspaces (id) ON DELETE CASCADE,
+    project_id UUID,
+    change_seq BIGINT NOT NULL,
+    -- Users who could see the todo when it was deleted, as for todo_events.
+    visible_to UUID[] NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS todo_tombstones_workspace_change_seq_idx ON todo_tombstones (workspace_id, change_seq);
+
+-- Sequence numbers are handed out when rows are written, not when they
+-- commit. Taking the same per-workspace lock as the event log makes changes
+-- become visible in `change_seq` order, so a cursor never skips past a write
+-- that commits late.
+CREATE OR REPLACE FUNCTION todos_bump_change_seq() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtextextended(NEW.workspace_id::text, 0));
+    NEW.change_seq := nextval('todo_change_seq');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Runs as the table owner so that deletes cascading from users or projects
+-- outside a tenant transaction still leave tombstones. Deleting a workspace
+-- deletes its tombstones too, so none are written for it.
+CREATE OR REPLACE FUNCTION todos_write_tombstone() RETURNS trigger AS $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM workspaces WHERE id = OLD.workspace_id) THEN
+        RETURN OLD;
+    END IF;
+
+    PERFORM pg_advisory_xact_lock(hashtextextended(OLD.workspace_id::text, 0));
+    INSERT INTO todo_tombstones (todo_id, workspace_id, project_id, change_seq, visible_to, deleted_at)
+    VALUES (OLD.id, OLD.workspace_id, OLD.project_id, nextval('todo_change_seq'), todo_viewers(OLD.id), now())
+    ON CONFLICT (todo_id) DO NOTHING;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER SET search_path = public;
+
+DROP TRIGGER IF EXISTS todos_change_seq ON todos;
+CREATE TRIGGER todos_change_seq BEFORE INSERT OR UPDATE ON todos
+    FOR EACH ROW EXECUTE FUNCTION todos_bump_change_seq();
+
+DROP TRIGGER IF EXISTS todos_tombstone ON todos;
+CREATE TRIGGER todos_tombstone BEFORE DELETE ON todos
+    FOR EACH ROW EXECUTE FUNCTION todos_write_tombstone();
+
+-- Only ENABLE: the tombstone trigger writes as the table owner, which
+-- row-level security does not apply to. Tenant transactions are still
+-- confined to their workspace.
+ALTER TABLE todo_tombstones ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY workspace_isolation ON todo_tombstones
+    USING (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid)
+    WITH CHECK (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS todo_ops;
+DROP TABLE IF EXISTS todo_crdt;
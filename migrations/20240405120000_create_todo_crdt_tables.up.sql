@@ -0,0 +1,39 @@
+-- Concurrent edits to a todo are merged as CRDT operations. `todo_crdt`
+-- holds the merge state (see `CrdtState`); the todo row keeps the merged
+-- values. Todos without a state row have never been edited this way.
+CREATE TABLE IF NOT EXISTS todo_crdt (
+    todo_id UUID PRIMARY KEY REFERENCES todos (id) ON DELETE CASCADE,
+    workspace_id UUID NOT NULL DEFAULT NULLIF(current_setting('app.workspace_id', true), '')::uuid
+        REFERENCES workspaces (id) ON DELETE CASCADE,
+    state JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Every operation applied to a todo, from clients and from the REST API.
+-- Devices replay the operations they have not seen, in `seq` order.
+CREATE TABLE IF NOT EXISTS todo_ops (
+    seq BIGSERIAL PRIMARY KEY,
+    workspace_id UUID NOT NULL DEFAULT NULLIF(current_setting('app.workspace_id', true), '')::uuid
+        REFERENCES workspaces (id) ON DELETE CASCADE,
+    todo_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+    -- Unique per todo, so replaying an operation is a no-op.
+    hlc TEXT NOT NULL,
+    op JSONB NOT NULL,
+    created_by UUID REFERENCES users (id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (todo_id, hlc)
+);
+
+CREATE INDEX IF NOT EXISTS todo_ops_todo_seq_idx ON todo_ops (todo_id, seq);
+
+ALTER TABLE todo_crdt ENABLE ROW LEVEL SECURITY;
+ALTER TABLE todo_crdt FORCE ROW LEVEL SECURITY;
+ALTER TABLE todo_ops ENABLE ROW LEVEL SECURITY;
+ALTER TABLE todo_ops FORCE ROW LEVEL SECURITY;
+
+CREATE POLICY workspace_isolation ON todo_crdt
+    USING (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid)
+    WITH CHECK (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid);
+CREATE POLICY workspace_isolation ON todo_ops
+    USING (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid)
+    WITH CHECK (workspace_id = NULLIF(current_setting('app.workspace_id', true), '')::uuid);
@@ -0,0 +1,6 @@
+DROP POLICY IF EXISTS metrics_read ON todos;
+
+-- The role is shared by every database in the cluster, so it stays; only
+-- what this database granted it is taken back.
+REVOKE SELECT (status) ON todos FROM ai_todo_metrics;
+REVOKE USAGE ON SCHEMA public FROM ai_todo_metrics;
@@ -0,0 +1,16 @@
+-- `/metrics` reports todo counts across every workspace. Row-level security
+-- confines the tenant role to one workspace, so the counts are read as this
+-- role instead: it can see every row, but only the status column.
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT FROM pg_roles WHERE rolname = 'ai_todo_metrics') THEN
+        CREATE ROLE ai_todo_metrics NOLOGIN;
+    END IF;
+END
+$$;
+
+GRANT ai_todo_metrics TO CURRENT_USER;
+GRANT USAGE ON SCHEMA public TO ai_todo_metrics;
+GRANT SELECT (status) ON todos TO ai_todo_metrics;
+
+CREATE POLICY metrics_read ON todos FOR SELECT TO ai_todo_metrics USING (true);
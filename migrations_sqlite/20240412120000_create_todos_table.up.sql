@@ -0,0 +1,27 @@
+-- Todos for SQLite storage, which serves a single user: there are no
+-- projects, shares or workspaces tables, so their IDs are plain columns.
+-- IDs are hyphenated UUIDs and timestamps RFC 3339 text.
+CREATE TABLE IF NOT EXISTS todos (
+    id TEXT PRIMARY KEY NOT NULL,
+    workspace_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    status TEXT NOT NULL,
+    priority TEXT NOT NULL,
+    source TEXT NOT NULL,
+    owner_id TEXT,
+    project_id TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS todos_workspace_created_at ON todos (workspace_id, created_at);
+
+-- Daily AI-call counters backing the per-client quota on /audio/suggest, as
+-- in PostgreSQL.
+CREATE TABLE IF NOT EXISTS ai_usage (
+    client TEXT NOT NULL,
+    day TEXT NOT NULL,
+    calls INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (client, day)
+);
@@ -1,35 +1,292 @@
 use axum::{
-    Router,
-    http::Method,
+    Extension, Router,
+    http::{Method, header},
+    middleware,
     routing::{delete, get, patch, post},
 };
 use tower_http::{
     cors::{Any, CorsLayer},
-    trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
+    trace::TraceLayer,
 };
-use tracing::Level;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
-use crate::{routes, state::AppState};
+use crate::{
+    auth, graphql, metrics, models::token::Scope, openapi::ApiDoc, rate_limit, request_id, routes,
+    state::AppState, telemetry, versioning,
+};
 
 pub fn create_app(state: AppState) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
-        .allow_headers(Any);
+        .allow_headers(Any)
+        .expose_headers([
+            versioning::DEPRECATION,
+            versioning::SUNSET,
+            header::LINK,
+            request_id::X_REQUEST_ID,
+        ]);
+
+    let v1 = match state.sqlite {
+        Some(_) => v1_sqlite(state.clone()),
+        None => v1(state.clone()),
+    };
+    let schema = graphql::build_schema(state.clone());
+
+    // Static documentation, exempt from rate limiting.
+    let docs = SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi());
+
+    // Probes and metrics, likewise unversioned and exempt.
+    let health = Router::new()
+        .route("/healthz", get(routes::health::healthz))
+        .route("/readyz", get(routes::health::readyz))
+        .route("/status", get(routes::health::status))
+        .route("/metrics", get(routes::metrics::export));
 
     Router::new()
-        .route("/todos", post(routes::todos::create_todo))
+        .nest("/v1", v1.clone())
+        // Unprefixed routes from before versioning, kept until their sunset.
+        .merge(v1.layer(middleware::from_fn(versioning::deprecated_alias)))
+        .merge(docs)
+        .merge(health)
+        .layer(Extension(schema))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            metrics::track,
+        ))
+        // Inside the trace span and request ID, so that preflight requests
+        // it answers itself are logged with an ID and echo it.
+        .layer(cors)
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(telemetry::http_span)
+                .on_response(telemetry::on_http_response),
+        )
+        .layer(middleware::from_fn(request_id::assign))
+        .with_state(state)
+}
+
+/// Version 1 of the API. A later version gets a function like this one,
+/// mounted under its own prefix in [`create_app`]. Its handlers live in their
+/// own modules and convert their request and response shapes to and from the
+/// shared models, reusing storage functions such as
+/// [`crate::repositories::postgres::insert_todo`] and
+/// [`crate::repositories::postgres::apply_update`].
+fn v1(state: AppState) -> Router<AppState> {
+    let todos_read = Router::new()
         .route("/todos", get(routes::todos::list_todos))
+        .route("/todos/events", get(routes::events::stream_todo_events))
         .route("/todos/:id", get(routes::todos::get_todo))
+        .route("/todos/:id/members", get(routes::shares::list_todo_members))
+        .route("/projects", get(routes::projects::list_projects))
+        .route("/projects/:id", get(routes::projects::get_project))
+        .route(
+            "/projects/:id/members",
+            get(routes::shares::list_project_members),
+        )
+        .route("/invitations", get(routes::shares::list_invitations))
+        .route("/todos/:id/ops", get(routes::sync::list_ops))
+        .route("/sync", get(routes::sync::pull))
+        .route("/workspaces", get(routes::workspaces::list_workspaces))
+        .route(
+            "/workspaces/:id/members",
+            get(routes::workspaces::list_members),
+        )
+        // Mutations additionally require todos:write, checked per field.
+        .route("/graphql", post(routes::graphql::execute))
+        .route_layer(middleware::from_fn(|req, next| {
+            auth::require_scope(Scope::TodosRead, req, next)
+        }));
+
+    let todos_write = Router::new()
+        .route("/todos", post(routes::todos::create_todo))
         .route("/todos/:id", patch(routes::todos::update_todo))
         .route("/todos/:id", delete(routes::todos::delete_todo))
-        .route("/audio/suggest", post(routes::audio::suggest_tasks))
         .route("/audio/confirm", post(routes::audio::confirm_tasks))
-        .layer(
-            TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
-                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        .route(
+            "/todos/:id/invitations",
+            post(routes::shares::invite_to_todo),
         )
-        .layer(cors)
-        .with_state(state)
+        .route(
+            "/todos/:id/members/:user_id",
+            patch(routes::shares::update_todo_member).delete(routes::shares::remove_todo_member),
+        )
+        .route("/projects", post(routes::projects::create_project))
+        .route(
+            "/projects/:id",
+            patch(routes::projects::update_project).delete(routes::projects::delete_project),
+        )
+        .route(
+            "/projects/:id/invitations",
+            post(routes::shares::invite_to_project),
+        )
+        .route(
+            "/projects/:id/members/:user_id",
+            patch(routes::shares::update_project_member)
+                .delete(routes::shares::remove_project_member),
+        )
+        .route(
+            "/invitations/:id/accept",
+            post(routes::shares::accept_invitation),
+        )
+        .route(
+            "/invitations/:id/decline",
+            post(routes::shares::decline_invitation),
+        )
+        .route("/todos/:id/ops", post(routes::sync::push_ops))
+        .route("/sync", post(routes::sync::push))
+        .route("/workspaces", post(routes::workspaces::create_workspace))
+        .route(
+            "/workspaces/:id/members",
+            post(routes::workspaces::add_member),
+        )
+        .route(
+            "/workspaces/:id/members/:user_id",
+            delete(routes::workspaces::remove_member),
+        )
+        .route_layer(middleware::from_fn(|req, next| {
+            auth::require_scope(Scope::TodosWrite, req, next)
+        }));
+
+    let audio_suggest = Router::new()
+        .route("/audio/suggest", post(routes::audio::suggest_tasks))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::limit_ai,
+        ))
+        .route_layer(middleware::from_fn(|req, next| {
+            auth::require_scope(Scope::AudioSuggest, req, next)
+        }));
+
+    let tokens = Router::new()
+        .route("/tokens", post(routes::tokens::create_token))
+        .route("/tokens", get(routes::tokens::list_tokens))
+        .route("/tokens/:id", delete(routes::tokens::revoke_token))
+        .route_layer(middleware::from_fn(|req, next| {
+            auth::require_scope(Scope::TokensManage, req, next)
+        }));
+
+    let webhooks = Router::new()
+        .route(
+            "/webhooks",
+            get(routes::webhooks::list_webhooks).post(routes::webhooks::create_webhook),
+        )
+        .route(
+            "/webhooks/:id",
+            get(routes::webhooks::get_webhook)
+                .patch(routes::webhooks::update_webhook)
+                .delete(routes::webhooks::delete_webhook),
+        )
+        .route(
+            "/webhooks/:id/deliveries",
+            get(routes::webhooks::list_deliveries),
+        )
+        .route(
+            "/webhooks/:id/deliveries/:delivery_id/redeliver",
+            post(routes::webhooks::redeliver),
+        )
+        .route_layer(middleware::from_fn(|req, next| {
+            auth::require_scope(Scope::WebhooksManage, req, next)
+        }));
+
+    let admin = Router::new()
+        .route(
+            "/admin/users/:id/totp/reset",
+            post(routes::totp::admin_reset),
+        )
+        .route_layer(middleware::from_fn(|req, next| {
+            auth::require_scope(Scope::UsersAdmin, req, next)
+        }));
+
+    let session = Router::new()
+        .route("/auth/me", get(routes::auth::me))
+        .route("/auth/logout", post(routes::auth::logout))
+        .route("/auth/totp/enroll", post(routes::totp::enroll))
+        .route("/auth/totp/activate", post(routes::totp::activate))
+        .route(
+            "/auth/totp/recovery-codes",
+            post(routes::totp::regenerate_recovery_codes),
+        )
+        .route("/auth/totp/disable", post(routes::totp::disable));
+
+    // AI routes have their own, stricter limit; everything else shares one.
+    let api = Router::new()
+        .merge(todos_read)
+        .merge(todos_write)
+        .merge(tokens)
+        .merge(webhooks)
+        .merge(admin)
+        .merge(session)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::limit_api,
+        ));
+
+    let authenticated =
+        Router::new()
+            .merge(api)
+            .merge(audio_suggest)
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::authenticate,
+            ));
+
+    let public = Router::new()
+        .route("/auth/oidc/login", get(routes::auth::oidc_login))
+        .route("/auth/oidc/callback", get(routes::auth::oidc_callback))
+        .route("/auth/totp/verify", post(routes::totp::verify))
+        .route("/ws", get(routes::realtime::connect))
+        .route("/graphql", get(routes::graphql::playground))
+        .route("/graphql/ws", get(routes::graphql::subscribe))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::limit_api,
+        ));
+
+    Router::new().merge(authenticated).merge(public)
+}
+
+/// The part of [`v1`] that SQLite storage supports: todos and audio. The
+/// handlers and guards are the same; everything that needs PostgreSQL
+/// answers `404`.
+fn v1_sqlite(state: AppState) -> Router<AppState> {
+    let todos_read = Router::new()
+        .route("/todos", get(routes::todos::list_todos))
+        .route("/todos/:id", get(routes::todos::get_todo))
+        .route_layer(middleware::from_fn(|req, next| {
+            auth::require_scope(Scope::TodosRead, req, next)
+        }));
+
+    let todos_write = Router::new()
+        .route("/todos", post(routes::todos::create_todo))
+        .route("/todos/:id", patch(routes::todos::update_todo))
+        .route("/todos/:id", delete(routes::todos::delete_todo))
+        .route("/audio/confirm", post(routes::audio::confirm_tasks))
+        .route_layer(middleware::from_fn(|req, next| {
+            auth::require_scope(Scope::TodosWrite, req, next)
+        }));
+
+    let audio_suggest = Router::new()
+        .route("/audio/suggest", post(routes::audio::suggest_tasks))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::limit_ai,
+        ))
+        .route_layer(middleware::from_fn(|req, next| {
+            auth::require_scope(Scope::AudioSuggest, req, next)
+        }));
+
+    let api = Router::new()
+        .merge(todos_read)
+        .merge(todos_write)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::limit_api,
+        ));
+
+    Router::new()
+        .merge(api)
+        .merge(audio_suggest)
+        .layer(middleware::from_fn_with_state(state, auth::authenticate))
 }
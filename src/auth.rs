@@ -0,0 +1,265 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, header},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        token::{Scope, Scopes},
+        workspace::DEFAULT_WORKSPACE_ID,
+    },
+    state::AppState,
+};
+
+const TOKEN_PREFIX: &str = "aitodo_";
+
+/// Header for choosing the workspace a request operates in.
+const WORKSPACE_HEADER: &str = "x-workspace-id";
+
+/// Lifetime of the session tokens issued after an interactive login.
+const SESSION_TTL: Duration = Duration::hours(12);
+
+/// The authenticated caller, inserted into request extensions by
+/// [`authenticate`] so handlers can extract it with `Extension<AuthContext>`.
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    /// `None` when the request used the bootstrap `ADMIN_TOKEN`.
+    pub token_id: Option<Uuid>,
+    /// `None` for the bootstrap token and for tokens created with it.
+    pub user_id: Option<Uuid>,
+    /// The workspace this request operates in; see [`AppState::tenant`].
+    pub workspace_id: Uuid,
+    pub scopes: Scopes,
+}
+
+impl AuthContext {
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(scope)
+    }
+}
+
+/// Generates a new random token secret, e.g. `aitodo_3f9c...`.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{TOKEN_PREFIX}{}", hex::encode(bytes))
+}
+
+/// Tokens are long random strings, so a fast hash is sufficient here.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Issues a session token for `user_id` after a successful interactive login.
+/// Sessions are stored alongside personal access tokens with `kind = 'session'`
+/// and start in the user's oldest workspace.
+pub async fn create_session(
+    pool: &sqlx::PgPool,
+    user_id: Uuid,
+) -> Result<(String, DateTime<Utc>), AppError> {
+    let token = generate_token();
+    let now = Utc::now();
+    let expires_at = now + SESSION_TTL;
+
+    let workspace_id = sqlx::query_scalar!(
+        "SELECT workspace_id FROM workspace_members WHERE user_id = $1 ORDER BY created_at LIMIT 1",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to find workspace for session: {:?}", e);
+        AppError::Internal("failed to create session".into())
+    })?
+    .ok_or_else(|| AppError::Internal("user does not belong to any workspace".into()))?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO api_tokens (id, name, token_hash, token_prefix, scopes, created_at, expires_at, user_id, kind, workspace_id)
+        VALUES ($1, 'session', $2, $3, $4, $5, $6, $7, 'session', $8)
+        "#,
+        Uuid::new_v4(),
+        hash_token(&token),
+        token.chars().take(12).collect::<String>(),
+        &Scopes::session().to_strings(),
+        now,
+        expires_at,
+        user_id,
+        workspace_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create session: {:?}", e);
+        AppError::Internal("failed to create session".into())
+    })?;
+
+    Ok((token, expires_at))
+}
+
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+async fn resolve_token(state: &AppState, token: &str) -> Result<Option<AuthContext>, AppError> {
+    let hash = hash_token(token);
+
+    if state.admin_token_hash.as_deref() == Some(hash.as_str()) {
+        return Ok(Some(AuthContext {
+            token_id: None,
+            user_id: None,
+            workspace_id: DEFAULT_WORKSPACE_ID,
+            scopes: Scopes::all(),
+        }));
+    }
+
+    // Tokens are stored in PostgreSQL; with SQLite only the bootstrap token
+    // exists.
+    if state.sqlite.is_some() {
+        return Ok(None);
+    }
+
+    // Look the token up and record its use in a single round trip.
+    let row = sqlx::query!(
+        r#"
+        UPDATE api_tokens
+        SET last_used_at = $2
+        WHERE token_hash = $1
+          AND revoked_at IS NULL
+          AND (expires_at IS NULL OR expires_at > $2)
+        RETURNING id, user_id, workspace_id, scopes
+        "#,
+        hash,
+        Utc::now()
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to resolve api token: {:?}", e);
+        AppError::Internal("failed to authenticate".into())
+    })?;
+
+    Ok(row.map(|r| AuthContext {
+        token_id: Some(r.id),
+        user_id: r.user_id,
+        workspace_id: r.workspace_id,
+        scopes: r.scopes.into(),
+    }))
+}
+
+/// Rejects requests without a valid bearer token and exposes the caller as
+/// an [`AuthContext`] extension.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let token = bearer_token(req.headers()).ok_or(AppError::Unauthorized)?;
+    let workspace_id = requested_workspace(req.headers())?;
+    let ctx = authenticate_token(&state, token, workspace_id).await?;
+
+    req.extensions_mut().insert(ctx);
+    Ok(next.run(req).await)
+}
+
+/// Resolves `token` and, if given, moves the caller into `workspace_id`. For
+/// connections that cannot go through [`authenticate`], such as WebSockets.
+pub async fn authenticate_token(
+    state: &AppState,
+    token: &str,
+    workspace_id: Option<Uuid>,
+) -> Result<AuthContext, AppError> {
+    let mut ctx = resolve_token(state, token)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    if let Some(workspace_id) = workspace_id {
+        switch_workspace(state, &mut ctx, workspace_id).await?;
+    }
+
+    Ok(ctx)
+}
+
+pub fn requested_workspace(headers: &HeaderMap) -> Result<Option<Uuid>, AppError> {
+    let Some(value) = headers.get(WORKSPACE_HEADER) else {
+        return Ok(None);
+    };
+
+    value
+        .to_str()
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .map(Some)
+        .ok_or_else(|| AppError::invalid_field(WORKSPACE_HEADER, "must be a workspace UUID"))
+}
+
+/// Moves the request into another workspace. Users may switch to any
+/// workspace they belong to; the bootstrap token may switch anywhere; tokens
+/// not tied to a user are bound to the workspace they were created in.
+async fn switch_workspace(
+    state: &AppState,
+    ctx: &mut AuthContext,
+    workspace_id: Uuid,
+) -> Result<(), AppError> {
+    if workspace_id == ctx.workspace_id {
+        return Ok(());
+    }
+    // SQLite storage has only the default workspace.
+    if state.sqlite.is_some() {
+        return Err(AppError::Forbidden);
+    }
+
+    let allowed = match (ctx.token_id, ctx.user_id) {
+        (None, _) => sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM workspaces WHERE id = $1) AS "exists!""#,
+            workspace_id
+        )
+        .fetch_one(&state.pool)
+        .await,
+        (Some(_), Some(user_id)) => sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM workspace_members WHERE workspace_id = $1 AND user_id = $2) AS "exists!""#,
+            workspace_id,
+            user_id
+        )
+        .fetch_one(&state.pool)
+        .await,
+        (Some(_), None) => Ok(false),
+    }
+    .map_err(|e| {
+        tracing::error!("Failed to check workspace membership: {:?}", e);
+        AppError::Internal("failed to authenticate".into())
+    })?;
+
+    if !allowed {
+        return Err(AppError::Forbidden);
+    }
+
+    ctx.workspace_id = workspace_id;
+    Ok(())
+}
+
+/// Route-level guard; must run after [`authenticate`].
+pub async fn require_scope(scope: Scope, req: Request, next: Next) -> Result<Response, AppError> {
+    let ctx = req
+        .extensions()
+        .get::<AuthContext>()
+        .ok_or(AppError::Unauthorized)?;
+
+    if !ctx.has_scope(scope) {
+        return Err(AppError::InsufficientScope(scope));
+    }
+
+    Ok(next.run(req).await)
+}
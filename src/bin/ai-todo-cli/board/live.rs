@@ -0,0 +1,73 @@
+//! Live updates from the server's todo event stream.
+
+use std::time::Duration;
+
+use ai_todo::models::event::TodoEvent;
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+use crate::{CliError, client::ApiClient};
+
+const RETRY_DELAY: Duration = Duration::from_secs(3);
+
+pub enum Message {
+    /// The stream is open. Changes from before it opened are missing, so the
+    /// board reloads.
+    Connected,
+    Event(TodoEvent),
+    Disconnected(String),
+}
+
+/// Follows the event stream until the board goes away, reconnecting
+/// whenever the connection drops.
+pub async fn listen(client: ApiClient, project_id: Option<Uuid>, tx: UnboundedSender<Message>) {
+    loop {
+        let reason = match follow(&client, project_id, &tx).await {
+            Ok(()) => "stream closed".to_string(),
+            Err(e) => e.to_string(),
+        };
+        if tx.send(Message::Disconnected(reason)).is_err() {
+            return;
+        }
+        tokio::time::sleep(RETRY_DELAY).await;
+    }
+}
+
+async fn follow(
+    client: &ApiClient,
+    project_id: Option<Uuid>,
+    tx: &UnboundedSender<Message>,
+) -> Result<(), CliError> {
+    let mut response = client.todo_events(project_id).await?;
+    if tx.send(Message::Connected).is_err() {
+        return Ok(());
+    }
+
+    let mut buffer = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = buffer.drain(..end + 2).collect();
+            if let Some(event) = parse(&String::from_utf8_lossy(&block))
+                && tx.send(Message::Event(event)).is_err()
+            {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes one SSE block. Keep-alive comments and anything that is not a
+/// todo event yield `None`.
+fn parse(block: &str) -> Option<TodoEvent> {
+    let data: Vec<&str> = block
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect();
+    if data.is_empty() {
+        return None;
+    }
+    serde_json::from_str(&data.join("\n")).ok()
+}
@@ -0,0 +1,284 @@
+//! Interactive kanban board, one column per status, kept in sync with the
+//! server through its event stream.
+
+mod live;
+mod state;
+mod ui;
+
+use ai_todo::{
+    models::todo::{Todo, TodoStatus},
+    routes::todos::{CreateTodo, UpdateTodo},
+};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use futures::StreamExt;
+use ratatui::DefaultTerminal;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{CliError, client::ApiClient};
+use live::Message;
+use state::{Board, COLUMNS, Edit, Field, Input, Mode, Target};
+
+enum Flow {
+    Continue,
+    Quit,
+}
+
+/// Runs the board until the user quits. `project_id` limits it to one
+/// project; new cards are added there.
+pub async fn run(client: ApiClient, project_id: Option<Uuid>) -> Result<(), CliError> {
+    // Fail before taking over the terminal if the server is unreachable.
+    let mut board = Board::new(client.list_todos(project_id).await?);
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let listener = tokio::spawn(live::listen(client.clone(), project_id, tx));
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &client, project_id, &mut board, &mut rx).await;
+    ratatui::restore();
+    listener.abort();
+    result
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    client: &ApiClient,
+    project_id: Option<Uuid>,
+    board: &mut Board,
+    rx: &mut mpsc::UnboundedReceiver<Message>,
+) -> Result<(), CliError> {
+    let mut events = EventStream::new();
+    loop {
+        terminal.draw(|frame| ui::draw(frame, board))?;
+
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    if let Flow::Quit = handle_key(client, project_id, board, key).await {
+                        return Ok(());
+                    }
+                }
+                // Resizes only need the redraw at the top of the loop.
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => return Ok(()),
+            },
+            Some(message) = rx.recv() => match message {
+                Message::Connected => {
+                    board.live = true;
+                    reload(client, project_id, board).await;
+                }
+                Message::Event(event) => board.apply(event),
+                Message::Disconnected(reason) => {
+                    if board.live {
+                        board.message = Some(format!("Lost live updates: {reason}"));
+                    }
+                    board.live = false;
+                }
+            },
+        }
+    }
+}
+
+async fn handle_key(
+    client: &ApiClient,
+    project_id: Option<Uuid>,
+    board: &mut Board,
+    key: KeyEvent,
+) -> Flow {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Flow::Quit;
+    }
+
+    match std::mem::replace(&mut board.mode, Mode::Normal) {
+        Mode::Normal => return normal_key(client, project_id, board, key).await,
+        Mode::Edit(mut edit) => match key.code {
+            KeyCode::Enter => save(client, project_id, board, edit).await,
+            KeyCode::Esc => {}
+            code => {
+                match code {
+                    KeyCode::Char(c) => edit.input.insert(c),
+                    KeyCode::Backspace => edit.input.backspace(),
+                    KeyCode::Delete => edit.input.delete(),
+                    KeyCode::Left => edit.input.left(),
+                    KeyCode::Right => edit.input.right(),
+                    KeyCode::Home => edit.input.home(),
+                    KeyCode::End => edit.input.end(),
+                    _ => {}
+                }
+                board.mode = Mode::Edit(edit);
+            }
+        },
+        Mode::ConfirmDelete(id) => {
+            if let KeyCode::Char('y' | 'Y') = key.code {
+                match client.delete_todo(id).await {
+                    Ok(()) => board.remove(id),
+                    Err(e) => board.message = Some(e.to_string()),
+                }
+            }
+        }
+    }
+    Flow::Continue
+}
+
+async fn normal_key(
+    client: &ApiClient,
+    project_id: Option<Uuid>,
+    board: &mut Board,
+    key: KeyEvent,
+) -> Flow {
+    board.message = None;
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return Flow::Quit,
+        KeyCode::Char('H' | '<') => move_card(client, board, -1).await,
+        KeyCode::Char('L' | '>') => move_card(client, board, 1).await,
+        KeyCode::Left if shift => move_card(client, board, -1).await,
+        KeyCode::Right if shift => move_card(client, board, 1).await,
+        KeyCode::Char('h') | KeyCode::Left => board.move_focus(-1),
+        KeyCode::Char('l') | KeyCode::Right => board.move_focus(1),
+        KeyCode::Char('k') | KeyCode::Up => board.move_selection(-1),
+        KeyCode::Char('j') | KeyCode::Down => board.move_selection(1),
+        KeyCode::Char('g') | KeyCode::Home => board.select_edge(false),
+        KeyCode::Char('G') | KeyCode::End => board.select_edge(true),
+        KeyCode::Char('+' | '=') => change_priority(client, board, true).await,
+        KeyCode::Char('-') => change_priority(client, board, false).await,
+        KeyCode::Char('e') | KeyCode::Enter => start_edit(board, Field::Title),
+        KeyCode::Char('E') => start_edit(board, Field::Description),
+        KeyCode::Char('a' | 'n') => {
+            board.mode = Mode::Edit(Edit {
+                target: Target::New(COLUMNS[board.column].clone()),
+                field: Field::Title,
+                input: Input::default(),
+            });
+        }
+        KeyCode::Char('x' | 'd') | KeyCode::Delete => {
+            if let Some(todo) = board.selected() {
+                board.mode = Mode::ConfirmDelete(todo.id);
+            }
+        }
+        KeyCode::Char('r') => reload(client, project_id, board).await,
+        _ => {}
+    }
+    Flow::Continue
+}
+
+fn start_edit(board: &mut Board, field: Field) {
+    if let Some(todo) = board.selected() {
+        let text = match field {
+            Field::Title => todo.title.as_str(),
+            Field::Description => todo.description.as_deref().unwrap_or(""),
+        };
+        board.mode = Mode::Edit(Edit {
+            target: Target::Existing(todo.id),
+            field,
+            input: Input::new(text),
+        });
+    }
+}
+
+async fn save(client: &ApiClient, project_id: Option<Uuid>, board: &mut Board, edit: Edit) {
+    let text = edit.input.text().trim().to_string();
+    let result = match edit.target {
+        Target::Existing(id) => {
+            let update = match edit.field {
+                Field::Title => UpdateTodo {
+                    title: Some(text),
+                    ..Default::default()
+                },
+                Field::Description => UpdateTodo {
+                    description: Some(text),
+                    ..Default::default()
+                },
+            };
+            client.update_todo(id, &update).await
+        }
+        Target::New(_) if text.is_empty() => return,
+        Target::New(status) => create(client, project_id, text, status).await,
+    };
+    match result {
+        Ok(todo) => {
+            let id = todo.id;
+            board.upsert(todo);
+            board.select(id);
+        }
+        Err(e) => board.message = Some(e.to_string()),
+    }
+}
+
+/// New todos always start in the first column, so cards added elsewhere
+/// are moved there right after.
+async fn create(
+    client: &ApiClient,
+    project_id: Option<Uuid>,
+    title: String,
+    status: TodoStatus,
+) -> Result<Todo, CliError> {
+    let todo = client
+        .create_todo(&CreateTodo {
+            title,
+            description: None,
+            priority: None,
+            project_id,
+        })
+        .await?;
+    if todo.status == status {
+        return Ok(todo);
+    }
+    let update = UpdateTodo {
+        status: Some(status),
+        ..Default::default()
+    };
+    client.update_todo(todo.id, &update).await
+}
+
+async fn move_card(client: &ApiClient, board: &mut Board, delta: isize) {
+    let Some(todo) = board.selected() else {
+        return;
+    };
+    let Some(status) = board
+        .column
+        .checked_add_signed(delta)
+        .and_then(|column| COLUMNS.get(column))
+    else {
+        return;
+    };
+    let update = UpdateTodo {
+        status: Some(status.clone()),
+        ..Default::default()
+    };
+    apply_update(client, board, todo.id, update).await;
+}
+
+async fn change_priority(client: &ApiClient, board: &mut Board, raise: bool) {
+    let Some(todo) = board.selected() else {
+        return;
+    };
+    let Some(priority) = state::step_priority(&todo.priority, raise) else {
+        return;
+    };
+    let update = UpdateTodo {
+        priority: Some(priority),
+        ..Default::default()
+    };
+    apply_update(client, board, todo.id, update).await;
+}
+
+/// Saves a change and keeps the changed card selected, wherever it lands.
+async fn apply_update(client: &ApiClient, board: &mut Board, id: Uuid, update: UpdateTodo) {
+    match client.update_todo(id, &update).await {
+        Ok(todo) => {
+            board.upsert(todo);
+            board.select(id);
+        }
+        Err(e) => board.message = Some(e.to_string()),
+    }
+}
+
+async fn reload(client: &ApiClient, project_id: Option<Uuid>, board: &mut Board) {
+    match client.list_todos(project_id).await {
+        Ok(todos) => board.replace_all(todos),
+        Err(e) => board.message = Some(e.to_string()),
+    }
+}
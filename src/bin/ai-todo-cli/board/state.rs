@@ -0,0 +1,245 @@
+//! What the board shows: the todos, the selection and any edit in progress.
+
+use ai_todo::models::{
+    event::{TodoEvent, TodoEventKind},
+    todo::{Priority, Todo, TodoStatus},
+};
+use uuid::Uuid;
+
+/// Board columns, left to right.
+pub const COLUMNS: [TodoStatus; 3] = [TodoStatus::Todo, TodoStatus::Doing, TodoStatus::Done];
+
+pub enum Mode {
+    Normal,
+    Edit(Edit),
+    ConfirmDelete(Uuid),
+}
+
+pub struct Edit {
+    pub target: Target,
+    pub field: Field,
+    pub input: Input,
+}
+
+pub enum Target {
+    Existing(Uuid),
+    /// A card being added to the given column.
+    New(TodoStatus),
+}
+
+#[derive(Clone, Copy)]
+pub enum Field {
+    Title,
+    Description,
+}
+
+/// A single-line text input. `cursor` counts characters, not bytes.
+#[derive(Default)]
+pub struct Input {
+    text: String,
+    cursor: usize,
+}
+
+impl Input {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor: text.chars().count(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let at = self.byte_offset(self.cursor);
+        self.text.insert(at, c);
+        self.cursor += 1;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.text.remove(self.byte_offset(self.cursor));
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.chars().count() {
+            self.text.remove(self.byte_offset(self.cursor));
+        }
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.text.chars().count());
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.chars().count();
+    }
+
+    fn byte_offset(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+}
+
+pub struct Board {
+    todos: Vec<Todo>,
+    /// Index into [`COLUMNS`] of the focused column.
+    pub column: usize,
+    /// Selected card in each column.
+    rows: [usize; 3],
+    pub mode: Mode,
+    /// Result of the last action, shown in the footer.
+    pub message: Option<String>,
+    /// Whether the event stream is connected.
+    pub live: bool,
+}
+
+impl Board {
+    pub fn new(todos: Vec<Todo>) -> Self {
+        Self {
+            todos,
+            column: 0,
+            rows: [0; 3],
+            mode: Mode::Normal,
+            message: None,
+            live: false,
+        }
+    }
+
+    /// Replaces every todo, keeping the selected card selected if it still
+    /// exists.
+    pub fn replace_all(&mut self, todos: Vec<Todo>) {
+        let selected = self.selected().map(|t| t.id);
+        self.todos = todos;
+        if let Some(id) = selected {
+            self.select(id);
+        }
+        self.clamp();
+    }
+
+    pub fn upsert(&mut self, todo: Todo) {
+        match self.todos.iter_mut().find(|t| t.id == todo.id) {
+            Some(existing) => *existing = todo,
+            None => self.todos.push(todo),
+        }
+        self.clamp();
+    }
+
+    pub fn remove(&mut self, id: Uuid) {
+        self.todos.retain(|t| t.id != id);
+        self.clamp();
+    }
+
+    /// Applies a change made by anyone, including this board.
+    pub fn apply(&mut self, event: TodoEvent) {
+        match event.kind {
+            TodoEventKind::Deleted => self.remove(event.todo_id),
+            TodoEventKind::Created | TodoEventKind::Updated => {
+                if let Ok(todo) = serde_json::from_value(event.todo) {
+                    self.upsert(todo);
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&Todo> {
+        self.todos.iter().find(|t| t.id == id)
+    }
+
+    /// Cards in a column, highest priority first and newest first within a
+    /// priority.
+    pub fn cards(&self, column: usize) -> Vec<&Todo> {
+        let mut cards: Vec<&Todo> = self
+            .todos
+            .iter()
+            .filter(|t| t.status == COLUMNS[column])
+            .collect();
+        cards.sort_by(|a, b| {
+            rank(&a.priority)
+                .cmp(&rank(&b.priority))
+                .then(b.created_at.cmp(&a.created_at))
+        });
+        cards
+    }
+
+    pub fn row(&self, column: usize) -> usize {
+        self.rows[column]
+    }
+
+    pub fn selected(&self) -> Option<&Todo> {
+        self.cards(self.column).get(self.rows[self.column]).copied()
+    }
+
+    /// Focuses the column holding `id` and selects its card there.
+    pub fn select(&mut self, id: Uuid) {
+        for column in 0..COLUMNS.len() {
+            if let Some(row) = self.cards(column).iter().position(|t| t.id == id) {
+                self.column = column;
+                self.rows[column] = row;
+            }
+        }
+    }
+
+    pub fn move_focus(&mut self, delta: isize) {
+        self.column = self
+            .column
+            .saturating_add_signed(delta)
+            .min(COLUMNS.len() - 1);
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let len = self.cards(self.column).len();
+        let row = &mut self.rows[self.column];
+        *row = row.saturating_add_signed(delta).min(len.saturating_sub(1));
+    }
+
+    pub fn select_edge(&mut self, last: bool) {
+        let len = self.cards(self.column).len();
+        self.rows[self.column] = if last { len.saturating_sub(1) } else { 0 };
+    }
+
+    /// Keeps every selection on a card after cards come and go.
+    fn clamp(&mut self) {
+        for column in 0..COLUMNS.len() {
+            let len = self.cards(column).len();
+            self.rows[column] = self.rows[column].min(len.saturating_sub(1));
+        }
+    }
+}
+
+fn rank(priority: &Priority) -> u8 {
+    match priority {
+        Priority::High => 0,
+        Priority::Medium => 1,
+        Priority::Low => 2,
+    }
+}
+
+/// The next priority up (`raise`) or down, if there is one.
+pub fn step_priority(priority: &Priority, raise: bool) -> Option<Priority> {
+    match (priority, raise) {
+        (Priority::Low, true) => Some(Priority::Medium),
+        (Priority::Medium, true) => Some(Priority::High),
+        (Priority::High, false) => Some(Priority::Medium),
+        (Priority::Medium, false) => Some(Priority::Low),
+        _ => None,
+    }
+}
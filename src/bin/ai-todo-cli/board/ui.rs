@@ -0,0 +1,157 @@
+//! Rendering.
+
+use ai_todo::models::todo::{Priority, Todo};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, List, ListItem, ListState, Paragraph},
+};
+
+use super::state::{Board, COLUMNS, Edit, Field, Input, Mode, Target};
+use crate::output::short_id;
+
+pub fn draw(frame: &mut Frame, board: &Board) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let columns: [Rect; 3] = Layout::horizontal([Constraint::Ratio(1, 3); 3]).areas(main);
+    for (column, area) in columns.into_iter().enumerate() {
+        draw_column(frame, board, column, area);
+    }
+    draw_footer(frame, board, footer);
+}
+
+fn draw_column(frame: &mut Frame, board: &Board, column: usize, area: Rect) {
+    let focused = column == board.column;
+    let cards = board.cards(column);
+    let edit = match &board.mode {
+        Mode::Edit(edit) => Some(edit),
+        _ => None,
+    };
+
+    let mut items: Vec<ListItem> = cards
+        .iter()
+        .map(|todo| {
+            let editing =
+                edit.filter(|e| matches!(e.target, Target::Existing(id) if id == todo.id));
+            card(todo, editing)
+        })
+        .collect();
+    let mut selected = board.row(column);
+    // A card being added goes on top of its column.
+    if let Some(edit) = edit
+        && matches!(&edit.target, Target::New(status) if *status == COLUMNS[column])
+    {
+        items.insert(0, new_card(&edit.input));
+        selected = 0;
+    }
+
+    let border = if focused {
+        Style::new().fg(Color::Cyan)
+    } else {
+        Style::new().fg(Color::DarkGray)
+    };
+    let block = Block::bordered()
+        .border_type(BorderType::Rounded)
+        .border_style(border)
+        .title(format!(" {} ({}) ", COLUMNS[column], cards.len()).bold());
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::new().bg(Color::DarkGray));
+    let mut state = ListState::default().with_selected(focused.then_some(selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn card<'a>(todo: &'a Todo, edit: Option<&'a Edit>) -> ListItem<'a> {
+    let title = match edit {
+        Some(Edit {
+            field: Field::Title,
+            input,
+            ..
+        }) => input_spans(input),
+        _ => vec![Span::raw(todo.title.as_str()).bold()],
+    };
+    let notes = match edit {
+        Some(Edit {
+            field: Field::Description,
+            input,
+            ..
+        }) => input_spans(input),
+        _ => vec![Span::raw(todo.description.as_deref().unwrap_or("")).dim()],
+    };
+
+    let mut first = vec![priority_marker(&todo.priority)];
+    first.extend(title);
+    let mut second = vec![Span::raw(format!("  {} ", short_id(todo))).dark_gray()];
+    second.extend(notes);
+    ListItem::new(Text::from(vec![
+        Line::from(first),
+        Line::from(second),
+        Line::default(),
+    ]))
+}
+
+fn new_card(input: &Input) -> ListItem<'_> {
+    let mut first = vec![Span::raw("+ ").green()];
+    first.extend(input_spans(input));
+    ListItem::new(Text::from(vec![
+        Line::from(first),
+        Line::from("  new todo".dark_gray()),
+        Line::default(),
+    ]))
+}
+
+/// The input's text with the character under the cursor shown reversed.
+fn input_spans(input: &Input) -> Vec<Span<'_>> {
+    let text = input.text();
+    let (before, rest) = text.split_at(
+        text.char_indices()
+            .nth(input.cursor())
+            .map_or(text.len(), |(i, _)| i),
+    );
+    let mut chars = rest.chars();
+    let under = chars.next().map_or(" ".to_string(), String::from);
+    vec![
+        Span::raw(before).underlined(),
+        Span::raw(under).add_modifier(Modifier::REVERSED),
+        Span::raw(chars.as_str()).underlined(),
+    ]
+}
+
+fn priority_marker(priority: &Priority) -> Span<'static> {
+    match priority {
+        Priority::High => "▲ ".red(),
+        Priority::Medium => "■ ".yellow(),
+        Priority::Low => "▼ ".blue(),
+    }
+}
+
+fn draw_footer(frame: &mut Frame, board: &Board, area: Rect) {
+    let hints = match &board.mode {
+        Mode::Normal => {
+            "←→ column  ↑↓ card  H/L move  +/- priority  e title  E notes  a add  x delete  r reload  q quit"
+        }
+        Mode::Edit(_) => "enter save  esc cancel",
+        Mode::ConfirmDelete(_) => "",
+    };
+    let left = match (&board.mode, &board.message) {
+        (Mode::ConfirmDelete(id), _) => {
+            let title = board.get(*id).map_or("", |t| t.title.as_str());
+            Line::from(format!("Delete \"{title}\"? y/n").red().bold())
+        }
+        (Mode::Normal, Some(message)) => Line::from(message.as_str().yellow()),
+        _ => Line::from(hints.dark_gray()),
+    };
+    let right = if board.live {
+        Line::from("● live ".green())
+    } else {
+        Line::from("○ offline ".red())
+    };
+
+    let [left_area, right_area] =
+        Layout::horizontal([Constraint::Min(0), Constraint::Length(10)]).areas(area);
+    frame.render_widget(Paragraph::new(left), left_area);
+    frame.render_widget(Paragraph::new(right.right_aligned()), right_area);
+}
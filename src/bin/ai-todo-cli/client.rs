@@ -0,0 +1,153 @@
+//! HTTP client for the `/v1` API.
+
+use std::path::Path;
+
+use ai_todo::{
+    error::ErrorResponse,
+    models::todo::{SuggestedTodo, Todo},
+    routes::{
+        audio::{ConfirmTasksRequest, SuggestedTasksResponse},
+        todos::{CreateTodo, UpdateTodo},
+    },
+};
+use reqwest::{Method, RequestBuilder, Response, multipart};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use crate::CliError;
+
+#[derive(Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+    workspace: Option<Uuid>,
+}
+
+impl ApiClient {
+    pub fn new(server: &str, token: String, workspace: Option<Uuid>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: format!("{}/v1", server.trim_end_matches('/')),
+            token,
+            workspace,
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut builder = self
+            .http
+            .request(method, format!("{}{path}", self.base_url))
+            .bearer_auth(&self.token);
+        if let Some(workspace) = self.workspace {
+            builder = builder.header("X-Workspace-Id", workspace.to_string());
+        }
+        builder
+    }
+
+    pub async fn list_todos(&self, project_id: Option<Uuid>) -> Result<Vec<Todo>, CliError> {
+        let mut request = self.request(Method::GET, "/todos");
+        if let Some(project_id) = project_id {
+            request = request.query(&[("project_id", project_id)]);
+        }
+        json(request.send().await?).await
+    }
+
+    pub async fn get_todo(&self, id: Uuid) -> Result<Todo, CliError> {
+        let request = self.request(Method::GET, &format!("/todos/{id}"));
+        json(request.send().await?).await
+    }
+
+    pub async fn create_todo(&self, todo: &CreateTodo) -> Result<Todo, CliError> {
+        let request = self.request(Method::POST, "/todos");
+        json(request.json(todo).send().await?).await
+    }
+
+    pub async fn update_todo(&self, id: Uuid, update: &UpdateTodo) -> Result<Todo, CliError> {
+        let request = self.request(Method::PATCH, &format!("/todos/{id}"));
+        json(request.json(update).send().await?).await
+    }
+
+    pub async fn delete_todo(&self, id: Uuid) -> Result<(), CliError> {
+        let response = self
+            .request(Method::DELETE, &format!("/todos/{id}"))
+            .send()
+            .await?;
+        check(response).await.map(drop)
+    }
+
+    /// Opens the server-sent event stream of todo changes. The caller reads
+    /// the body as it arrives.
+    pub async fn todo_events(&self, project_id: Option<Uuid>) -> Result<Response, CliError> {
+        let mut request = self
+            .request(Method::GET, "/todos/events")
+            .header(reqwest::header::ACCEPT, "text/event-stream");
+        if let Some(project_id) = project_id {
+            request = request.query(&[("project_id", project_id)]);
+        }
+        check(request.send().await?).await
+    }
+
+    pub async fn suggest(
+        &self,
+        file: &Path,
+        mime_type: &str,
+    ) -> Result<Vec<SuggestedTodo>, CliError> {
+        let data = tokio::fs::read(file)
+            .await
+            .map_err(|e| CliError::Input(format!("{}: {e}", file.display())))?;
+        let file_name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let part = multipart::Part::bytes(data)
+            .file_name(file_name)
+            .mime_str(mime_type)
+            .map_err(|_| CliError::Input(format!("invalid MIME type: {mime_type}")))?;
+        let form = multipart::Form::new().part("audio", part);
+
+        let request = self.request(Method::POST, "/audio/suggest").multipart(form);
+        let response: SuggestedTasksResponse = json(request.send().await?).await?;
+        Ok(response.tasks)
+    }
+
+    pub async fn confirm(
+        &self,
+        tasks: Vec<SuggestedTodo>,
+        project_id: Option<Uuid>,
+    ) -> Result<(), CliError> {
+        let body = ConfirmTasksRequest { tasks, project_id };
+        let request = self.request(Method::POST, "/audio/confirm").json(&body);
+        check(request.send().await?).await.map(drop)
+    }
+}
+
+/// Turns an error status into [`CliError::Api`], using the server's error
+/// body when it sent one.
+async fn check(response: Response) -> Result<Response, CliError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let request_id = response
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = response.text().await.unwrap_or_default();
+    let error = serde_json::from_str(&body).unwrap_or_else(|_| ErrorResponse {
+        message: status
+            .canonical_reason()
+            .unwrap_or("Request failed")
+            .to_string(),
+        status: status.as_u16(),
+        errors: Default::default(),
+        request_id,
+    });
+    Err(CliError::Api(error))
+}
+
+async fn json<T: DeserializeOwned>(response: Response) -> Result<T, CliError> {
+    Ok(check(response).await?.json().await?)
+}
@@ -0,0 +1,57 @@
+//! The client's config file, `~/.config/ai-todo/config.toml` by default.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::CliError;
+
+pub const DEFAULT_SERVER: &str = "http://127.0.0.1:5000";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    /// Base URL of the server, without the `/v1` prefix.
+    pub server: Option<String>,
+    pub token: Option<String>,
+    /// Workspace to operate in instead of the token's default one.
+    pub workspace: Option<Uuid>,
+}
+
+impl Config {
+    /// `~/.config/ai-todo/config.toml`, or the platform's equivalent.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("ai-todo").join("config.toml"))
+    }
+
+    /// Reads the config at `path`. A missing file is an empty config.
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents)
+                .map_err(|e| CliError::Config(format!("{}: {e}", path.display()))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(CliError::Config(format!("{}: {e}", path.display()))),
+        }
+    }
+
+    /// Writes the config to `path`, readable only by the current user since
+    /// it holds a token.
+    pub fn save(&self, path: &Path) -> Result<(), CliError> {
+        let contents = toml::to_string(self).map_err(|e| CliError::Config(e.to_string()))?;
+        let write = || -> std::io::Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut options = fs::OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+            options.open(path)?.write_all(contents.as_bytes())
+        };
+        write().map_err(|e| CliError::Config(format!("{}: {e}", path.display())))
+    }
+}
@@ -0,0 +1,472 @@
+//! Command-line client for the AI-Todo HTTP API.
+
+mod board;
+mod client;
+mod config;
+mod output;
+mod suggest;
+
+use std::{path::PathBuf, process::ExitCode};
+
+use ai_todo::{
+    error::ErrorResponse,
+    models::todo::{Priority, Todo, TodoStatus},
+    routes::todos::{CreateTodo, UpdateTodo},
+};
+use clap::{Args, Parser, Subcommand};
+use thiserror::Error;
+use uuid::Uuid;
+
+use client::ApiClient;
+use config::Config;
+
+#[derive(Parser)]
+#[command(
+    name = "ai-todo-cli",
+    version,
+    about = "Manage AI-Todo todos from the terminal"
+)]
+struct Cli {
+    /// Server URL, e.g. http://127.0.0.1:5000 [default: from the config file]
+    #[arg(long, global = true, env = "AI_TODO_SERVER")]
+    server: Option<String>,
+
+    /// API token [default: from the config file]
+    #[arg(long, global = true, env = "AI_TODO_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// Workspace to operate in [default: the token's workspace]
+    #[arg(long, global = true, env = "AI_TODO_WORKSPACE")]
+    workspace: Option<Uuid>,
+
+    /// Config file [default: ~/.config/ai-todo/config.toml]
+    #[arg(long, global = true, env = "AI_TODO_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Print JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List todos
+    #[command(alias = "ls")]
+    List(ListArgs),
+    /// Show one todo
+    Show { id: String },
+    /// Add a todo
+    Add(AddArgs),
+    /// Change a todo's fields
+    Edit(EditArgs),
+    /// Mark todos as done
+    Done {
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Delete todos
+    #[command(alias = "rm")]
+    Delete {
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// Suggest todos from an audio recording and pick which to create
+    Suggest(SuggestArgs),
+    /// Open an interactive kanban board that updates live
+    Board {
+        /// Only show this project's todos, and add new ones to it
+        #[arg(long)]
+        project: Option<Uuid>,
+    },
+    /// Show the config, or save the given settings to it
+    Config(ConfigArgs),
+}
+
+#[derive(Args)]
+struct ListArgs {
+    #[arg(long, value_parser = parse_status)]
+    status: Option<TodoStatus>,
+    #[arg(long, value_parser = parse_priority)]
+    priority: Option<Priority>,
+    #[arg(long)]
+    project: Option<Uuid>,
+    /// Only todos whose title or description contains this text
+    #[arg(long)]
+    search: Option<String>,
+    /// Hide done todos
+    #[arg(long, conflicts_with = "status")]
+    open: bool,
+}
+
+#[derive(Args)]
+struct AddArgs {
+    title: String,
+    #[arg(short, long)]
+    description: Option<String>,
+    #[arg(short, long, value_parser = parse_priority)]
+    priority: Option<Priority>,
+    #[arg(long)]
+    project: Option<Uuid>,
+}
+
+#[derive(Args)]
+struct EditArgs {
+    id: String,
+    #[arg(short, long)]
+    title: Option<String>,
+    #[arg(short, long)]
+    description: Option<String>,
+    #[arg(short, long, value_parser = parse_status)]
+    status: Option<TodoStatus>,
+    #[arg(short, long, value_parser = parse_priority)]
+    priority: Option<Priority>,
+    /// Move the todo into this project
+    #[arg(long)]
+    project: Option<Uuid>,
+}
+
+#[derive(Args)]
+struct SuggestArgs {
+    file: PathBuf,
+    /// MIME type of the recording [default: guessed from the file extension]
+    #[arg(long)]
+    mime_type: Option<String>,
+    /// Add the created todos to this project
+    #[arg(long)]
+    project: Option<Uuid>,
+    /// Create every suggestion without asking
+    #[arg(short, long)]
+    yes: bool,
+}
+
+#[derive(Args)]
+struct ConfigArgs {
+    /// Save the server URL
+    #[arg(long = "set-server")]
+    set_server: Option<String>,
+    /// Save the API token
+    #[arg(long = "set-token")]
+    set_token: Option<String>,
+    /// Save the workspace
+    #[arg(long = "set-workspace")]
+    set_workspace: Option<Uuid>,
+}
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("{0}")]
+    Config(String),
+
+    #[error("{0}")]
+    Input(String),
+
+    #[error("terminal error: {0}")]
+    Terminal(#[from] std::io::Error),
+
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("{}", describe(.0))]
+    Api(ErrorResponse),
+}
+
+fn describe(error: &ErrorResponse) -> String {
+    let mut fields: Vec<_> = error
+        .errors
+        .iter()
+        .map(|(field, messages)| format!("{field}: {}", messages.join(", ")))
+        .collect();
+    fields.sort();
+
+    // Server errors are worth reporting, and the request ID helps with that.
+    let mut text = match &error.request_id {
+        Some(request_id) if error.status >= 500 => {
+            format!("{} ({}, request {request_id})", error.message, error.status)
+        }
+        _ => format!("{} ({})", error.message, error.status),
+    };
+    for field in fields {
+        text.push_str("\n  ");
+        text.push_str(&field);
+    }
+    text
+}
+
+/// Case-insensitive, unlike [`TodoStatus`]'s `FromStr`.
+fn parse_status(value: &str) -> Result<TodoStatus, String> {
+    [TodoStatus::Todo, TodoStatus::Doing, TodoStatus::Done]
+        .into_iter()
+        .find(|s| s.to_string().eq_ignore_ascii_case(value))
+        .ok_or_else(|| format!("expected todo, doing or done, got {value:?}"))
+}
+
+/// Case-insensitive, unlike [`Priority`]'s `FromStr`.
+pub fn parse_priority(value: &str) -> Result<Priority, String> {
+    [Priority::Low, Priority::Medium, Priority::High]
+        .into_iter()
+        .find(|p| p.to_string().eq_ignore_ascii_case(value))
+        .ok_or_else(|| format!("expected low, medium or high, got {value:?}"))
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), CliError> {
+    let config_path = cli
+        .config
+        .clone()
+        .or_else(Config::default_path)
+        .ok_or_else(|| CliError::Config("cannot locate the config directory".to_string()))?;
+    let config = Config::load(&config_path)?;
+
+    if let Command::Config(args) = cli.command {
+        return configure(config, &config_path, args);
+    }
+
+    // Flags and environment variables take precedence over the config file.
+    let server = cli
+        .server
+        .or(config.server)
+        .unwrap_or_else(|| config::DEFAULT_SERVER.to_string());
+    let token = cli.token.or(config.token).ok_or_else(|| {
+        CliError::Config(
+            "no API token; run `ai-todo-cli config --set-token <token>` or set AI_TODO_TOKEN"
+                .to_string(),
+        )
+    })?;
+    let client = ApiClient::new(&server, token, cli.workspace.or(config.workspace));
+
+    match cli.command {
+        Command::List(args) => list(&client, args, cli.json).await,
+        Command::Show { id } => {
+            let id = resolve_id(&client, &id).await?;
+            let todo = client.get_todo(id).await?;
+            print(&todo, cli.json);
+            Ok(())
+        }
+        Command::Add(args) => {
+            let todo = client
+                .create_todo(&CreateTodo {
+                    title: args.title,
+                    description: args.description,
+                    priority: args.priority,
+                    project_id: args.project,
+                })
+                .await?;
+            print(&todo, cli.json);
+            Ok(())
+        }
+        Command::Edit(args) => {
+            let id = resolve_id(&client, &args.id).await?;
+            let update = UpdateTodo {
+                title: args.title,
+                description: args.description,
+                status: args.status,
+                priority: args.priority,
+                project_id: args.project,
+            };
+            let todo = client.update_todo(id, &update).await?;
+            print(&todo, cli.json);
+            Ok(())
+        }
+        Command::Done { ids } => {
+            for id in ids {
+                let id = resolve_id(&client, &id).await?;
+                let update = UpdateTodo {
+                    status: Some(TodoStatus::Done),
+                    ..Default::default()
+                };
+                let todo = client.update_todo(id, &update).await?;
+                print(&todo, cli.json);
+            }
+            Ok(())
+        }
+        Command::Delete { ids } => {
+            for id in ids {
+                let id = resolve_id(&client, &id).await?;
+                client.delete_todo(id).await?;
+                if !cli.json {
+                    println!("Deleted {id}");
+                }
+            }
+            Ok(())
+        }
+        Command::Suggest(args) => suggest_todos(&client, args, cli.json).await,
+        Command::Board { project } => board::run(client, project).await,
+        Command::Config(_) => unreachable!("handled above"),
+    }
+}
+
+fn print(todo: &Todo, json: bool) {
+    if json {
+        output::print_json(todo);
+    } else {
+        output::print_todo(todo);
+    }
+}
+
+async fn list(client: &ApiClient, args: ListArgs, json: bool) -> Result<(), CliError> {
+    let search = args.search.map(|s| s.to_lowercase());
+    let todos: Vec<Todo> = client
+        .list_todos(args.project)
+        .await?
+        .into_iter()
+        .filter(|t| args.status.as_ref().is_none_or(|s| &t.status == s))
+        .filter(|t| !args.open || t.status != TodoStatus::Done)
+        .filter(|t| args.priority.as_ref().is_none_or(|p| &t.priority == p))
+        .filter(|t| {
+            search.as_ref().is_none_or(|s| {
+                t.title.to_lowercase().contains(s)
+                    || t.description
+                        .as_ref()
+                        .is_some_and(|d| d.to_lowercase().contains(s))
+            })
+        })
+        .collect();
+
+    if json {
+        output::print_json(&todos);
+    } else {
+        output::print_table(&todos);
+    }
+    Ok(())
+}
+
+/// Accepts a full ID or a unique prefix of one, such as the short IDs in
+/// `list` output.
+async fn resolve_id(client: &ApiClient, id: &str) -> Result<Uuid, CliError> {
+    if let Ok(id) = id.parse() {
+        return Ok(id);
+    }
+
+    let prefix = id.replace('-', "").to_lowercase();
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(CliError::Input(format!("not a todo ID: {id}")));
+    }
+
+    let matches: Vec<Uuid> = client
+        .list_todos(None)
+        .await?
+        .into_iter()
+        .map(|t| t.id)
+        .filter(|t| t.simple().to_string().starts_with(&prefix))
+        .collect();
+    match matches.as_slice() {
+        [id] => Ok(*id),
+        [] => Err(CliError::Input(format!("no todo matches {id}"))),
+        _ => Err(CliError::Input(format!(
+            "{id} matches {} todos; use more characters",
+            matches.len()
+        ))),
+    }
+}
+
+async fn suggest_todos(client: &ApiClient, args: SuggestArgs, json: bool) -> Result<(), CliError> {
+    let mime_type = args
+        .mime_type
+        .unwrap_or_else(|| guess_mime_type(&args.file).to_string());
+
+    if !json {
+        println!("Uploading {}...", args.file.display());
+    }
+    let suggestions = client.suggest(&args.file, &mime_type).await?;
+    if suggestions.is_empty() {
+        if !json {
+            println!("No todos found in the recording.");
+        }
+        return Ok(());
+    }
+
+    let accepted = if args.yes {
+        suggestions
+    } else {
+        println!("{} suggested:", todos(suggestions.len()));
+        let accepted = suggest::review(suggestions)?;
+        println!();
+        if accepted.is_empty()
+            || !suggest::confirm(&format!("Create {}?", todos(accepted.len())), true)?
+        {
+            println!("Nothing created.");
+            return Ok(());
+        }
+        accepted
+    };
+
+    let count = accepted.len();
+    if json {
+        output::print_json(&accepted);
+    }
+    client.confirm(accepted, args.project).await?;
+    if !json {
+        println!("Created {}.", todos(count));
+    }
+    Ok(())
+}
+
+fn todos(count: usize) -> String {
+    if count == 1 {
+        "1 todo".to_string()
+    } else {
+        format!("{count} todos")
+    }
+}
+
+/// The server defaults to `audio/mpeg` when no type is given.
+fn guess_mime_type(file: &std::path::Path) -> &'static str {
+    let extension = file
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("wav") => "audio/wav",
+        Some("m4a" | "mp4") => "audio/mp4",
+        Some("aac") => "audio/aac",
+        Some("ogg" | "oga") => "audio/ogg",
+        Some("webm") => "audio/webm",
+        Some("flac") => "audio/flac",
+        _ => "audio/mpeg",
+    }
+}
+
+fn configure(mut config: Config, path: &std::path::Path, args: ConfigArgs) -> Result<(), CliError> {
+    let changed =
+        args.set_server.is_some() || args.set_token.is_some() || args.set_workspace.is_some();
+    if changed {
+        config.server = args.set_server.or(config.server);
+        config.token = args.set_token.or(config.token);
+        config.workspace = args.set_workspace.or(config.workspace);
+        config.save(path)?;
+        println!("Saved {}", path.display());
+    }
+
+    println!("config:    {}", path.display());
+    println!(
+        "server:    {}",
+        config.server.as_deref().unwrap_or(config::DEFAULT_SERVER)
+    );
+    // Enough of the token to tell which one it is.
+    let token = config
+        .token
+        .as_deref()
+        .map(|t| format!("{}…", t.chars().take(12).collect::<String>()))
+        .unwrap_or_else(|| "(not set)".to_string());
+    println!("token:     {token}");
+    let workspace = config
+        .workspace
+        .map(|w| w.to_string())
+        .unwrap_or_else(|| "(token default)".to_string());
+    println!("workspace: {workspace}");
+    Ok(())
+}
@@ -0,0 +1,98 @@
+//! Table and JSON output.
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use ai_todo::models::todo::Todo;
+use serde::Serialize;
+
+const TITLE_WIDTH: usize = 48;
+
+/// Prints a line of output. Exits quietly once stdout is closed, e.g. when
+/// piped into `head`.
+fn line(text: impl Display) {
+    if let Err(e) = writeln!(io::stdout().lock(), "{text}")
+        && e.kind() == io::ErrorKind::BrokenPipe
+    {
+        std::process::exit(0);
+    }
+}
+
+pub fn print_json<T: Serialize>(value: &T) {
+    // Serializing the API's own types cannot fail.
+    line(serde_json::to_string_pretty(value).unwrap());
+}
+
+/// Prints todos as a table with short IDs, which every command accepts.
+pub fn print_table(todos: &[Todo]) {
+    if todos.is_empty() {
+        line("No todos.");
+        return;
+    }
+
+    let rows: Vec<[String; 5]> = todos
+        .iter()
+        .map(|todo| {
+            [
+                short_id(todo),
+                todo.status.to_string(),
+                todo.priority.to_string(),
+                truncate(&todo.title, TITLE_WIDTH),
+                todo.created_at.format("%Y-%m-%d").to_string(),
+            ]
+        })
+        .collect();
+    let header = ["ID", "STATUS", "PRIORITY", "TITLE", "CREATED"].map(String::from);
+
+    let mut widths = header.clone().map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        line(cells.join("  ").trim_end());
+    }
+}
+
+/// Prints one todo with all of its fields.
+pub fn print_todo(todo: &Todo) {
+    line(format_args!("{}  {}", short_id(todo), todo.title));
+    line(format_args!("  id:        {}", todo.id));
+    line(format_args!("  status:    {}", todo.status));
+    line(format_args!("  priority:  {}", todo.priority));
+    if let Some(description) = &todo.description {
+        line(format_args!("  notes:     {description}"));
+    }
+    if let Some(project_id) = todo.project_id {
+        line(format_args!("  project:   {project_id}"));
+    }
+    line(format_args!(
+        "  created:   {}",
+        todo.created_at.format("%Y-%m-%d %H:%M")
+    ));
+    line(format_args!(
+        "  updated:   {}",
+        todo.updated_at.format("%Y-%m-%d %H:%M")
+    ));
+}
+
+pub fn short_id(todo: &Todo) -> String {
+    todo.id.simple().to_string()[..8].to_string()
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(width - 1).collect();
+    format!("{kept}…")
+}
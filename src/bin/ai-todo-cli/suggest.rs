@@ -0,0 +1,123 @@
+//! Interactive review of suggested todos before they are created.
+
+use std::io::{self, BufRead, Write};
+
+use ai_todo::models::todo::{Priority, SuggestedTodo};
+
+use crate::{CliError, parse_priority};
+
+enum Choice {
+    Accept,
+    Edit,
+    Reject,
+    Quit,
+}
+
+/// Asks about each suggestion in turn and returns the accepted ones, edited
+/// where the user chose to. Quitting drops the remaining suggestions.
+pub fn review(suggestions: Vec<SuggestedTodo>) -> Result<Vec<SuggestedTodo>, CliError> {
+    let total = suggestions.len();
+    let mut accepted = Vec::new();
+
+    for (i, mut todo) in suggestions.into_iter().enumerate() {
+        println!();
+        println!("[{}/{total}] {} ({})", i + 1, todo.title, todo.priority);
+        if let Some(description) = &todo.description {
+            println!("      {description}");
+        }
+
+        match choose()? {
+            Choice::Accept => accepted.push(todo),
+            Choice::Edit => {
+                edit(&mut todo)?;
+                accepted.push(todo);
+            }
+            Choice::Reject => {}
+            Choice::Quit => break,
+        }
+    }
+
+    Ok(accepted)
+}
+
+/// Asks a yes/no question; `default` is used for an empty answer.
+pub fn confirm(question: &str, default: bool) -> Result<bool, CliError> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    loop {
+        let answer = prompt(&format!("{question} [{hint}] "))?;
+        match answer.to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("Please answer y or n."),
+        }
+    }
+}
+
+fn choose() -> Result<Choice, CliError> {
+    loop {
+        let answer = prompt("Accept, edit, reject or quit? [A/e/r/q] ")?;
+        match answer.to_lowercase().as_str() {
+            "" | "a" | "accept" => return Ok(Choice::Accept),
+            "e" | "edit" => return Ok(Choice::Edit),
+            "r" | "reject" => return Ok(Choice::Reject),
+            "q" | "quit" => return Ok(Choice::Quit),
+            _ => println!("Please answer a, e, r or q."),
+        }
+    }
+}
+
+/// Prompts for each field, keeping the current value on an empty answer.
+fn edit(todo: &mut SuggestedTodo) -> Result<(), CliError> {
+    let title = prompt(&format!("Title [{}]: ", todo.title))?;
+    if !title.is_empty() {
+        todo.title = title;
+    }
+
+    let current = todo.description.as_deref().unwrap_or("");
+    let description = prompt(&format!("Description [{current}] ('-' to clear): "))?;
+    match description.as_str() {
+        "" => {}
+        "-" => todo.description = None,
+        _ => todo.description = Some(description),
+    }
+
+    loop {
+        let answer = prompt(&format!("Priority [{}]: ", todo.priority))?;
+        if answer.is_empty() {
+            break;
+        }
+        match parse_priority(&answer) {
+            Ok(priority) => {
+                todo.priority = priority;
+                break;
+            }
+            Err(_) => println!("Please answer {}.", priority_names()),
+        }
+    }
+
+    Ok(())
+}
+
+fn priority_names() -> String {
+    [Priority::Low, Priority::Medium, Priority::High]
+        .map(|p| p.to_string().to_lowercase())
+        .join(", ")
+}
+
+/// Reads one trimmed line. End of input aborts, so that piping in too few
+/// answers never creates todos the user did not see.
+fn prompt(question: &str) -> Result<String, CliError> {
+    print!("{question}");
+    io::stdout().flush().ok();
+
+    let mut line = String::new();
+    let read = io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| CliError::Input(e.to_string()))?;
+    if read == 0 {
+        return Err(CliError::Input("aborted".to_string()));
+    }
+    Ok(line.trim().to_string())
+}
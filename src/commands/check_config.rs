@@ -0,0 +1,142 @@
+//! `ai-todo check-config`: validates the configuration without starting the
+//! server.
+
+use super::{CommandError, describe, migrate};
+use crate::{
+    config::{Backend, Config},
+    services,
+};
+
+enum Level {
+    Ok,
+    Warn,
+    Error,
+}
+
+/// Reports the settings `serve` would use, and checks that the database is
+/// reachable and migrated. Settings that fail to parse never get here: the
+/// config fails to load instead. Fails if any check fails; warnings do not.
+pub async fn run(config: &Config) -> Result<(), CommandError> {
+    let mut errors = 0;
+    let mut report = |level: Level, name: &str, detail: String| {
+        let label = match level {
+            Level::Ok => "ok",
+            Level::Warn => "warn",
+            Level::Error => {
+                errors += 1;
+                "error"
+            }
+        };
+        println!("{label:<6}{name:<14}{detail}");
+    };
+
+    let sqlite = config.database.backend() == Backend::Sqlite;
+    let server = &config.server;
+    report(Level::Ok, "http", server.http_addr.to_string());
+    if sqlite {
+        report(Level::Ok, "grpc", "disabled with SQLite".to_string());
+    } else {
+        report(Level::Ok, "grpc", server.grpc_addr.to_string());
+    }
+    report(
+        Level::Ok,
+        "shutdown",
+        format!("{}s timeout", server.shutdown_timeout_secs),
+    );
+
+    match check_database(config).await {
+        Ok((applied, 0)) => report(
+            Level::Ok,
+            "database",
+            format!("connected; {applied} migrations applied"),
+        ),
+        // `serve` migrates SQLite databases itself.
+        Ok((applied, pending)) if sqlite => report(
+            Level::Ok,
+            "database",
+            format!(
+                "connected; {applied} migrations applied, {pending} to apply when the server starts"
+            ),
+        ),
+        Ok((applied, pending)) => report(
+            Level::Warn,
+            "database",
+            format!(
+                "connected; {applied} migrations applied, {pending} pending (run `ai-todo migrate up`)"
+            ),
+        ),
+        Err(e) => report(Level::Error, "database", e.to_string()),
+    }
+
+    match services::gemini::GeminiService::new(&config.gemini) {
+        Ok(_) => report(
+            Level::Ok,
+            "gemini",
+            format!("model {}", config.gemini.model),
+        ),
+        Err(e) => report(Level::Error, "gemini", describe(&e)),
+    }
+
+    match &config.oidc.issuer_url {
+        Some(_) if sqlite => report(
+            Level::Warn,
+            "oidc",
+            "issuer_url is ignored with SQLite".to_string(),
+        ),
+        Some(issuer_url) => report(Level::Ok, "oidc", format!("enabled; issuer {issuer_url}")),
+        None => report(Level::Ok, "oidc", "disabled".to_string()),
+    }
+
+    match config.auth.admin_token {
+        Some(_) => report(Level::Ok, "admin token", "set".to_string()),
+        None if sqlite => report(
+            Level::Error,
+            "admin token",
+            "not set; required with SQLite, where it is the only way to sign in".to_string(),
+        ),
+        None => report(
+            Level::Warn,
+            "admin token",
+            "not set; only tokens stored in the database will be accepted".to_string(),
+        ),
+    }
+
+    report(Level::Ok, "logs", format!("{} format", config.log.format));
+
+    let limits = &config.rate_limits;
+    report(
+        Level::Ok,
+        "rate limits",
+        format!(
+            "{}/min, AI {}/min, {} AI calls per day",
+            limits.per_minute, limits.ai_per_minute, limits.ai_daily_quota
+        ),
+    );
+
+    match errors {
+        0 => Ok(()),
+        1 => Err(CommandError::Invalid("1 problem found".to_string())),
+        n => Err(CommandError::Invalid(format!("{n} problems found"))),
+    }
+}
+
+/// Connects and counts applied and pending migrations.
+async fn check_database(config: &Config) -> Result<(usize, usize), CommandError> {
+    let (applied, migrator) = match config.database.backend() {
+        Backend::Postgres => {
+            let pool = super::connect(config).await?;
+            (migrate::applied_versions(&pool).await?, &migrate::MIGRATOR)
+        }
+        Backend::Sqlite => {
+            let pool = super::connect_sqlite(config).await?;
+            (
+                migrate::applied_versions(&pool).await?,
+                &migrate::SQLITE_MIGRATOR,
+            )
+        }
+    };
+    let pending = migrate::up_migrations(migrator)
+        .filter(|m| !applied.contains_key(&m.version))
+        .count();
+    Ok((applied.len(), pending))
+}
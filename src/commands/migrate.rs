@@ -0,0 +1,151 @@
+//! `ai-todo migrate up|down|status`.
+
+use std::collections::HashMap;
+
+use sqlx::{
+    Database, Pool,
+    migrate::{Migrate, Migrator},
+};
+
+use super::CommandError;
+
+/// The migrations in `migrations/`, embedded at build time. Each has an
+/// `.up.sql` and a `.down.sql` file so that it can be reverted.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// The migrations for SQLite storage, in `migrations_sqlite/`.
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations_sqlite");
+
+// Every command takes the migrator that matches the pool: `MIGRATOR` for
+// PostgreSQL, `SQLITE_MIGRATOR` for SQLite.
+
+/// Applies every pending migration.
+pub async fn up<DB>(migrator: &'static Migrator, pool: &Pool<DB>) -> Result<(), CommandError>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let before = applied_versions(pool).await?;
+    migrator.run(pool).await?;
+
+    let mut count = 0;
+    for migration in up_migrations(migrator).filter(|m| !before.contains_key(&m.version)) {
+        println!("Applied {} {}", migration.version, migration.description);
+        count += 1;
+    }
+    if count == 0 {
+        println!("Already up to date.");
+    }
+    Ok(())
+}
+
+/// Reverts every applied migration newer than `target`, or only the latest
+/// one when no target is given. A target of 0 reverts everything.
+pub async fn down<DB>(
+    migrator: &'static Migrator,
+    pool: &Pool<DB>,
+    target: Option<i64>,
+) -> Result<(), CommandError>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let applied = applied_versions(pool).await?;
+    let mut versions: Vec<i64> = applied.keys().copied().collect();
+    versions.sort_unstable();
+
+    let target = match target {
+        Some(target) => {
+            if target != 0 && !migrator.version_exists(target) {
+                return Err(CommandError::Invalid(format!("no migration {target}")));
+            }
+            target
+        }
+        None => match versions.as_slice() {
+            [] => {
+                println!("No migrations applied.");
+                return Ok(());
+            }
+            [.., previous, _] => *previous,
+            [_] => 0,
+        },
+    };
+
+    migrator.undo(pool, target).await?;
+
+    let mut count = 0;
+    for migration in up_migrations(migrator)
+        .rev()
+        .filter(|m| m.version > target && applied.contains_key(&m.version))
+    {
+        println!("Reverted {} {}", migration.version, migration.description);
+        count += 1;
+    }
+    if count == 0 {
+        println!("Nothing to revert.");
+    }
+    Ok(())
+}
+
+/// Lists every migration and whether it has been applied.
+pub async fn status<DB>(migrator: &'static Migrator, pool: &Pool<DB>) -> Result<(), CommandError>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let applied = applied_versions(pool).await?;
+    let dirty = pool.acquire().await?.dirty_version().await?;
+
+    let mut pending = 0;
+    for migration in up_migrations(migrator) {
+        let state = match applied.get(&migration.version) {
+            _ if dirty == Some(migration.version) => "failed",
+            Some(checksum) if *checksum != migration.checksum.as_ref() => "changed",
+            Some(_) => "applied",
+            None => {
+                pending += 1;
+                "pending"
+            }
+        };
+        println!(
+            "{:<8} {} {}",
+            state, migration.version, migration.description
+        );
+    }
+
+    // Applied versions with no file, e.g. after switching to an older build.
+    for version in applied.keys().filter(|v| !migrator.version_exists(**v)) {
+        println!("{:<8} {version} (not in this build)", "unknown");
+    }
+
+    println!();
+    println!("{pending} pending");
+    Ok(())
+}
+
+pub(crate) fn up_migrations(
+    migrator: &'static Migrator,
+) -> impl DoubleEndedIterator<Item = &'static sqlx::migrate::Migration> {
+    migrator
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+}
+
+/// Checksums of the applied migrations by version, creating sqlx's
+/// bookkeeping table on a fresh database.
+pub(crate) async fn applied_versions<DB>(
+    pool: &Pool<DB>,
+) -> Result<HashMap<i64, Vec<u8>>, CommandError>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    Ok(conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| (m.version, m.checksum.into_owned()))
+        .collect())
+}
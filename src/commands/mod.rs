@@ -0,0 +1,82 @@
+//! Subcommands of the server binary, e.g. `ai-todo migrate status`. Each one
+//! is a plain async function; argument parsing lives in `main.rs`.
+
+pub mod check_config;
+pub mod migrate;
+pub mod purge;
+pub mod seed;
+pub mod serve;
+pub mod transfer;
+
+use std::str::FromStr;
+
+use sqlx::{
+    PgPool, SqlitePool,
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+};
+use thiserror::Error;
+
+use crate::{
+    config::{Backend, Config, ConfigError},
+    error::AppError,
+};
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    /// A bad argument, or a check that did not pass.
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+
+    #[error("{}", describe(.0))]
+    App(#[from] AppError),
+
+    #[error("{0}")]
+    Io(String),
+}
+
+/// Connects to PostgreSQL, which every command but `serve`, `check-config`
+/// and `migrate` requires.
+pub async fn connect(config: &Config) -> Result<PgPool, CommandError> {
+    if config.database.backend() == Backend::Sqlite {
+        return Err(CommandError::Invalid(
+            "this command needs PostgreSQL, but database.url is a SQLite database".to_string(),
+        ));
+    }
+
+    Ok(PgPoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .connect(&config.database.url)
+        .await?)
+}
+
+/// Opens the SQLite database `database.url` names, creating the file if it
+/// does not exist.
+pub async fn connect_sqlite(config: &Config) -> Result<SqlitePool, CommandError> {
+    let options = SqliteConnectOptions::from_str(&config.database.url)?
+        .create_if_missing(true)
+        // Readers do not block the writer.
+        .journal_mode(SqliteJournalMode::Wal);
+    Ok(SqlitePoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .connect_with(options)
+        .await?)
+}
+
+/// The message of an [`AppError`], without the "Something went wrong" that
+/// API responses get.
+pub(crate) fn describe(error: &AppError) -> String {
+    match error {
+        AppError::Internal(message) => message.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,67 @@
+//! `ai-todo purge-trash`: drops what deleted todos leave behind.
+
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+
+use super::CommandError;
+use crate::state;
+
+/// Deletes, in every workspace, the tombstones of todos deleted more than
+/// `older_than_days` ago and the events of todos that no longer exist from
+/// before then. With `dry_run`, only reports what would be deleted.
+///
+/// Sync clients that last pulled before the cutoff never hear about the
+/// purged deletions, so the retention period should be longer than any
+/// client stays offline.
+pub async fn run(pool: &PgPool, older_than_days: u32, dry_run: bool) -> Result<(), CommandError> {
+    let cutoff = Utc::now() - Duration::days(older_than_days.into());
+    let workspaces = sqlx::query!("SELECT id, name FROM workspaces ORDER BY created_at")
+        .fetch_all(pool)
+        .await?;
+
+    let (mut total_tombstones, mut total_events) = (0, 0);
+    for workspace in workspaces {
+        let mut tx = pool.begin().await?;
+        state::enter_tenant(&mut tx, workspace.id).await?;
+
+        let tombstones = sqlx::query!("DELETE FROM todo_tombstones WHERE deleted_at < $1", cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        let events = sqlx::query!(
+            r#"
+            DELETE FROM todo_events e
+            WHERE e.created_at < $1
+              AND NOT EXISTS (SELECT 1 FROM todos t WHERE t.id = e.todo_id)
+            "#,
+            cutoff
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // A dry run performs the same deletes and rolls them back, so the
+        // counts are exact.
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+
+        if tombstones + events > 0 {
+            println!(
+                "{} ({}): {tombstones} tombstones, {events} events",
+                workspace.name, workspace.id
+            );
+        }
+        total_tombstones += tombstones;
+        total_events += events;
+    }
+
+    let verb = if dry_run { "Would delete" } else { "Deleted" };
+    println!(
+        "{verb} {total_tombstones} tombstones and {total_events} events older than {}.",
+        cutoff.format("%Y-%m-%d %H:%M UTC")
+    );
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! `ai-todo seed`: demo data for trying the API out.
+
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::CommandError;
+use crate::{
+    auth::{self, AuthContext},
+    models::{
+        todo::{Priority, TodoStatus},
+        token::{Scope, Scopes},
+    },
+    repositories::postgres,
+    routes::todos::{CreateTodo, UpdateTodo},
+    services::crdt::HlcClock,
+    state,
+};
+
+const DEMO_EMAIL: &str = "demo@example.com";
+
+/// Title, description, priority, status and whether it belongs to the demo
+/// project.
+const TODOS: &[(&str, Option<&str>, Priority, TodoStatus, bool)] = &[
+    (
+        "Draft the launch announcement",
+        Some("Blog post and newsletter"),
+        Priority::High,
+        TodoStatus::Doing,
+        true,
+    ),
+    (
+        "Book the venue",
+        None,
+        Priority::High,
+        TodoStatus::Done,
+        true,
+    ),
+    (
+        "Send invitations",
+        Some("Use the guest list from last year"),
+        Priority::Medium,
+        TodoStatus::Todo,
+        true,
+    ),
+    (
+        "Order t-shirts",
+        None,
+        Priority::Low,
+        TodoStatus::Todo,
+        true,
+    ),
+    (
+        "Renew passport",
+        Some("Expires in March"),
+        Priority::Medium,
+        TodoStatus::Todo,
+        false,
+    ),
+    (
+        "Water the plants",
+        None,
+        Priority::Low,
+        TodoStatus::Done,
+        false,
+    ),
+];
+
+/// Creates a demo user with their own workspace, a project and a handful of
+/// todos, and prints an API token for the user. Does nothing if the demo
+/// user already exists.
+pub async fn run(pool: &PgPool) -> Result<(), CommandError> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    let user_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO users (id, email, display_name, created_at, updated_at)
+        VALUES ($1, $2, 'Demo User', $3, $3)
+        ON CONFLICT (email) DO NOTHING
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        DEMO_EMAIL,
+        now
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(user_id) = user_id else {
+        println!("Demo data already present ({DEMO_EMAIL} exists); nothing to do.");
+        return Ok(());
+    };
+
+    let workspace_id = Uuid::new_v4();
+    sqlx::query!(
+        "INSERT INTO workspaces (id, name, created_at) VALUES ($1, 'Demo', $2)",
+        workspace_id,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO workspace_members (workspace_id, user_id, role, created_at) VALUES ($1, $2, 'Admin', $3)",
+        workspace_id,
+        user_id,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let token = auth::generate_token();
+    let scopes = Scopes(vec![
+        Scope::TodosRead,
+        Scope::TodosWrite,
+        Scope::AudioSuggest,
+    ]);
+    sqlx::query!(
+        r#"
+        INSERT INTO api_tokens (id, name, token_hash, token_prefix, scopes, created_at, user_id, workspace_id)
+        VALUES ($1, 'demo', $2, $3, $4, $5, $6, $7)
+        "#,
+        Uuid::new_v4(),
+        auth::hash_token(&token),
+        token.chars().take(12).collect::<String>(),
+        &scopes.to_strings(),
+        now,
+        user_id,
+        workspace_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Todos go through the same code as the API, so that they get events
+    // and CRDT state like any other todo.
+    state::enter_tenant(&mut tx, workspace_id).await?;
+    let ctx = AuthContext {
+        token_id: None,
+        user_id: Some(user_id),
+        workspace_id,
+        scopes,
+    };
+
+    let project_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        INSERT INTO projects (id, name, owner_id, created_at, updated_at)
+        VALUES ($1, 'Product launch', $2, $3, $3)
+        "#,
+        project_id,
+        user_id,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let clock = HlcClock::default();
+    for (title, description, priority, status, in_project) in TODOS {
+        let create = CreateTodo {
+            title: title.to_string(),
+            description: description.map(str::to_string),
+            priority: Some(priority.clone()),
+            project_id: in_project.then_some(project_id),
+        };
+        let todo = postgres::insert_todo(&mut tx, &ctx, Uuid::new_v4(), create).await?;
+        if todo.status != *status {
+            let update = UpdateTodo {
+                status: Some(status.clone()),
+                ..Default::default()
+            };
+            postgres::apply_update(&mut tx, &ctx, &clock, todo.id, update).await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    println!("Created demo user {DEMO_EMAIL} in workspace {workspace_id}");
+    println!("with 1 project and {} todos.", TODOS.len());
+    println!();
+    println!("API token (shown only once):");
+    println!("  {token}");
+    Ok(())
+}
@@ -0,0 +1,142 @@
+//! `ai-todo serve`, the default command: runs the HTTP and gRPC servers.
+
+use std::{net::SocketAddr, time::Duration};
+
+use axum::Router;
+
+use super::{
+    CommandError,
+    migrate::{MIGRATOR, SQLITE_MIGRATOR},
+};
+use crate::{
+    app,
+    config::{Backend, Config},
+    grpc, services, state,
+};
+
+pub async fn run(config: Config) -> Result<(), CommandError> {
+    if config.database.backend() == Backend::Sqlite {
+        return run_sqlite(config).await;
+    }
+
+    let pool = super::connect(&config).await?;
+
+    // Run migrations if enabled (mostly for local dev)
+    if config.database.run_migrations {
+        tracing::info!("Running migrations...");
+        MIGRATOR.run(&pool).await?;
+        tracing::info!("Migrations executed successfully.");
+    }
+
+    if config.oidc.issuer_url.is_none() {
+        tracing::info!("oidc.issuer_url not set; SSO login is disabled");
+    }
+    if config.auth.admin_token.is_none() {
+        tracing::warn!(
+            "auth.admin_token not set; only tokens stored in the database will be accepted"
+        );
+    }
+    services::webhooks::WebhookWorker::new(pool.clone(), &config.webhooks).spawn();
+
+    let http_addr = config.server.http_addr;
+    let grpc_addr = config.server.grpc_addr;
+    let shutdown_timeout = config.server.shutdown_timeout();
+    let state = state::AppState::new(pool, config)?;
+    state.todo_events.spawn_listener(state.pool.clone());
+    // Both servers stop on the same signal.
+    let (shutdown_tx, mut shutdown_rx) = tokio::sync::watch::channel(());
+    let grpc = tokio::spawn(grpc::serve(state.clone(), grpc_addr, async move {
+        let _ = shutdown_rx.changed().await;
+    }));
+
+    let app = app::create_app(state);
+    serve_http(app, http_addr, async move {
+        shutdown_signal(shutdown_timeout).await;
+        let _ = shutdown_tx.send(());
+    })
+    .await?;
+
+    if let Ok(Err(e)) = grpc.await {
+        tracing::error!("gRPC server failed: {:?}", e);
+    }
+    Ok(())
+}
+
+/// Serves todos from a SQLite file, creating and migrating it as needed.
+/// Only the todo and audio routes are available, to the bootstrap token;
+/// gRPC and the background workers, which need PostgreSQL, do not run.
+async fn run_sqlite(config: Config) -> Result<(), CommandError> {
+    if config.auth.admin_token.is_none() {
+        return Err(CommandError::Invalid(
+            "auth.admin_token (ADMIN_TOKEN) is required with SQLite, where it is the only way to sign in"
+                .to_string(),
+        ));
+    }
+    if config.oidc.issuer_url.is_some() {
+        tracing::warn!("oidc.issuer_url is ignored with SQLite");
+    }
+
+    let pool = super::connect_sqlite(&config).await?;
+    SQLITE_MIGRATOR.run(&pool).await?;
+
+    let http_addr = config.server.http_addr;
+    let shutdown_timeout = config.server.shutdown_timeout();
+    let state = state::AppState::new_sqlite(pool, config)?;
+    tracing::info!("Storing todos in SQLite; gRPC is disabled");
+
+    let app = app::create_app(state);
+    serve_http(app, http_addr, shutdown_signal(shutdown_timeout)).await
+}
+
+async fn serve_http(
+    app: Router,
+    addr: SocketAddr,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), CommandError> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| CommandError::Io(format!("cannot listen on {addr}: {e}")))?;
+
+    tracing::info!("Listening on {}", addr);
+
+    // Peer addresses are needed to rate limit anonymous requests.
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown)
+    .await
+    .map_err(|e| CommandError::Io(format!("HTTP server failed: {e}")))
+}
+
+async fn shutdown_signal(timeout: Duration) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+
+    eprintln!("signal received, starting graceful shutdown");
+
+    tokio::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        eprintln!("shutdown timed out, forcing exit");
+        std::process::exit(1);
+    });
+}
@@ -0,0 +1,197 @@
+//! `ai-todo export` and `ai-todo import`: copies workspaces with their
+//! projects and todos to and from a JSON file.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::CommandError;
+use crate::{models::todo::Todo, state};
+
+/// Bumped whenever the file layout changes incompatibly.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Export {
+    version: u32,
+    exported_at: DateTime<Utc>,
+    workspaces: Vec<ExportedWorkspace>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedWorkspace {
+    id: Uuid,
+    name: String,
+    created_at: DateTime<Utc>,
+    projects: Vec<ExportedProject>,
+    todos: Vec<Todo>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedProject {
+    id: Uuid,
+    name: String,
+    owner_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Writes the given workspaces, or all of them, to `path`; `-` is stdout.
+pub async fn export(pool: &PgPool, path: &Path, workspaces: &[Uuid]) -> Result<(), CommandError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, name, created_at FROM workspaces
+        WHERE cardinality($1::uuid[]) = 0 OR id = ANY ($1)
+        ORDER BY created_at
+        "#,
+        workspaces
+    )
+    .fetch_all(pool)
+    .await?;
+    if let Some(missing) = workspaces
+        .iter()
+        .find(|id| !rows.iter().any(|row| row.id == **id))
+    {
+        return Err(CommandError::Invalid(format!("no workspace {missing}")));
+    }
+
+    let mut export = Export {
+        version: FORMAT_VERSION,
+        exported_at: Utc::now(),
+        workspaces: Vec::with_capacity(rows.len()),
+    };
+    for row in rows {
+        // Tenant tables are only readable from inside their workspace.
+        let mut tx = pool.begin().await?;
+        state::enter_tenant(&mut tx, row.id).await?;
+
+        let projects = sqlx::query_as!(
+            ExportedProject,
+            "SELECT id, name, owner_id, created_at, updated_at FROM projects ORDER BY created_at"
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let todos = sqlx::query_as!(
+            Todo,
+            r#"
+            SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+            FROM todos
+            ORDER BY created_at
+            "#
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        eprintln!(
+            "Exporting {} ({}): {} projects, {} todos",
+            row.name,
+            row.id,
+            projects.len(),
+            todos.len()
+        );
+        export.workspaces.push(ExportedWorkspace {
+            id: row.id,
+            name: row.name,
+            created_at: row.created_at,
+            projects,
+            todos,
+        });
+    }
+
+    // Serializing our own types cannot fail.
+    let json = serde_json::to_string_pretty(&export).unwrap() + "\n";
+    if path == Path::new("-") {
+        print!("{json}");
+        Ok(())
+    } else {
+        std::fs::write(path, json).map_err(|e| CommandError::Io(format!("{}: {e}", path.display())))
+    }
+}
+
+/// Loads a file written by [`export`]. Workspaces, projects and todos that
+/// already exist are left alone, so importing the same file twice is safe.
+/// Users are not part of exports: owners missing from this database are
+/// dropped, leaving the project or todo unowned.
+pub async fn import(pool: &PgPool, path: &Path) -> Result<(), CommandError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| CommandError::Io(format!("{}: {e}", path.display())))?;
+    let export: Export = serde_json::from_str(&contents)
+        .map_err(|e| CommandError::Io(format!("{}: not an export file: {e}", path.display())))?;
+    if export.version != FORMAT_VERSION {
+        return Err(CommandError::Io(format!(
+            "{}: unsupported export version {}",
+            path.display(),
+            export.version
+        )));
+    }
+
+    for workspace in export.workspaces {
+        let mut tx = pool.begin().await?;
+        sqlx::query!(
+            "INSERT INTO workspaces (id, name, created_at) VALUES ($1, $2, $3) ON CONFLICT (id) DO NOTHING",
+            workspace.id,
+            workspace.name,
+            workspace.created_at
+        )
+        .execute(&mut *tx)
+        .await?;
+        state::enter_tenant(&mut tx, workspace.id).await?;
+
+        let mut projects = 0;
+        for project in &workspace.projects {
+            projects += sqlx::query!(
+                r#"
+                INSERT INTO projects (id, name, owner_id, created_at, updated_at)
+                VALUES ($1, $2, (SELECT id FROM users WHERE id = $3), $4, $5)
+                ON CONFLICT (id) DO NOTHING
+                "#,
+                project.id,
+                project.name,
+                project.owner_id,
+                project.created_at,
+                project.updated_at
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        let mut todos = 0;
+        for todo in &workspace.todos {
+            todos += sqlx::query!(
+                r#"
+                INSERT INTO todos (id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, (SELECT id FROM users WHERE id = $7), $8, $9, $10)
+                ON CONFLICT (id) DO NOTHING
+                "#,
+                todo.id,
+                todo.title,
+                todo.description,
+                todo.status.to_string(),
+                todo.priority.to_string(),
+                todo.source.to_string(),
+                todo.owner_id,
+                todo.project_id,
+                todo.created_at,
+                todo.updated_at
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+
+        println!(
+            "Imported {} ({}): {projects} of {} projects, {todos} of {} todos",
+            workspace.name,
+            workspace.id,
+            workspace.projects.len(),
+            workspace.todos.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,482 @@
+//! Server configuration: built-in defaults, overridden by an optional TOML
+//! file, overridden in turn by environment variables. See
+//! `ai-todo.example.toml` for every setting.
+
+use std::{
+    env, fmt,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Read when no file is given with `--config` or `AI_TODO_CONFIG`, if it
+/// exists.
+pub const DEFAULT_PATH: &str = "ai-todo.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub gemini: GeminiConfig,
+    pub auth: AuthConfig,
+    pub oidc: OidcConfig,
+    pub rate_limits: RateLimitConfig,
+    pub webhooks: WebhookConfig,
+    pub telemetry: TelemetryConfig,
+    pub log: LogConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// `HTTP_ADDR`
+    pub http_addr: SocketAddr,
+    /// `GRPC_ADDR`
+    pub grpc_addr: SocketAddr,
+    /// `SHUTDOWN_TIMEOUT`: seconds in-flight requests get to finish after a
+    /// shutdown signal.
+    pub shutdown_timeout_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            http_addr: SocketAddr::from(([127, 0, 0, 1], 5000)),
+            grpc_addr: SocketAddr::from(([127, 0, 0, 1], 50051)),
+            shutdown_timeout_secs: 1,
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout_secs)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// `DATABASE_URL`; required. A `sqlite:` URL selects SQLite storage.
+    pub url: String,
+    /// `DATABASE_MAX_CONNECTIONS`
+    pub max_connections: u32,
+    /// `RUN_MIGRATIONS`: apply pending migrations when the server starts.
+    pub run_migrations: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            max_connections: 5,
+            run_migrations: false,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn backend(&self) -> Backend {
+        if self.url.starts_with("sqlite:") {
+            Backend::Sqlite
+        } else {
+            Backend::Postgres
+        }
+    }
+}
+
+/// Where the server keeps its data, chosen by the scheme of `database.url`.
+/// SQLite holds only todos and serves a single user; see `docs/SERVER.md`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Postgres,
+    Sqlite,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Postgres => write!(f, "PostgreSQL"),
+            Backend::Sqlite => write!(f, "SQLite"),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeminiConfig {
+    /// `GOOGLE_API_KEY`; required to serve, but not by the other commands.
+    pub api_key: Option<String>,
+    /// `GEMINI_MODEL`
+    pub model: String,
+}
+
+impl Default for GeminiConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            model: "gemini-2.5-flash".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// `ADMIN_TOKEN`: bootstrap token granted every scope.
+    pub admin_token: Option<String>,
+}
+
+/// SSO is enabled when `issuer_url` is set.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcConfig {
+    /// `OIDC_ISSUER_URL`
+    pub issuer_url: Option<String>,
+    /// `OIDC_CLIENT_ID`; required with `issuer_url`.
+    pub client_id: Option<String>,
+    /// `OIDC_CLIENT_SECRET`; omit for public clients.
+    pub client_secret: Option<String>,
+    /// `OIDC_REDIRECT_URL`; required with `issuer_url`.
+    pub redirect_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// `RATE_LIMIT_PER_MINUTE`: requests per client per minute.
+    pub per_minute: u32,
+    /// `RATE_LIMIT_AI_PER_MINUTE`: requests per client per minute to routes
+    /// that call the AI model.
+    pub ai_per_minute: u32,
+    /// `AI_DAILY_QUOTA`: AI calls per client per UTC day.
+    pub ai_daily_quota: i32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            per_minute: 120,
+            ai_per_minute: 10,
+            ai_daily_quota: 200,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// `WEBHOOKS_ALLOW_LOOPBACK`: lets webhooks point at this host, for
+    /// testing against a local receiver. Private and link-local addresses are
+    /// refused regardless.
+    pub allow_loopback: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT`: base URL of an OTLP/HTTP collector,
+    /// e.g. `http://localhost:4318`. Traces are only exported when set.
+    pub otlp_endpoint: Option<String>,
+    /// `OTEL_SERVICE_NAME`
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "ai-todo".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// `LOG_FORMAT`
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line, for log aggregators.
+    Json,
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("Invalid log format: {s}")),
+        }
+    }
+}
+
+// Hand-written so that secrets never end up in logs.
+impl fmt::Debug for DatabaseConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatabaseConfig")
+            .field("url", &redact(Some(&self.url)))
+            .field("max_connections", &self.max_connections)
+            .field("run_migrations", &self.run_migrations)
+            .finish()
+    }
+}
+
+impl fmt::Debug for GeminiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GeminiConfig")
+            .field("api_key", &redact(self.api_key.as_ref()))
+            .field("model", &self.model)
+            .finish()
+    }
+}
+
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("admin_token", &redact(self.admin_token.as_ref()))
+            .finish()
+    }
+}
+
+impl fmt::Debug for OidcConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OidcConfig")
+            .field("issuer_url", &self.issuer_url)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &redact(self.client_secret.as_ref()))
+            .field("redirect_url", &self.redirect_url)
+            .finish()
+    }
+}
+
+fn redact(value: Option<&String>) -> Option<&'static str> {
+    value.filter(|v| !v.is_empty()).map(|_| "<redacted>")
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("{}: {message}", .path.display())]
+    File { path: PathBuf, message: String },
+
+    /// Every problem found, one per line.
+    #[error("invalid configuration:\n  {}", .0.join("\n  "))]
+    Invalid(Vec<String>),
+}
+
+impl Config {
+    /// Loads `path`, or [`DEFAULT_PATH`] if it exists when `path` is `None`,
+    /// applies environment variables on top and validates the result.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_PATH).exists() => Self::from_file(Path::new(DEFAULT_PATH))?,
+            None => Self::default(),
+        };
+
+        let mut errors = Vec::new();
+        config.apply_env(&mut errors);
+        config.validate(&mut errors);
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let file_error = |message: String| ConfigError::File {
+            path: path.to_path_buf(),
+            message,
+        };
+        let contents = std::fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+        toml::from_str(&contents).map_err(|e| file_error(e.to_string()))
+    }
+
+    fn apply_env(&mut self, errors: &mut Vec<String>) {
+        let mut env = EnvLayer { errors };
+
+        env.parse("HTTP_ADDR", &mut self.server.http_addr);
+        env.parse("GRPC_ADDR", &mut self.server.grpc_addr);
+        env.parse("SHUTDOWN_TIMEOUT", &mut self.server.shutdown_timeout_secs);
+
+        env.parse("DATABASE_URL", &mut self.database.url);
+        env.parse(
+            "DATABASE_MAX_CONNECTIONS",
+            &mut self.database.max_connections,
+        );
+        env.parse("RUN_MIGRATIONS", &mut self.database.run_migrations);
+
+        env.optional("GOOGLE_API_KEY", &mut self.gemini.api_key);
+        env.parse("GEMINI_MODEL", &mut self.gemini.model);
+
+        env.optional("ADMIN_TOKEN", &mut self.auth.admin_token);
+
+        env.optional("OIDC_ISSUER_URL", &mut self.oidc.issuer_url);
+        env.optional("OIDC_CLIENT_ID", &mut self.oidc.client_id);
+        env.optional("OIDC_CLIENT_SECRET", &mut self.oidc.client_secret);
+        env.optional("OIDC_REDIRECT_URL", &mut self.oidc.redirect_url);
+
+        env.parse("RATE_LIMIT_PER_MINUTE", &mut self.rate_limits.per_minute);
+        env.parse(
+            "RATE_LIMIT_AI_PER_MINUTE",
+            &mut self.rate_limits.ai_per_minute,
+        );
+        env.parse("AI_DAILY_QUOTA", &mut self.rate_limits.ai_daily_quota);
+
+        env.parse("WEBHOOKS_ALLOW_LOOPBACK", &mut self.webhooks.allow_loopback);
+
+        env.optional(
+            "OTEL_EXPORTER_OTLP_ENDPOINT",
+            &mut self.telemetry.otlp_endpoint,
+        );
+        env.parse("OTEL_SERVICE_NAME", &mut self.telemetry.service_name);
+
+        env.parse("LOG_FORMAT", &mut self.log.format);
+    }
+
+    fn validate(&mut self, errors: &mut Vec<String>) {
+        // Empty strings mean "unset", whether they came from the file or the
+        // environment.
+        for value in [
+            &mut self.gemini.api_key,
+            &mut self.auth.admin_token,
+            &mut self.oidc.issuer_url,
+            &mut self.oidc.client_id,
+            &mut self.oidc.client_secret,
+            &mut self.oidc.redirect_url,
+            &mut self.telemetry.otlp_endpoint,
+        ] {
+            if value.as_deref().is_some_and(|v| v.trim().is_empty()) {
+                *value = None;
+            }
+        }
+
+        if self.database.url.is_empty() {
+            errors.push("database.url (DATABASE_URL) is required".to_string());
+        }
+        if self.database.max_connections == 0 {
+            errors.push(
+                "database.max_connections (DATABASE_MAX_CONNECTIONS) must be at least 1"
+                    .to_string(),
+            );
+        }
+        if self.gemini.model.is_empty() {
+            errors.push("gemini.model (GEMINI_MODEL) cannot be empty".to_string());
+        }
+        if self.rate_limits.per_minute == 0 {
+            errors.push(
+                "rate_limits.per_minute (RATE_LIMIT_PER_MINUTE) must be at least 1".to_string(),
+            );
+        }
+        if self.rate_limits.ai_per_minute == 0 {
+            errors.push(
+                "rate_limits.ai_per_minute (RATE_LIMIT_AI_PER_MINUTE) must be at least 1"
+                    .to_string(),
+            );
+        }
+        if self.rate_limits.ai_daily_quota < 0 {
+            errors
+                .push("rate_limits.ai_daily_quota (AI_DAILY_QUOTA) cannot be negative".to_string());
+        }
+        if self.telemetry.service_name.is_empty() {
+            errors.push("telemetry.service_name (OTEL_SERVICE_NAME) cannot be empty".to_string());
+        }
+        if self.oidc.issuer_url.is_some() {
+            if self.oidc.client_id.is_none() {
+                errors.push(
+                    "oidc.client_id (OIDC_CLIENT_ID) is required when oidc.issuer_url is set"
+                        .to_string(),
+                );
+            }
+            if self.oidc.redirect_url.is_none() {
+                errors.push(
+                    "oidc.redirect_url (OIDC_REDIRECT_URL) is required when oidc.issuer_url is set"
+                        .to_string(),
+                );
+            }
+        }
+    }
+}
+
+/// Applies environment variables over values from the file, collecting
+/// parse errors instead of stopping at the first.
+struct EnvLayer<'a> {
+    errors: &'a mut Vec<String>,
+}
+
+impl EnvLayer<'_> {
+    fn parse<T: Setting>(&mut self, name: &str, target: &mut T) {
+        let Ok(value) = env::var(name) else {
+            return;
+        };
+        match value.trim().parse() {
+            Ok(parsed) => *target = parsed,
+            Err(_) => self
+                .errors
+                .push(format!("{name}: expected {}, got {value:?}", T::EXPECTED)),
+        }
+    }
+
+    fn optional(&mut self, name: &str, target: &mut Option<String>) {
+        if let Ok(value) = env::var(name) {
+            *target = Some(value);
+        }
+    }
+}
+
+/// A value that can be set from an environment variable.
+trait Setting: FromStr {
+    /// What the value should look like, for error messages.
+    const EXPECTED: &'static str;
+}
+
+impl Setting for SocketAddr {
+    const EXPECTED: &'static str = "an address such as 127.0.0.1:5000";
+}
+
+impl Setting for String {
+    const EXPECTED: &'static str = "text";
+}
+
+impl Setting for bool {
+    const EXPECTED: &'static str = "true or false";
+}
+
+impl Setting for u32 {
+    const EXPECTED: &'static str = "a whole number";
+}
+
+impl Setting for u64 {
+    const EXPECTED: &'static str = "a whole number";
+}
+
+impl Setting for i32 {
+    const EXPECTED: &'static str = "a whole number";
+}
+
+impl Setting for LogFormat {
+    const EXPECTED: &'static str = "text or json";
+}
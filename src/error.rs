@@ -1,20 +1,27 @@
 use axum::{
     Json,
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use thiserror::Error;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize)]
+use crate::{models::token::Scope, request_id};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub message: String,
     pub status: u16,
     pub errors: HashMap<String, Vec<String>>,
+    /// The `X-Request-Id` of the failed request; quote it when reporting a
+    /// problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum AppError {
     #[error("Resource not found")]
     NotFound,
@@ -23,13 +30,40 @@ pub enum AppError {
     #[error("Validation failed")]
     InvalidInput(HashMap<String, Vec<String>>),
 
+    #[error("Authentication required")]
+    Unauthorized,
+
+    #[error("Forbidden")]
+    Forbidden,
+
+    #[error("Missing required scope: {0}")]
+    InsufficientScope(Scope),
+
+    /// The request clashes with the current state of the resource.
+    #[error("{0}")]
+    Conflict(String),
+
+    /// The client exceeded a rate limit or quota and may retry after
+    /// `retry_after` seconds.
+    #[error("{message}")]
+    TooManyRequests { message: String, retry_after: u64 },
+
     #[error("Something went wrong: {0}")]
     Internal(String),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, message, errors) = match self {
+impl AppError {
+    /// Shorthand for a validation error on a single field.
+    pub fn invalid_field(field: &str, message: &str) -> Self {
+        let mut errors = HashMap::new();
+        errors.insert(field.to_string(), vec![message.to_string()]);
+        AppError::InvalidInput(errors)
+    }
+}
+
+impl From<AppError> for ErrorResponse {
+    fn from(error: AppError) -> Self {
+        let (status, message, errors) = match error {
             AppError::InvalidInput(errs) => (
                 StatusCode::BAD_REQUEST,
                 "Validation failed".to_string(),
@@ -40,14 +74,59 @@ impl IntoResponse for AppError {
                 "Resource not found".to_string(),
                 HashMap::new(),
             ),
+            AppError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                "Authentication required".to_string(),
+                HashMap::new(),
+            ),
+            AppError::Forbidden => (
+                StatusCode::FORBIDDEN,
+                "You do not have permission to perform this action".to_string(),
+                HashMap::new(),
+            ),
+            AppError::InsufficientScope(scope) => (
+                StatusCode::FORBIDDEN,
+                format!("Missing required scope: {scope}"),
+                HashMap::new(),
+            ),
+            AppError::Conflict(message) => (StatusCode::CONFLICT, message, HashMap::new()),
+            AppError::TooManyRequests { message, .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, message, HashMap::new())
+            }
             AppError::Internal(s) => (StatusCode::INTERNAL_SERVER_ERROR, s, HashMap::new()),
         };
 
-        let body = Json(ErrorResponse {
+        ErrorResponse {
             message,
             status: status.as_u16(),
             errors,
-        });
+            request_id: request_id::current(),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let retry_after = match &self {
+            AppError::TooManyRequests { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        };
+        let body = ErrorResponse::from(self);
+        let status = StatusCode::from_u16(body.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = Json(body);
+
+        if let Some(retry_after) = retry_after {
+            return (
+                status,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                body,
+            )
+                .into_response();
+        }
+
+        if status == StatusCode::UNAUTHORIZED {
+            return (status, [(header::WWW_AUTHENTICATE, "Bearer")], body).into_response();
+        }
 
         (status, body).into_response()
     }
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use async_graphql::{ErrorExtensions, dataloader::Loader};
+use uuid::Uuid;
+
+use super::types::UserObject;
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    models::{project::Project, share::Member, todo::Todo},
+    state::AppState,
+};
+
+fn db_err(context: &'static str) -> impl Fn(sqlx::Error) -> async_graphql::Error {
+    move |e| {
+        tracing::error!("Failed to {}: {:?}", context, e);
+        AppError::Internal(format!("failed to {context}")).extend()
+    }
+}
+
+/// Projects by ID, with the caller's role. Projects the caller cannot see are
+/// left out.
+pub struct ProjectLoader {
+    state: AppState,
+    auth: AuthContext,
+}
+
+impl ProjectLoader {
+    pub fn new(state: &AppState, auth: &AuthContext) -> Self {
+        Self {
+            state: state.clone(),
+            auth: auth.clone(),
+        }
+    }
+}
+
+impl Loader<Uuid> for ProjectLoader {
+    type Value = Project;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Project>, Self::Error> {
+        let mut tx = self
+            .state
+            .tenant(&self.auth)
+            .await
+            .map_err(|e| e.extend())?;
+
+        let projects = sqlx::query_as!(
+            Project,
+            r#"
+            SELECT id, name, owner_id, COALESCE(project_role(id, $2), 'Owner') AS "role!", created_at, updated_at
+            FROM projects
+            WHERE id = ANY($1)
+              AND ($2::uuid IS NULL OR project_role(id, $2) IS NOT NULL)
+            "#,
+            keys,
+            self.auth.user_id
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_err("load projects"))?;
+
+        Ok(projects.into_iter().map(|p| (p.id, p)).collect())
+    }
+}
+
+/// The todos the caller can see in each project, newest first.
+pub struct ProjectTodosLoader {
+    state: AppState,
+    auth: AuthContext,
+}
+
+impl ProjectTodosLoader {
+    pub fn new(state: &AppState, auth: &AuthContext) -> Self {
+        Self {
+            state: state.clone(),
+            auth: auth.clone(),
+        }
+    }
+}
+
+impl Loader<Uuid> for ProjectTodosLoader {
+    type Value = Vec<Todo>;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Vec<Todo>>, Self::Error> {
+        let mut tx = self
+            .state
+            .tenant(&self.auth)
+            .await
+            .map_err(|e| e.extend())?;
+
+        let todos = sqlx::query_as!(
+            Todo,
+            r#"
+            SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+            FROM todos
+            WHERE project_id = ANY($1)
+              AND ($2::uuid IS NULL OR todo_role(id, $2) IS NOT NULL)
+            ORDER BY created_at DESC
+            "#,
+            keys,
+            self.auth.user_id
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_err("load project todos"))?;
+
+        let mut by_project: HashMap<Uuid, Vec<Todo>> = HashMap::new();
+        for todo in todos {
+            if let Some(project_id) = todo.project_id {
+                by_project.entry(project_id).or_default().push(todo);
+            }
+        }
+        Ok(by_project)
+    }
+}
+
+/// Whose members to load. Only requested for projects and todos the caller
+/// has already been allowed to see.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MembersOf {
+    Project(Uuid),
+    Todo(Uuid),
+}
+
+/// The owner and share holders of projects and todos, as listed by the
+/// `/members` routes.
+pub struct MembersLoader {
+    state: AppState,
+    auth: AuthContext,
+}
+
+impl MembersLoader {
+    pub fn new(state: &AppState, auth: &AuthContext) -> Self {
+        Self {
+            state: state.clone(),
+            auth: auth.clone(),
+        }
+    }
+}
+
+impl Loader<MembersOf> for MembersLoader {
+    type Value = Vec<Member>;
+    type Error = async_graphql::Error;
+
+    async fn load(
+        &self,
+        keys: &[MembersOf],
+    ) -> Result<HashMap<MembersOf, Vec<Member>>, Self::Error> {
+        let (mut project_ids, mut todo_ids) = (Vec::new(), Vec::new());
+        for key in keys {
+            match *key {
+                MembersOf::Project(id) => project_ids.push(id),
+                MembersOf::Todo(id) => todo_ids.push(id),
+            }
+        }
+
+        let mut tx = self
+            .state
+            .tenant(&self.auth)
+            .await
+            .map_err(|e| e.extend())?;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT p.id AS "project_id?", NULL::uuid AS "todo_id?",
+                   u.id AS "user_id!", u.email AS "email?", u.display_name AS "display_name?", 'Owner' AS "role!"
+            FROM projects p JOIN users u ON u.id = p.owner_id
+            WHERE p.id = ANY($1)
+            UNION ALL
+            SELECT NULL, t.id, u.id, u.email, u.display_name, 'Owner'
+            FROM todos t JOIN users u ON u.id = t.owner_id
+            WHERE t.id = ANY($2)
+            UNION ALL
+            SELECT s.project_id, s.todo_id, u.id, u.email, u.display_name, s.role
+            FROM shares s JOIN users u ON u.id = s.user_id
+            WHERE s.project_id = ANY($1) OR s.todo_id = ANY($2)
+            "#,
+            &project_ids,
+            &todo_ids
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_err("load members"))?;
+
+        let mut members: HashMap<MembersOf, Vec<Member>> = HashMap::new();
+        for row in rows {
+            let key = match (row.project_id, row.todo_id) {
+                (Some(id), _) => MembersOf::Project(id),
+                (None, Some(id)) => MembersOf::Todo(id),
+                (None, None) => continue,
+            };
+            members.entry(key).or_default().push(Member {
+                user_id: row.user_id,
+                email: row.email,
+                display_name: row.display_name,
+                role: row.role.into(),
+            });
+        }
+        Ok(members)
+    }
+}
+
+/// Users by ID, limited to members of the caller's workspace.
+pub struct UserLoader {
+    state: AppState,
+    workspace_id: Uuid,
+}
+
+impl UserLoader {
+    pub fn new(state: &AppState, auth: &AuthContext) -> Self {
+        Self {
+            state: state.clone(),
+            workspace_id: auth.workspace_id,
+        }
+    }
+}
+
+impl Loader<Uuid> for UserLoader {
+    type Value = UserObject;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, UserObject>, Self::Error> {
+        let users = sqlx::query_as!(
+            UserObject,
+            r#"
+            SELECT u.id, u.email, u.display_name
+            FROM users u
+            JOIN workspace_members m ON m.user_id = u.id AND m.workspace_id = $2
+            WHERE u.id = ANY($1)
+            "#,
+            keys,
+            self.workspace_id
+        )
+        .fetch_all(&self.state.pool)
+        .await
+        .map_err(db_err("load users"))?;
+
+        Ok(users.into_iter().map(|u| (u.id, u)).collect())
+    }
+}
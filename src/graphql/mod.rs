@@ -0,0 +1,101 @@
+//! GraphQL API over the same data and permission rules as the REST routes.
+//! Every resolver runs on the caller's tenant transaction; relations are
+//! fetched through per-request [`DataLoader`]s so a list of todos costs one
+//! query per relation rather than one per todo.
+
+mod loaders;
+mod mutation;
+mod query;
+mod subscription;
+mod types;
+
+use async_graphql::{Context, Data, ErrorExtensions, Schema, dataloader::DataLoader};
+
+use crate::{auth::AuthContext, error::AppError, models::token::Scope, state::AppState};
+
+pub use mutation::MutationRoot;
+pub use query::QueryRoot;
+pub use subscription::SubscriptionRoot;
+
+pub type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
+
+/// Deepest selection a query may nest, e.g. `todos { project { todos { ... } } }`.
+const MAX_DEPTH: usize = 8;
+/// Each field costs 1; list fields multiply the cost of their selection by
+/// 5 (members) or 10 (todos, projects).
+const MAX_COMPLEXITY: usize = 2000;
+
+pub fn build_schema(state: AppState) -> AppSchema {
+    Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(state)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Data attached to each request or subscription connection: the caller and
+/// their loaders. Loaders do not cache, so they are safe to keep for the
+/// lifetime of a WebSocket.
+pub fn request_data(state: &AppState, auth: AuthContext) -> Data {
+    let mut data = Data::default();
+    data.insert(DataLoader::new(
+        loaders::ProjectLoader::new(state, &auth),
+        tokio::spawn,
+    ));
+    data.insert(DataLoader::new(
+        loaders::ProjectTodosLoader::new(state, &auth),
+        tokio::spawn,
+    ));
+    data.insert(DataLoader::new(
+        loaders::MembersLoader::new(state, &auth),
+        tokio::spawn,
+    ));
+    data.insert(DataLoader::new(
+        loaders::UserLoader::new(state, &auth),
+        tokio::spawn,
+    ));
+    data.insert(auth);
+    data
+}
+
+fn app_state<'a>(ctx: &Context<'a>) -> &'a AppState {
+    ctx.data_unchecked::<AppState>()
+}
+
+/// The caller, provided they hold `scope`.
+fn authorize<'a>(ctx: &Context<'a>, scope: Scope) -> async_graphql::Result<&'a AuthContext> {
+    let auth = ctx
+        .data::<AuthContext>()
+        .map_err(|_| AppError::Unauthorized.extend())?;
+    if !auth.has_scope(scope) {
+        return Err(AppError::InsufficientScope(scope).extend());
+    }
+    Ok(auth)
+}
+
+/// Errors carry the REST status and field errors as extensions, e.g.
+/// `{"code": "NOT_FOUND", "status": 404}`.
+impl ErrorExtensions for AppError {
+    fn extend(&self) -> async_graphql::Error {
+        let code = match self {
+            AppError::NotFound => "NOT_FOUND",
+            AppError::InvalidInput(_) => "BAD_USER_INPUT",
+            AppError::Unauthorized => "UNAUTHENTICATED",
+            AppError::Forbidden | AppError::InsufficientScope(_) => "FORBIDDEN",
+            AppError::Conflict(_) => "CONFLICT",
+            AppError::TooManyRequests { .. } => "TOO_MANY_REQUESTS",
+            AppError::Internal(_) => "INTERNAL_SERVER_ERROR",
+        };
+        let body = crate::error::ErrorResponse::from(self.clone());
+
+        async_graphql::Error::new(body.message).extend_with(|_, e| {
+            e.set("code", code);
+            e.set("status", body.status);
+            if !body.errors.is_empty()
+                && let Ok(errors) = async_graphql::to_value(&body.errors)
+            {
+                e.set("errors", errors);
+            }
+        })
+    }
+}
@@ -0,0 +1,87 @@
+use async_graphql::{Context, Object, ResultExt};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::{
+    app_state, authorize,
+    types::{CreateTodoInput, TodoObject, UpdateTodoInput},
+};
+use crate::{
+    error::AppError,
+    models::token::Scope,
+    repositories::postgres,
+    routes::todos::{CreateTodo, UpdateTodo},
+    validator::validation_error,
+};
+
+pub struct MutationRoot;
+
+fn commit_err(context: &'static str) -> impl Fn(sqlx::Error) -> AppError {
+    move |e| {
+        tracing::error!("Failed to commit {}: {:?}", context, e);
+        AppError::Internal(format!("failed to {context}"))
+    }
+}
+
+/// Mutations go through the same code as the REST routes, so they record
+/// events, queue webhooks and merge with concurrent edits in the same way.
+#[Object]
+impl MutationRoot {
+    async fn create_todo(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateTodoInput,
+    ) -> async_graphql::Result<TodoObject> {
+        let auth = authorize(ctx, Scope::TodosWrite)?;
+        let payload = CreateTodo::from(input);
+        payload.validate().map_err(validation_error).extend()?;
+
+        let mut tx = app_state(ctx).tenant(auth).await.extend()?;
+        let todo = postgres::insert_todo(&mut tx, auth, Uuid::new_v4(), payload)
+            .await
+            .extend()?;
+        tx.commit()
+            .await
+            .map_err(commit_err("create todo"))
+            .extend()?;
+
+        Ok(TodoObject(todo))
+    }
+
+    async fn update_todo(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        input: UpdateTodoInput,
+    ) -> async_graphql::Result<TodoObject> {
+        let auth = authorize(ctx, Scope::TodosWrite)?;
+        let payload = UpdateTodo::from(input);
+        payload.validate().map_err(validation_error).extend()?;
+
+        let state = app_state(ctx);
+        let mut tx = state.tenant(auth).await.extend()?;
+        let todo = postgres::apply_update(&mut tx, auth, &state.clock, id, payload)
+            .await
+            .extend()?;
+        tx.commit()
+            .await
+            .map_err(commit_err("update todo"))
+            .extend()?;
+
+        Ok(TodoObject(todo))
+    }
+
+    /// Returns the todo as it was before being deleted.
+    async fn delete_todo(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<TodoObject> {
+        let auth = authorize(ctx, Scope::TodosWrite)?;
+
+        let mut tx = app_state(ctx).tenant(auth).await.extend()?;
+        let todo = postgres::remove_todo(&mut tx, auth, id).await.extend()?;
+        tx.commit()
+            .await
+            .map_err(commit_err("delete todo"))
+            .extend()?;
+
+        Ok(TodoObject(todo))
+    }
+}
@@ -0,0 +1,130 @@
+use async_graphql::{Context, Object, ResultExt, dataloader::DataLoader};
+use uuid::Uuid;
+
+use super::{
+    app_state, authorize,
+    loaders::{ProjectLoader, UserLoader},
+    types::{ProjectObject, TodoObject, TodoStatusEnum, UserObject},
+};
+use crate::{
+    error::AppError,
+    models::{project::Project, todo::Todo, token::Scope},
+};
+
+pub struct QueryRoot;
+
+fn db_err(context: &'static str) -> impl Fn(sqlx::Error) -> AppError {
+    move |e| {
+        tracing::error!("Failed to {}: {:?}", context, e);
+        AppError::Internal(format!("failed to {context}"))
+    }
+}
+
+#[Object]
+impl QueryRoot {
+    /// The authenticated user; null for service-account tokens.
+    async fn me(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<UserObject>> {
+        let auth = authorize(ctx, Scope::TodosRead)?;
+        let Some(user_id) = auth.user_id else {
+            return Ok(None);
+        };
+        ctx.data_unchecked::<DataLoader<UserLoader>>()
+            .load_one(user_id)
+            .await
+    }
+
+    /// The todos the caller can see, newest first.
+    #[graphql(complexity = "10 * child_complexity")]
+    async fn todos(
+        &self,
+        ctx: &Context<'_>,
+        project_id: Option<Uuid>,
+        status: Option<TodoStatusEnum>,
+    ) -> async_graphql::Result<Vec<TodoObject>> {
+        let auth = authorize(ctx, Scope::TodosRead)?;
+        let mut tx = app_state(ctx).tenant(auth).await.extend()?;
+        let status = status.map(|s| crate::models::todo::TodoStatus::from(s).to_string());
+
+        let todos = sqlx::query_as!(
+            Todo,
+            r#"
+            SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+            FROM todos
+            WHERE ($1::uuid IS NULL OR todo_role(id, $1) IS NOT NULL)
+              AND ($2::uuid IS NULL OR project_id = $2)
+              AND ($3::text IS NULL OR status = $3)
+            ORDER BY created_at DESC
+            "#,
+            auth.user_id,
+            project_id,
+            status
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_err("list todos"))
+        .extend()?;
+
+        Ok(todos.into_iter().map(TodoObject).collect())
+    }
+
+    /// A todo by ID, or null if it does not exist or is hidden from the caller.
+    async fn todo(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Option<TodoObject>> {
+        let auth = authorize(ctx, Scope::TodosRead)?;
+        let mut tx = app_state(ctx).tenant(auth).await.extend()?;
+
+        let todo = sqlx::query_as!(
+            Todo,
+            r#"
+            SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+            FROM todos
+            WHERE id = $1 AND ($2::uuid IS NULL OR todo_role(id, $2) IS NOT NULL)
+            "#,
+            id,
+            auth.user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err("get todo"))
+        .extend()?;
+
+        Ok(todo.map(TodoObject))
+    }
+
+    /// The projects the caller owns or that have been shared with them.
+    #[graphql(complexity = "10 * child_complexity")]
+    async fn projects(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<ProjectObject>> {
+        let auth = authorize(ctx, Scope::TodosRead)?;
+        let mut tx = app_state(ctx).tenant(auth).await.extend()?;
+
+        let projects = sqlx::query_as!(
+            Project,
+            r#"
+            SELECT id, name, owner_id, COALESCE(project_role(id, $1), 'Owner') AS "role!", created_at, updated_at
+            FROM projects
+            WHERE $1::uuid IS NULL OR project_role(id, $1) IS NOT NULL
+            ORDER BY created_at DESC
+            "#,
+            auth.user_id
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_err("list projects"))
+        .extend()?;
+
+        Ok(projects.into_iter().map(ProjectObject).collect())
+    }
+
+    /// A project by ID, or null if it does not exist or is hidden from the caller.
+    async fn project(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+    ) -> async_graphql::Result<Option<ProjectObject>> {
+        authorize(ctx, Scope::TodosRead)?;
+        let project = ctx
+            .data_unchecked::<DataLoader<ProjectLoader>>()
+            .load_one(id)
+            .await?;
+        Ok(project.map(ProjectObject))
+    }
+}
@@ -0,0 +1,50 @@
+use async_graphql::{Context, ResultExt, Subscription};
+use futures::{Stream, stream};
+use uuid::Uuid;
+
+use super::{
+    app_state, authorize,
+    types::{TodoEventObject, TodoObject, TodoStatusEnum, snapshot_todo},
+};
+use crate::{
+    models::token::Scope,
+    routes::events::{Cursor, StreamQuery},
+};
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Changes to the todos the caller can see, read from the same log as
+    /// `GET /todos/events`. Without `after` only changes made after
+    /// subscribing are sent.
+    async fn todo_events(
+        &self,
+        ctx: &Context<'_>,
+        project_id: Option<Uuid>,
+        status: Option<TodoStatusEnum>,
+        after: Option<i64>,
+    ) -> async_graphql::Result<impl Stream<Item = async_graphql::Result<TodoEventObject>>> {
+        let auth = authorize(ctx, Scope::TodosRead)?.clone();
+        let query = StreamQuery {
+            project_id,
+            status: status.map(Into::into),
+            last_event_id: after,
+        };
+        let cursor = Cursor::open(app_state(ctx).clone(), auth, query, after)
+            .await
+            .extend()?;
+
+        Ok(stream::unfold(cursor, |mut cursor| async move {
+            let event = cursor.next().await?;
+            let item = snapshot_todo(event.todo).map(|todo| TodoEventObject {
+                seq: event.seq,
+                kind: event.kind.into(),
+                todo_id: event.todo_id,
+                todo: TodoObject(todo),
+                created_at: event.created_at,
+            });
+            Some((item, cursor))
+        }))
+    }
+}
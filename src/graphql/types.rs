@@ -0,0 +1,299 @@
+use async_graphql::{
+    Context, Enum, InputObject, Object, ResultExt, SimpleObject, dataloader::DataLoader,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::loaders::{MembersLoader, MembersOf, ProjectLoader, ProjectTodosLoader, UserLoader};
+use crate::{
+    error::AppError,
+    models::{self, project::Project, share::Member, todo::Todo},
+    routes::todos::{CreateTodo, UpdateTodo},
+};
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "TodoStatus", remote = "models::todo::TodoStatus")]
+pub enum TodoStatusEnum {
+    Todo,
+    Doing,
+    Done,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "Priority", remote = "models::todo::Priority")]
+pub enum PriorityEnum {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "TodoSource", remote = "models::todo::TodoSource")]
+pub enum TodoSourceEnum {
+    Manual,
+    Audio,
+    Ai,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "Role", remote = "models::share::Role")]
+pub enum RoleEnum {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "TodoEventType", remote = "models::event::TodoEventKind")]
+pub enum TodoEventKindEnum {
+    Created,
+    Updated,
+    Deleted,
+}
+
+pub struct TodoObject(pub Todo);
+
+#[Object(name = "Todo")]
+impl TodoObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    async fn status(&self) -> TodoStatusEnum {
+        self.0.status.clone().into()
+    }
+
+    async fn priority(&self) -> PriorityEnum {
+        self.0.priority.clone().into()
+    }
+
+    async fn source(&self) -> TodoSourceEnum {
+        self.0.source.clone().into()
+    }
+
+    async fn owner_id(&self) -> Option<Uuid> {
+        self.0.owner_id
+    }
+
+    async fn project_id(&self) -> Option<Uuid> {
+        self.0.project_id
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    /// Null when the todo is in no project, or was shared with the caller on
+    /// its own without its project.
+    async fn project(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<ProjectObject>> {
+        let Some(project_id) = self.0.project_id else {
+            return Ok(None);
+        };
+        let project = ctx
+            .data_unchecked::<DataLoader<ProjectLoader>>()
+            .load_one(project_id)
+            .await?;
+        Ok(project.map(ProjectObject))
+    }
+
+    async fn owner(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<UserObject>> {
+        load_user(ctx, self.0.owner_id).await
+    }
+
+    /// The owner and everyone the todo was shared with directly.
+    #[graphql(complexity = "5 * child_complexity")]
+    async fn members(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<MemberObject>> {
+        load_members(ctx, MembersOf::Todo(self.0.id)).await
+    }
+}
+
+pub struct ProjectObject(pub Project);
+
+#[Object(name = "Project")]
+impl ProjectObject {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn owner_id(&self) -> Option<Uuid> {
+        self.0.owner_id
+    }
+
+    /// The caller's role on this project.
+    async fn role(&self) -> RoleEnum {
+        self.0.role.into()
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    async fn owner(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<UserObject>> {
+        load_user(ctx, self.0.owner_id).await
+    }
+
+    /// The todos in this project, newest first.
+    #[graphql(complexity = "10 * child_complexity")]
+    async fn todos(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<TodoStatusEnum>,
+    ) -> async_graphql::Result<Vec<TodoObject>> {
+        let todos = ctx
+            .data_unchecked::<DataLoader<ProjectTodosLoader>>()
+            .load_one(self.0.id)
+            .await?
+            .unwrap_or_default();
+        let status = status.map(models::todo::TodoStatus::from);
+
+        Ok(todos
+            .into_iter()
+            .filter(|t| status.is_none() || status.as_ref() == Some(&t.status))
+            .map(TodoObject)
+            .collect())
+    }
+
+    /// The owner and everyone the project was shared with.
+    #[graphql(complexity = "5 * child_complexity")]
+    async fn members(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<MemberObject>> {
+        load_members(ctx, MembersOf::Project(self.0.id)).await
+    }
+}
+
+/// Public profile of a user in the caller's workspace.
+#[derive(SimpleObject, Clone)]
+#[graphql(name = "User")]
+pub struct UserObject {
+    pub id: Uuid,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Member")]
+pub struct MemberObject {
+    pub user_id: Uuid,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub role: RoleEnum,
+}
+
+impl From<Member> for MemberObject {
+    fn from(member: Member) -> Self {
+        Self {
+            user_id: member.user_id,
+            email: member.email,
+            display_name: member.display_name,
+            role: member.role.into(),
+        }
+    }
+}
+
+/// A change to a todo the caller can see.
+#[derive(SimpleObject)]
+#[graphql(name = "TodoEvent")]
+pub struct TodoEventObject {
+    /// Position in the event log; pass it as `after` to resume.
+    pub seq: i64,
+    #[graphql(name = "type")]
+    pub kind: TodoEventKindEnum,
+    pub todo_id: Uuid,
+    /// The todo after the change, or as it was before being deleted.
+    pub todo: TodoObject,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(InputObject)]
+pub struct CreateTodoInput {
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: Option<PriorityEnum>,
+    pub project_id: Option<Uuid>,
+}
+
+impl From<CreateTodoInput> for CreateTodo {
+    fn from(input: CreateTodoInput) -> Self {
+        Self {
+            title: input.title,
+            description: input.description,
+            priority: input.priority.map(Into::into),
+            project_id: input.project_id,
+        }
+    }
+}
+
+/// Omitted fields are left unchanged.
+#[derive(InputObject)]
+pub struct UpdateTodoInput {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<TodoStatusEnum>,
+    pub priority: Option<PriorityEnum>,
+    pub project_id: Option<Uuid>,
+}
+
+impl From<UpdateTodoInput> for UpdateTodo {
+    fn from(input: UpdateTodoInput) -> Self {
+        Self {
+            title: input.title,
+            description: input.description,
+            status: input.status.map(Into::into),
+            priority: input.priority.map(Into::into),
+            project_id: input.project_id,
+        }
+    }
+}
+
+async fn load_user(
+    ctx: &Context<'_>,
+    user_id: Option<Uuid>,
+) -> async_graphql::Result<Option<UserObject>> {
+    let Some(user_id) = user_id else {
+        return Ok(None);
+    };
+    ctx.data_unchecked::<DataLoader<UserLoader>>()
+        .load_one(user_id)
+        .await
+}
+
+async fn load_members(
+    ctx: &Context<'_>,
+    of: MembersOf,
+) -> async_graphql::Result<Vec<MemberObject>> {
+    let members = ctx
+        .data_unchecked::<DataLoader<MembersLoader>>()
+        .load_one(of)
+        .await?
+        .unwrap_or_default();
+    Ok(members.into_iter().map(MemberObject::from).collect())
+}
+
+/// Turns a logged todo snapshot back into a todo.
+pub fn snapshot_todo(snapshot: serde_json::Value) -> async_graphql::Result<Todo> {
+    serde_json::from_value(snapshot)
+        .map_err(|e| {
+            tracing::error!("Failed to read todo snapshot: {:?}", e);
+            AppError::Internal("failed to read todo event".into())
+        })
+        .extend()
+}
@@ -0,0 +1,106 @@
+//! Conversions between the protobuf messages and the REST models.
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::pb;
+use crate::{
+    error::AppError,
+    models::todo::{Priority, SuggestedTodo, Todo, TodoSource, TodoStatus},
+};
+
+pub fn parse_id(field: &str, value: &str) -> Result<Uuid, AppError> {
+    value
+        .parse()
+        .map_err(|_| AppError::invalid_field(field, "must be a UUID"))
+}
+
+pub fn parse_optional_id(field: &str, value: Option<&str>) -> Result<Option<Uuid>, AppError> {
+    value.map(|v| parse_id(field, v)).transpose()
+}
+
+/// `None` for `UNSPECIFIED`.
+pub fn status(field: &str, value: i32) -> Result<Option<TodoStatus>, AppError> {
+    match pb::TodoStatus::try_from(value) {
+        Ok(pb::TodoStatus::Unspecified) => Ok(None),
+        Ok(pb::TodoStatus::Todo) => Ok(Some(TodoStatus::Todo)),
+        Ok(pb::TodoStatus::Doing) => Ok(Some(TodoStatus::Doing)),
+        Ok(pb::TodoStatus::Done) => Ok(Some(TodoStatus::Done)),
+        Err(_) => Err(AppError::invalid_field(field, "unknown status")),
+    }
+}
+
+/// `None` for `UNSPECIFIED`.
+pub fn priority(field: &str, value: i32) -> Result<Option<Priority>, AppError> {
+    match pb::Priority::try_from(value) {
+        Ok(pb::Priority::Unspecified) => Ok(None),
+        Ok(pb::Priority::Low) => Ok(Some(Priority::Low)),
+        Ok(pb::Priority::Medium) => Ok(Some(Priority::Medium)),
+        Ok(pb::Priority::High) => Ok(Some(Priority::High)),
+        Err(_) => Err(AppError::invalid_field(field, "unknown priority")),
+    }
+}
+
+fn timestamp(at: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: at.timestamp(),
+        nanos: at.timestamp_subsec_nanos() as i32,
+    }
+}
+
+impl From<TodoStatus> for pb::TodoStatus {
+    fn from(status: TodoStatus) -> Self {
+        match status {
+            TodoStatus::Todo => pb::TodoStatus::Todo,
+            TodoStatus::Doing => pb::TodoStatus::Doing,
+            TodoStatus::Done => pb::TodoStatus::Done,
+        }
+    }
+}
+
+impl From<Priority> for pb::Priority {
+    fn from(priority: Priority) -> Self {
+        match priority {
+            Priority::Low => pb::Priority::Low,
+            Priority::Medium => pb::Priority::Medium,
+            Priority::High => pb::Priority::High,
+        }
+    }
+}
+
+impl From<TodoSource> for pb::TodoSource {
+    fn from(source: TodoSource) -> Self {
+        match source {
+            TodoSource::Manual => pb::TodoSource::Manual,
+            TodoSource::Audio => pb::TodoSource::Audio,
+            TodoSource::Ai => pb::TodoSource::Ai,
+        }
+    }
+}
+
+impl From<Todo> for pb::Todo {
+    fn from(todo: Todo) -> Self {
+        Self {
+            id: todo.id.to_string(),
+            title: todo.title,
+            description: todo.description,
+            status: pb::TodoStatus::from(todo.status).into(),
+            priority: pb::Priority::from(todo.priority).into(),
+            source: pb::TodoSource::from(todo.source).into(),
+            owner_id: todo.owner_id.map(|id| id.to_string()),
+            project_id: todo.project_id.map(|id| id.to_string()),
+            created_at: Some(timestamp(todo.created_at)),
+            updated_at: Some(timestamp(todo.updated_at)),
+        }
+    }
+}
+
+impl From<SuggestedTodo> for pb::SuggestedTodo {
+    fn from(suggested: SuggestedTodo) -> Self {
+        Self {
+            title: suggested.title,
+            description: suggested.description,
+            priority: pb::Priority::from(suggested.priority).into(),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! gRPC interface for internal services, defined in `proto/todo.proto`. It
+//! runs next to the HTTP server and shares its state, authentication and
+//! todo logic.
+
+mod convert;
+mod todos;
+
+use std::net::SocketAddr;
+
+use tonic::{
+    Status,
+    metadata::{MetadataMap, MetadataValue},
+};
+
+use crate::{
+    auth::{self, AuthContext},
+    error::{AppError, ErrorResponse},
+    models::token::Scope,
+    state::AppState,
+};
+
+pub mod pb {
+    tonic::include_proto!("aitodo.v1");
+}
+
+/// Serves gRPC on `addr` until `shutdown` resolves.
+pub async fn serve(
+    state: AppState,
+    addr: SocketAddr,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    tracing::info!("gRPC listening on {}", addr);
+
+    tonic::transport::Server::builder()
+        .add_service(pb::todo_service_server::TodoServiceServer::new(
+            todos::TodoService::new(state),
+        ))
+        .serve_with_shutdown(addr, shutdown)
+        .await
+}
+
+/// Authenticates a call from its `authorization` and `x-workspace-id`
+/// metadata and checks `scope`.
+async fn authenticate(
+    state: &AppState,
+    metadata: &MetadataMap,
+    scope: Scope,
+) -> Result<AuthContext, AppError> {
+    let headers = metadata.clone().into_headers();
+    let token = auth::bearer_token(&headers).ok_or(AppError::Unauthorized)?;
+    let workspace_id = auth::requested_workspace(&headers)?;
+    let ctx = auth::authenticate_token(state, token, workspace_id).await?;
+
+    if !ctx.has_scope(scope) {
+        return Err(AppError::InsufficientScope(scope));
+    }
+
+    Ok(ctx)
+}
+
+impl From<AppError> for Status {
+    fn from(error: AppError) -> Self {
+        let retry_after = match &error {
+            AppError::TooManyRequests { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        };
+        let code = match &error {
+            AppError::NotFound => tonic::Code::NotFound,
+            AppError::InvalidInput(_) => tonic::Code::InvalidArgument,
+            AppError::Unauthorized => tonic::Code::Unauthenticated,
+            AppError::Forbidden | AppError::InsufficientScope(_) => tonic::Code::PermissionDenied,
+            AppError::Conflict(_) => tonic::Code::Aborted,
+            AppError::TooManyRequests { .. } => tonic::Code::ResourceExhausted,
+            AppError::Internal(_) => tonic::Code::Internal,
+        };
+
+        // Field errors are folded into the message, e.g.
+        // "Validation failed: title: title cannot be empty".
+        let body = ErrorResponse::from(error);
+        let mut fields: Vec<_> = body
+            .errors
+            .iter()
+            .map(|(field, messages)| format!("{field}: {}", messages.join(", ")))
+            .collect();
+        fields.sort();
+        let message = if fields.is_empty() {
+            body.message
+        } else {
+            format!("{}: {}", body.message, fields.join("; "))
+        };
+
+        let mut status = Status::new(code, message);
+        if let Some(retry_after) = retry_after {
+            status
+                .metadata_mut()
+                .insert("retry-after", MetadataValue::from(retry_after));
+        }
+        status
+    }
+}
@@ -0,0 +1,233 @@
+use std::{collections::VecDeque, pin::Pin};
+
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt, stream};
+use tonic::{Request, Response, Status, Streaming};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::{authenticate, convert, pb};
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    models::{
+        todo::{Todo, TodoStatus},
+        token::Scope,
+    },
+    rate_limit,
+    repositories::postgres,
+    routes::{
+        audio,
+        todos::{CreateTodo, UpdateTodo},
+    },
+    state::AppState,
+    validator::validation_error,
+};
+
+/// Todos read per query while streaming a list.
+const PAGE_SIZE: i64 = 100;
+
+/// Largest recording accepted by `SuggestTasks`, the model's limit for
+/// inline audio.
+const MAX_AUDIO_BYTES: usize = 20 * 1024 * 1024;
+
+pub struct TodoService {
+    state: AppState,
+}
+
+impl TodoService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Authenticates the call and counts it against the general rate limit.
+    async fn caller<T>(&self, request: &Request<T>, scope: Scope) -> Result<AuthContext, AppError> {
+        let ctx = authenticate(&self.state, request.metadata(), scope).await?;
+        rate_limit::check_api(&self.state, &ctx)?;
+        Ok(ctx)
+    }
+}
+
+fn commit_err(context: &'static str) -> impl Fn(sqlx::Error) -> AppError {
+    move |e| {
+        tracing::error!("Failed to commit {}: {:?}", context, e);
+        AppError::Internal(format!("failed to {context}"))
+    }
+}
+
+type TodoStream = Pin<Box<dyn Stream<Item = Result<pb::Todo, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl pb::todo_service_server::TodoService for TodoService {
+    async fn create_todo(
+        &self,
+        request: Request<pb::CreateTodoRequest>,
+    ) -> Result<Response<pb::Todo>, Status> {
+        let ctx = self.caller(&request, Scope::TodosWrite).await?;
+        let req = request.into_inner();
+
+        let payload = CreateTodo {
+            title: req.title,
+            description: req.description,
+            priority: convert::priority("priority", req.priority)?,
+            project_id: convert::parse_optional_id("project_id", req.project_id.as_deref())?,
+        };
+        payload.validate().map_err(validation_error)?;
+
+        let mut tx = self.state.tenant(&ctx).await?;
+        let todo = postgres::insert_todo(&mut tx, &ctx, Uuid::new_v4(), payload).await?;
+        tx.commit().await.map_err(commit_err("create todo"))?;
+
+        Ok(Response::new(todo.into()))
+    }
+
+    async fn get_todo(
+        &self,
+        request: Request<pb::GetTodoRequest>,
+    ) -> Result<Response<pb::Todo>, Status> {
+        let ctx = self.caller(&request, Scope::TodosRead).await?;
+        let id = convert::parse_id("id", &request.get_ref().id)?;
+
+        let mut tx = self.state.tenant(&ctx).await?;
+        let todo = postgres::fetch_todo(&mut tx, &ctx, id).await?;
+
+        Ok(Response::new(todo.into()))
+    }
+
+    async fn update_todo(
+        &self,
+        request: Request<pb::UpdateTodoRequest>,
+    ) -> Result<Response<pb::Todo>, Status> {
+        let ctx = self.caller(&request, Scope::TodosWrite).await?;
+        let req = request.into_inner();
+        let id = convert::parse_id("id", &req.id)?;
+
+        let payload = UpdateTodo {
+            title: req.title,
+            description: req.description,
+            status: convert::status("status", req.status)?,
+            priority: convert::priority("priority", req.priority)?,
+            project_id: convert::parse_optional_id("project_id", req.project_id.as_deref())?,
+        };
+        payload.validate().map_err(validation_error)?;
+
+        let mut tx = self.state.tenant(&ctx).await?;
+        let todo = postgres::apply_update(&mut tx, &ctx, &self.state.clock, id, payload).await?;
+        tx.commit().await.map_err(commit_err("update todo"))?;
+
+        Ok(Response::new(todo.into()))
+    }
+
+    async fn delete_todo(
+        &self,
+        request: Request<pb::DeleteTodoRequest>,
+    ) -> Result<Response<pb::Todo>, Status> {
+        let ctx = self.caller(&request, Scope::TodosWrite).await?;
+        let id = convert::parse_id("id", &request.get_ref().id)?;
+
+        let mut tx = self.state.tenant(&ctx).await?;
+        let todo = postgres::remove_todo(&mut tx, &ctx, id).await?;
+        tx.commit().await.map_err(commit_err("delete todo"))?;
+
+        Ok(Response::new(todo.into()))
+    }
+
+    type ListTodosStream = TodoStream;
+
+    async fn list_todos(
+        &self,
+        request: Request<pb::ListTodosRequest>,
+    ) -> Result<Response<Self::ListTodosStream>, Status> {
+        let ctx = self.caller(&request, Scope::TodosRead).await?;
+        let req = request.into_inner();
+
+        let pager = Pager {
+            state: self.state.clone(),
+            ctx,
+            project_id: convert::parse_optional_id("project_id", req.project_id.as_deref())?,
+            status: convert::status("status", req.status)?,
+            after: None,
+            pending: VecDeque::new(),
+            done: false,
+        };
+
+        let stream = stream::unfold(pager, |mut pager| async move {
+            let item = pager.next().await?;
+            Some((item.map(pb::Todo::from).map_err(Status::from), pager))
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn suggest_tasks(
+        &self,
+        request: Request<Streaming<pb::AudioChunk>>,
+    ) -> Result<Response<pb::SuggestTasksResponse>, Status> {
+        let ctx = authenticate(&self.state, request.metadata(), Scope::AudioSuggest).await?;
+        let mut chunks = request.into_inner();
+
+        let mut audio_data = Vec::new();
+        let mut mime_type = None;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            if mime_type.is_none() {
+                mime_type = Some(chunk.mime_type).filter(|m| !m.is_empty());
+            }
+            if audio_data.len() + chunk.data.len() > MAX_AUDIO_BYTES {
+                return Err(AppError::invalid_field("data", "audio must be at most 20 MiB").into());
+            }
+            audio_data.extend_from_slice(&chunk.data);
+        }
+
+        // Checked once the upload is complete, so a dropped upload is free.
+        rate_limit::check_ai(&self.state, &ctx)?;
+        let mime_type = mime_type.unwrap_or_else(|| "audio/mpeg".to_string());
+        let tasks = audio::suggest(&self.state, &ctx, audio_data, &mime_type).await?;
+
+        Ok(Response::new(pb::SuggestTasksResponse {
+            tasks: tasks.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+/// Reads a todo list one page at a time, each in its own tenant transaction.
+struct Pager {
+    state: AppState,
+    ctx: AuthContext,
+    project_id: Option<Uuid>,
+    status: Option<TodoStatus>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    pending: VecDeque<Todo>,
+    done: bool,
+}
+
+impl Pager {
+    async fn next(&mut self) -> Option<Result<Todo, AppError>> {
+        if self.pending.is_empty()
+            && !self.done
+            && let Err(e) = self.fetch().await
+        {
+            self.done = true;
+            return Some(Err(e));
+        }
+        self.pending.pop_front().map(Ok)
+    }
+
+    async fn fetch(&mut self) -> Result<(), AppError> {
+        let mut tx = self.state.tenant(&self.ctx).await?;
+        let page = postgres::list_page(
+            &mut tx,
+            &self.ctx,
+            self.project_id,
+            self.status.clone(),
+            self.after,
+            PAGE_SIZE,
+        )
+        .await?;
+
+        self.done = (page.len() as i64) < PAGE_SIZE;
+        self.after = page.last().map(|t| (t.created_at, t.id));
+        self.pending.extend(page);
+        Ok(())
+    }
+}
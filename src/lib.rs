@@ -0,0 +1,24 @@
+//! The AI-Todo server: HTTP and gRPC APIs over a PostgreSQL store, or HTTP
+//! over a SQLite file for a single user. The models are shared with the
+//! `ai-todo-cli` client.
+
+pub mod app;
+pub mod auth;
+pub mod commands;
+pub mod config;
+pub mod error;
+pub mod graphql;
+pub mod grpc;
+pub mod metrics;
+pub mod models;
+pub mod openapi;
+pub mod permissions;
+pub mod rate_limit;
+pub mod repositories;
+pub mod request_id;
+pub mod routes;
+pub mod services;
+pub mod state;
+pub mod telemetry;
+pub mod validator;
+pub mod versioning;
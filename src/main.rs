@@ -1,92 +1,152 @@
-mod app;
-mod error;
-mod models;
-mod routes;
-mod services;
-mod state;
-mod validator;
-
+use std::{path::PathBuf, process::ExitCode};
+
+use ai_todo::{
+    commands::{
+        self, CommandError,
+        migrate::{MIGRATOR, SQLITE_MIGRATOR},
+    },
+    config::{Backend, Config},
+    telemetry,
+};
+use clap::{Parser, Subcommand};
 use dotenvy::dotenv;
-use sqlx::postgres::PgPoolOptions;
-use std::env;
-use tracing_subscriber::{EnvFilter, fmt};
+use sqlx::{
+    Database, Pool,
+    migrate::{Migrate, Migrator},
+};
+use uuid::Uuid;
+
+#[derive(Parser)]
+#[command(
+    name = "ai-todo",
+    version,
+    about = "AI-Todo server and operator commands"
+)]
+struct Cli {
+    /// Settings file; environment variables override it [default: ai-todo.toml if present]
+    #[arg(long, global = true, env = "AI_TODO_CONFIG")]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-#[tokio::main]
-async fn main() {
-    dotenv().ok();
+#[derive(Subcommand)]
+enum Command {
+    /// Run the HTTP and gRPC servers (the default)
+    Serve,
+    /// Apply, revert or list database migrations
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// Create a demo user, workspace, project and todos, and print a token
+    Seed,
+    /// Write workspaces with their projects and todos to a JSON file
+    Export {
+        /// File to write, or - for stdout
+        path: PathBuf,
+        /// Only export this workspace; may be repeated [default: all]
+        #[arg(long)]
+        workspace: Vec<Uuid>,
+    },
+    /// Load a file written by `export`, skipping what already exists
+    Import { path: PathBuf },
+    /// Delete tombstones and events left behind by deleted todos
+    PurgeTrash {
+        /// Only purge what is older than this many days
+        #[arg(long, default_value_t = 30)]
+        older_than_days: u32,
+        /// Report what would be deleted without deleting it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Validate the configuration and database without starting the server
+    CheckConfig,
+}
 
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-    fmt().with_env_filter(filter).init();
+#[derive(Subcommand)]
+enum MigrateAction {
+    /// Apply every pending migration
+    Up,
+    /// Revert the latest migration, or every one newer than --target
+    Down {
+        /// Version to revert to; 0 reverts everything
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// List migrations and whether each is applied
+    Status,
+}
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await
-        .expect("Failed to connect to Postgres");
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    let cli = Cli::parse();
 
-    // Run migrations if enabled (mostly for local dev)
-    let run_migrations = env::var("RUN_MIGRATIONS").is_ok_and(|v| v == "true");
+    let command = cli.command.unwrap_or(Command::Serve);
 
-    if run_migrations {
-        tracing::info!("Running migrations...");
-        sqlx::migrate!("./migrations")
-            .run(&pool)
-            .await
-            .expect("Failed to run migrations");
-        tracing::info!("Migrations executed successfully.");
+    // Operator commands print their own output; info logs would bury it.
+    let default_level = match command {
+        Command::Serve => "info",
+        _ => "warn",
+    };
+    let result = async {
+        let config = Config::load(cli.config.as_deref())?;
+        // Only the server exports traces. Dropping the guard flushes them.
+        let export = matches!(command, Command::Serve);
+        let _telemetry = telemetry::init(&config, default_level, export)?;
+        run(command, config).await
+    }
+    .await;
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
     }
-
-    let gemini =
-        services::gemini::GeminiService::new().expect("Failed to initialize GeminiService");
-    let state = state::AppState::new(pool, gemini);
-    let app = app::create_app(state);
-
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:5000")
-        .await
-        .unwrap();
-
-    tracing::info!("Listening on {}", listener.local_addr().unwrap());
-
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
 }
 
-async fn shutdown_signal() {
-    let ctrl_c = async {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("failed to install Ctrl+C handler");
-    };
-
-    #[cfg(unix)]
-    let terminate = async {
-        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-            .expect("failed to install signal handler")
-            .recv()
-            .await;
-    };
-
-    #[cfg(not(unix))]
-    let terminate = std::future::pending::<()>();
-
-    tokio::select! {
-        () = ctrl_c => {},
-        () = terminate => {},
+async fn run(command: Command, config: Config) -> Result<(), CommandError> {
+    match command {
+        Command::Serve => commands::serve::run(config).await,
+        Command::CheckConfig => commands::check_config::run(&config).await,
+        Command::Migrate { action } if config.database.backend() == Backend::Sqlite => {
+            let pool = commands::connect_sqlite(&config).await?;
+            migrate(action, &SQLITE_MIGRATOR, &pool).await
+        }
+        command => {
+            let pool = commands::connect(&config).await?;
+            match command {
+                Command::Migrate { action } => migrate(action, &MIGRATOR, &pool).await,
+                Command::Seed => commands::seed::run(&pool).await,
+                Command::Export { path, workspace } => {
+                    commands::transfer::export(&pool, &path, &workspace).await
+                }
+                Command::Import { path } => commands::transfer::import(&pool, &path).await,
+                Command::PurgeTrash {
+                    older_than_days,
+                    dry_run,
+                } => commands::purge::run(&pool, older_than_days, dry_run).await,
+                Command::Serve | Command::CheckConfig => unreachable!("handled above"),
+            }
+        }
     }
+}
 
-    eprintln!("signal received, starting graceful shutdown");
-
-    let timeout_secs = std::env::var("SHUTDOWN_TIMEOUT")
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(1);
-
-    tokio::spawn(async move {
-        tokio::time::sleep(std::time::Duration::from_secs(timeout_secs)).await;
-        eprintln!("shutdown timed out, forcing exit");
-        std::process::exit(1);
-    });
+async fn migrate<DB>(
+    action: MigrateAction,
+    migrator: &'static Migrator,
+    pool: &Pool<DB>,
+) -> Result<(), CommandError>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    match action {
+        MigrateAction::Up => commands::migrate::up(migrator, pool).await,
+        MigrateAction::Down { target } => commands::migrate::down(migrator, pool, target).await,
+        MigrateAction::Status => commands::migrate::status(migrator, pool).await,
+    }
 }
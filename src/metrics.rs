@@ -0,0 +1,32 @@
+//! Request metrics for Prometheus. The collectors live in
+//! [`crate::services::metrics`]; they are served by
+//! [`crate::routes::metrics::export`].
+
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::state::AppState;
+
+/// Counts and times every request by method, route and status.
+pub async fn track(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    // Unmatched paths share one label, so that scanning for URLs cannot
+    // create unbounded series.
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
+    let method = req.method().clone();
+    let started = Instant::now();
+
+    let response = next.run(req).await;
+    state
+        .metrics
+        .observe_request(&method, &route, response.status(), started.elapsed());
+    response
+}
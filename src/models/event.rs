@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A change to a todo, as recorded in the `todo_events` log.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TodoEvent {
+    /// Position in the workspace's event log; used as the SSE event ID.
+    pub seq: i64,
+    #[serde(rename = "type")]
+    pub kind: TodoEventKind,
+    pub todo_id: Uuid,
+    /// The todo after the change, or as it was before being deleted.
+    pub todo: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum TodoEventKind {
+    #[serde(rename = "todo.created")]
+    Created,
+    #[serde(rename = "todo.updated")]
+    Updated,
+    #[serde(rename = "todo.deleted")]
+    Deleted,
+}
+
+impl std::fmt::Display for TodoEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TodoEventKind::Created => write!(f, "todo.created"),
+            TodoEventKind::Updated => write!(f, "todo.updated"),
+            TodoEventKind::Deleted => write!(f, "todo.deleted"),
+        }
+    }
+}
+
+impl std::str::FromStr for TodoEventKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "todo.created" => Ok(TodoEventKind::Created),
+            "todo.updated" => Ok(TodoEventKind::Updated),
+            "todo.deleted" => Ok(TodoEventKind::Deleted),
+            _ => Err(format!("Invalid todo event: {s}")),
+        }
+    }
+}
+
+impl From<String> for TodoEventKind {
+    fn from(s: String) -> Self {
+        s.parse().unwrap_or(TodoEventKind::Updated)
+    }
+}
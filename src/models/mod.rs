@@ -1 +1,9 @@
+pub mod event;
+pub mod project;
+pub mod share;
+pub mod sync;
 pub mod todo;
+pub mod token;
+pub mod user;
+pub mod webhook;
+pub mod workspace;
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::todo::Todo;
+use crate::services::crdt::Op;
+
+/// A todo together with the sequence number of its latest change.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SyncedTodo {
+    #[serde(flatten)]
+    pub todo: Todo,
+    /// Sent back as `base_seq` when pushing changes to the todo.
+    pub change_seq: i64,
+}
+
+/// Marker left behind by a deleted todo.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Tombstone {
+    pub id: Uuid,
+    pub project_id: Option<Uuid>,
+    pub change_seq: i64,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MutationStatus {
+    Applied,
+    /// The todo changed on the server since the client's `base_seq`, or was
+    /// deleted. Nothing was written.
+    Conflict,
+    /// The mutation was invalid or not allowed. Nothing was written.
+    Rejected,
+}
+
+/// An operation from a todo's operation log.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LoggedOp {
+    /// Position in the log; pass the last one seen as `since`.
+    pub seq: i64,
+    #[serde(flatten)]
+    pub op: Op,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
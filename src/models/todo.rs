@@ -1,9 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Todo {
     pub id: Uuid,
     pub title: String,
@@ -18,11 +19,14 @@ pub struct Todo {
     #[sqlx(try_from = "String")]
     pub source: TodoSource,
 
+    pub owner_id: Option<Uuid>,
+    pub project_id: Option<Uuid>,
+
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum TodoStatus {
     Todo,
     Doing,
@@ -57,7 +61,7 @@ impl From<String> for TodoStatus {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum Priority {
     Low,
     Medium,
@@ -92,7 +96,7 @@ impl From<String> for Priority {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum TodoSource {
     Manual,
     Audio,
@@ -126,7 +130,7 @@ impl From<String> for TodoSource {
         s.parse().unwrap_or(TodoSource::Manual)
     }
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SuggestedTodo {
     pub title: String,
     pub description: Option<String>,
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A personal access token as exposed by the API. The secret itself is only
+/// returned once, at creation time; only its hash is stored.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Scopes,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum Scope {
+    #[serde(rename = "todos:read")]
+    TodosRead,
+    #[serde(rename = "todos:write")]
+    TodosWrite,
+    #[serde(rename = "audio:suggest")]
+    AudioSuggest,
+    #[serde(rename = "tokens:manage")]
+    TokensManage,
+    #[serde(rename = "users:admin")]
+    UsersAdmin,
+    #[serde(rename = "webhooks:manage")]
+    WebhooksManage,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 6] = [
+        Scope::TodosRead,
+        Scope::TodosWrite,
+        Scope::AudioSuggest,
+        Scope::TokensManage,
+        Scope::UsersAdmin,
+        Scope::WebhooksManage,
+    ];
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::TodosRead => write!(f, "todos:read"),
+            Scope::TodosWrite => write!(f, "todos:write"),
+            Scope::AudioSuggest => write!(f, "audio:suggest"),
+            Scope::TokensManage => write!(f, "tokens:manage"),
+            Scope::UsersAdmin => write!(f, "users:admin"),
+            Scope::WebhooksManage => write!(f, "webhooks:manage"),
+        }
+    }
+}
+
+impl std::str::FromStr for Scope {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "todos:read" => Ok(Scope::TodosRead),
+            "todos:write" => Ok(Scope::TodosWrite),
+            "audio:suggest" => Ok(Scope::AudioSuggest),
+            "tokens:manage" => Ok(Scope::TokensManage),
+            "users:admin" => Ok(Scope::UsersAdmin),
+            "webhooks:manage" => Ok(Scope::WebhooksManage),
+            _ => Err(format!("Invalid scope: {s}")),
+        }
+    }
+}
+
+/// The set of scopes granted to a token, stored as a `TEXT[]` column.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(transparent)]
+pub struct Scopes(pub Vec<Scope>);
+
+impl Scopes {
+    pub fn all() -> Self {
+        Scopes(Scope::ALL.to_vec())
+    }
+
+    /// Scopes granted to interactive sessions: everything except
+    /// administration, which must be delegated explicitly.
+    pub fn session() -> Self {
+        Scopes(
+            Scope::ALL
+                .into_iter()
+                .filter(|s| *s != Scope::UsersAdmin)
+                .collect(),
+        )
+    }
+
+    pub fn contains(&self, scope: Scope) -> bool {
+        self.0.contains(&scope)
+    }
+
+    pub fn to_strings(&self) -> Vec<String> {
+        self.0.iter().map(ToString::to_string).collect()
+    }
+}
+
+impl From<Vec<String>> for Scopes {
+    fn from(v: Vec<String>) -> Self {
+        // Unknown scopes (e.g. ones removed in a later release) are dropped
+        // rather than failing the whole row.
+        Scopes(v.iter().filter_map(|s| s.parse().ok()).collect())
+    }
+}
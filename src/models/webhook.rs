@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A webhook subscription. The signing secret is only returned when the
+/// webhook is created.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub events: WebhookEvents,
+    pub active: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum WebhookEvent {
+    #[serde(rename = "todo.created")]
+    TodoCreated,
+    #[serde(rename = "todo.updated")]
+    TodoUpdated,
+    #[serde(rename = "todo.deleted")]
+    TodoDeleted,
+    /// A todo's status changed to `Done`. Sent in addition to `todo.updated`.
+    #[serde(rename = "todo.completed")]
+    TodoCompleted,
+    /// Suggested tasks were confirmed through `POST /audio/confirm`.
+    #[serde(rename = "audio.confirmed")]
+    AudioConfirmed,
+}
+
+impl std::fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookEvent::TodoCreated => write!(f, "todo.created"),
+            WebhookEvent::TodoUpdated => write!(f, "todo.updated"),
+            WebhookEvent::TodoDeleted => write!(f, "todo.deleted"),
+            WebhookEvent::TodoCompleted => write!(f, "todo.completed"),
+            WebhookEvent::AudioConfirmed => write!(f, "audio.confirmed"),
+        }
+    }
+}
+
+impl std::str::FromStr for WebhookEvent {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "todo.created" => Ok(WebhookEvent::TodoCreated),
+            "todo.updated" => Ok(WebhookEvent::TodoUpdated),
+            "todo.deleted" => Ok(WebhookEvent::TodoDeleted),
+            "todo.completed" => Ok(WebhookEvent::TodoCompleted),
+            "audio.confirmed" => Ok(WebhookEvent::AudioConfirmed),
+            _ => Err(format!("Invalid webhook event: {s}")),
+        }
+    }
+}
+
+impl From<String> for WebhookEvent {
+    fn from(s: String) -> Self {
+        s.parse().unwrap_or(WebhookEvent::TodoUpdated)
+    }
+}
+
+/// The events a webhook subscribes to, stored as a `TEXT[]` column.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(transparent)]
+pub struct WebhookEvents(pub Vec<WebhookEvent>);
+
+impl WebhookEvents {
+    pub fn to_strings(&self) -> Vec<String> {
+        self.0.iter().map(ToString::to_string).collect()
+    }
+}
+
+impl From<Vec<String>> for WebhookEvents {
+    fn from(v: Vec<String>) -> Self {
+        WebhookEvents(v.iter().filter_map(|s| s.parse().ok()).collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    /// Every retry failed; the delivery can still be redelivered manually.
+    Failed,
+}
+
+impl std::fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeliveryStatus::Pending => write!(f, "Pending"),
+            DeliveryStatus::Succeeded => write!(f, "Succeeded"),
+            DeliveryStatus::Failed => write!(f, "Failed"),
+        }
+    }
+}
+
+impl std::str::FromStr for DeliveryStatus {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Pending" => Ok(DeliveryStatus::Pending),
+            "Succeeded" => Ok(DeliveryStatus::Succeeded),
+            "Failed" => Ok(DeliveryStatus::Failed),
+            _ => Err(format!("Invalid delivery status: {s}")),
+        }
+    }
+}
+
+impl From<String> for DeliveryStatus {
+    fn from(s: String) -> Self {
+        s.parse().unwrap_or(DeliveryStatus::Pending)
+    }
+}
+
+/// One attempt to send one event to one webhook, including its retries.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    /// Shared by every delivery of the same event, including redeliveries.
+    pub event_id: Uuid,
+    pub event: WebhookEvent,
+    pub payload: serde_json::Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Workspace that pre-existing data was migrated into. Requests made with the
+/// bootstrap `ADMIN_TOKEN` use it unless they pick another workspace.
+pub const DEFAULT_WORKSPACE_ID: Uuid = Uuid::from_u128(1);
+
+/// A tenant. Data in one workspace is never visible from another.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Workspace {
+    pub id: Uuid,
+    pub name: String,
+    /// The caller's role in this workspace.
+    pub role: WorkspaceRole,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+pub enum WorkspaceRole {
+    Member,
+    Admin,
+}
+
+impl std::fmt::Display for WorkspaceRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkspaceRole::Member => write!(f, "Member"),
+            WorkspaceRole::Admin => write!(f, "Admin"),
+        }
+    }
+}
+
+impl std::str::FromStr for WorkspaceRole {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Member" => Ok(WorkspaceRole::Member),
+            "Admin" => Ok(WorkspaceRole::Admin),
+            _ => Err(format!("Invalid workspace role: {s}")),
+        }
+    }
+}
+
+impl From<String> for WorkspaceRole {
+    fn from(s: String) -> Self {
+        s.parse().unwrap_or(WorkspaceRole::Member)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WorkspaceMember {
+    pub user_id: Uuid,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub role: WorkspaceRole,
+}
@@ -0,0 +1,214 @@
+//! OpenAPI 3.1 description of the REST API, generated from the handler
+//! annotations and model types. Served at `/openapi.json`, browsable with
+//! Swagger UI at `/docs`, and checked in as `docs/openapi.json`.
+
+use utoipa::{
+    Modify, OpenApi,
+    openapi::{
+        ContentBuilder, HeaderBuilder, KnownFormat, ObjectBuilder, Ref, RefOr, Required,
+        ResponseBuilder, SchemaFormat, Type,
+        path::{Operation, ParameterBuilder, ParameterIn},
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    },
+};
+
+use crate::routes;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "AI-Todo API",
+        description = "Todos with sharing, offline sync and task suggestions from audio. \
+                       See docs/REST_API.md for the full guide."
+    ),
+    servers((url = "/v1", description = "Version 1")),
+    paths(
+        routes::todos::create_todo,
+        routes::todos::list_todos,
+        routes::todos::get_todo,
+        routes::todos::update_todo,
+        routes::todos::delete_todo,
+        routes::events::stream_todo_events,
+        routes::audio::suggest_tasks,
+        routes::audio::confirm_tasks,
+        routes::projects::create_project,
+        routes::projects::list_projects,
+        routes::projects::get_project,
+        routes::projects::update_project,
+        routes::projects::delete_project,
+        routes::shares::list_project_members,
+        routes::shares::list_todo_members,
+        routes::shares::invite_to_project,
+        routes::shares::invite_to_todo,
+        routes::shares::update_project_member,
+        routes::shares::update_todo_member,
+        routes::shares::remove_project_member,
+        routes::shares::remove_todo_member,
+        routes::shares::list_invitations,
+        routes::shares::accept_invitation,
+        routes::shares::decline_invitation,
+        routes::sync::pull,
+        routes::sync::push,
+        routes::sync::push_ops,
+        routes::sync::list_ops,
+        routes::workspaces::list_workspaces,
+        routes::workspaces::create_workspace,
+        routes::workspaces::list_members,
+        routes::workspaces::add_member,
+        routes::workspaces::remove_member,
+        routes::webhooks::create_webhook,
+        routes::webhooks::list_webhooks,
+        routes::webhooks::get_webhook,
+        routes::webhooks::update_webhook,
+        routes::webhooks::delete_webhook,
+        routes::webhooks::list_deliveries,
+        routes::webhooks::redeliver,
+        routes::tokens::create_token,
+        routes::tokens::list_tokens,
+        routes::tokens::revoke_token,
+        routes::auth::oidc_login,
+        routes::auth::oidc_callback,
+        routes::auth::me,
+        routes::auth::logout,
+        routes::totp::enroll,
+        routes::totp::activate,
+        routes::totp::regenerate_recovery_codes,
+        routes::totp::disable,
+        routes::totp::verify,
+        routes::totp::admin_reset,
+    ),
+    modifiers(&CommonResponses),
+    security(("bearer" = [])),
+    tags(
+        (name = "todos", description = "Create, read, update and delete todos"),
+        (name = "audio", description = "Suggest todos from a recording"),
+        (name = "projects", description = "Group todos into projects"),
+        (name = "sharing", description = "Members and invitations of projects and todos"),
+        (name = "sync", description = "Offline sync and concurrent edit merging"),
+        (name = "workspaces", description = "Tenants and their members"),
+        (name = "webhooks", description = "Outgoing event notifications"),
+        (name = "tokens", description = "Personal access tokens"),
+        (name = "auth", description = "Single sign-on, sessions and two-factor authentication"),
+        (name = "admin", description = "Administration"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Adds what every operation has in common rather than repeating it on each
+/// handler: the bearer scheme, the `X-Workspace-Id` header and the 401 and
+/// 429 responses.
+struct CommonResponses;
+
+impl Modify for CommonResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // Taken from Cargo.toml, which declares none.
+        openapi.info.license = None;
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+
+        let error_body = || {
+            ContentBuilder::new()
+                .schema(Some(Ref::from_schema_name("ErrorResponse")))
+                .build()
+        };
+        components.responses.insert(
+            "Unauthorized".to_string(),
+            RefOr::T(
+                ResponseBuilder::new()
+                    .description("Missing, invalid or expired token")
+                    .content("application/json", error_body())
+                    .build(),
+            ),
+        );
+        components.responses.insert(
+            "TooManyRequests".to_string(),
+            RefOr::T(
+                ResponseBuilder::new()
+                    .description("Rate limit or daily quota exceeded")
+                    .header(
+                        "Retry-After",
+                        HeaderBuilder::new()
+                            .schema(ObjectBuilder::new().schema_type(Type::Integer))
+                            .description(Some("Seconds until the request may be retried"))
+                            .build(),
+                    )
+                    .content("application/json", error_body())
+                    .build(),
+            ),
+        );
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.patch,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                add_common(operation);
+            }
+        }
+    }
+}
+
+fn add_common(operation: &mut Operation) {
+    let responses = &mut operation.responses.responses;
+    responses.insert(
+        "429".to_string(),
+        RefOr::Ref(Ref::from_response_name("TooManyRequests")),
+    );
+
+    // Public operations override the global requirement with an empty one.
+    if operation.security.is_some() {
+        return;
+    }
+
+    responses
+        .entry("401".to_string())
+        .or_insert_with(|| RefOr::Ref(Ref::from_response_name("Unauthorized")));
+    operation.parameters.get_or_insert_with(Vec::new).push(
+        ParameterBuilder::new()
+            .name("X-Workspace-Id")
+            .parameter_in(ParameterIn::Header)
+            .required(Required::False)
+            .description(Some(
+                "Workspace to operate in; defaults to the caller's first workspace",
+            ))
+            .schema(Some(
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .format(Some(SchemaFormat::KnownFormat(KnownFormat::Uuid))),
+            ))
+            .build(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/docs/openapi.json");
+
+    /// Fails when the API changed without `docs/openapi.json` being
+    /// regenerated. Regenerate with `UPDATE_OPENAPI=1 cargo test openapi`.
+    #[test]
+    fn openapi_spec_is_up_to_date() {
+        let spec = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(SPEC_PATH, &spec).unwrap();
+            return;
+        }
+
+        let committed = std::fs::read_to_string(SPEC_PATH).unwrap_or_default();
+        assert!(
+            committed == spec,
+            "docs/openapi.json is out of date; regenerate it with \
+             `UPDATE_OPENAPI=1 cargo test openapi` and commit the result"
+        );
+    }
+}
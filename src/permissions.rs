@@ -0,0 +1,313 @@
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    models::{share::Role, workspace::WorkspaceRole},
+};
+
+// Role resolution lives in the `todo_role` and `project_role` SQL functions so
+// list queries can filter with the same rules.
+//
+// Callers that are not tied to a user (the bootstrap token and tokens created
+// with it) act as service accounts and are treated as owners of everything.
+
+/// Ensures the caller holds at least `min` on the todo and returns their role.
+/// Fails with `NotFound` if the todo does not exist and `Forbidden` otherwise.
+pub async fn authorize_todo<'e, E: PgExecutor<'e>>(
+    executor: E,
+    ctx: &AuthContext,
+    todo_id: Uuid,
+    min: Role,
+) -> Result<Role, AppError> {
+    let row = sqlx::query!(
+        "SELECT todo_role(id, $2) AS role FROM todos WHERE id = $1",
+        todo_id,
+        ctx.user_id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to resolve todo role: {:?}", e);
+        AppError::Internal("failed to check permissions".into())
+    })?
+    .ok_or(AppError::NotFound)?;
+
+    check(ctx, row.role, min)
+}
+
+/// Ensures the caller holds at least `min` on the project and returns their role.
+pub async fn authorize_project<'e, E: PgExecutor<'e>>(
+    executor: E,
+    ctx: &AuthContext,
+    project_id: Uuid,
+    min: Role,
+) -> Result<Role, AppError> {
+    let row = sqlx::query!(
+        "SELECT project_role(id, $2) AS role FROM projects WHERE id = $1",
+        project_id,
+        ctx.user_id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to resolve project role: {:?}", e);
+        AppError::Internal("failed to check permissions".into())
+    })?
+    .ok_or(AppError::NotFound)?;
+
+    check(ctx, row.role, min)
+}
+
+/// Ensures the caller holds at least `min` in the workspace and returns their
+/// role. Workspaces the caller does not belong to are reported as `NotFound`.
+pub async fn authorize_workspace<'e, E: PgExecutor<'e>>(
+    executor: E,
+    ctx: &AuthContext,
+    workspace_id: Uuid,
+    min: WorkspaceRole,
+) -> Result<WorkspaceRole, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT m.role AS "role?"
+        FROM workspaces w
+        LEFT JOIN workspace_members m ON m.workspace_id = w.id AND m.user_id = $2
+        WHERE w.id = $1
+        "#,
+        workspace_id,
+        ctx.user_id
+    )
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to resolve workspace role: {:?}", e);
+        AppError::Internal("failed to check permissions".into())
+    })?
+    .ok_or(AppError::NotFound)?;
+
+    // Service-account tokens other than the bootstrap token stay in the
+    // workspace they were created in.
+    let role = match (ctx.token_id, ctx.user_id) {
+        (None, _) => WorkspaceRole::Admin,
+        (Some(_), None) if workspace_id == ctx.workspace_id => WorkspaceRole::Admin,
+        (Some(_), None) => return Err(AppError::NotFound),
+        (Some(_), Some(_)) => row
+            .role
+            .map(WorkspaceRole::from)
+            .ok_or(AppError::NotFound)?,
+    };
+
+    if role < min {
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(role)
+}
+
+fn check(ctx: &AuthContext, role: Option<String>, min: Role) -> Result<Role, AppError> {
+    let role = match ctx.user_id {
+        None => Role::Owner,
+        Some(_) => role.map(Role::from).ok_or(AppError::Forbidden)?,
+    };
+
+    if role < min {
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(role)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::models::token::Scopes;
+
+    const ROLES: [Role; 3] = [Role::Viewer, Role::Editor, Role::Owner];
+
+    /// A project with one todo, owned by the first user, in a new workspace.
+    struct Fixture {
+        workspace_id: Uuid,
+        project_id: Uuid,
+        todo_id: Uuid,
+        owner: AuthContext,
+    }
+
+    fn caller(workspace_id: Uuid, user_id: Option<Uuid>) -> AuthContext {
+        AuthContext {
+            token_id: Some(Uuid::new_v4()),
+            user_id,
+            workspace_id,
+            scopes: Scopes::all(),
+        }
+    }
+
+    async fn user(pool: &PgPool, workspace_id: Uuid) -> AuthContext {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO users (id, created_at, updated_at) VALUES ($1, now(), now())",
+            id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        caller(workspace_id, Some(id))
+    }
+
+    async fn fixture(pool: &PgPool) -> Fixture {
+        let workspace_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO workspaces (id, name, created_at) VALUES ($1, 'Team', now())",
+            workspace_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        let owner = user(pool, workspace_id).await;
+
+        let project_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO projects (id, name, owner_id, workspace_id, created_at, updated_at) VALUES ($1, 'Launch', $2, $3, now(), now())",
+            project_id,
+            owner.user_id,
+            workspace_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        let todo_id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO todos (id, title, status, priority, source, owner_id, project_id, workspace_id, created_at, updated_at)
+            VALUES ($1, 'Ship it', 'Todo', 'Medium', 'Manual', $2, $3, $4, now(), now())
+            "#,
+            todo_id,
+            owner.user_id,
+            project_id,
+            workspace_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        Fixture {
+            workspace_id,
+            project_id,
+            todo_id,
+            owner,
+        }
+    }
+
+    async fn share(
+        pool: &PgPool,
+        fixture: &Fixture,
+        member: &AuthContext,
+        project_id: Option<Uuid>,
+        todo_id: Option<Uuid>,
+        role: Role,
+    ) {
+        sqlx::query!(
+            "INSERT INTO shares (id, user_id, project_id, todo_id, role, workspace_id, created_at) VALUES ($1, $2, $3, $4, $5, $6, now())",
+            Uuid::new_v4(),
+            member.user_id,
+            project_id,
+            todo_id,
+            role.to_string(),
+            fixture.workspace_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    /// Checks `held` against every minimum role: allowed up to and including
+    /// `held`, forbidden above it.
+    async fn assert_boundary<F, Fut>(authorize: F, held: Role)
+    where
+        F: Fn(Role) -> Fut,
+        Fut: Future<Output = Result<Role, AppError>>,
+    {
+        for min in ROLES {
+            let result = authorize(min).await;
+            if min <= held {
+                assert_eq!(result.unwrap(), held, "{held:?} needing {min:?}");
+            } else {
+                assert!(
+                    matches!(result, Err(AppError::Forbidden)),
+                    "{held:?} needing {min:?}"
+                );
+            }
+        }
+    }
+
+    #[sqlx::test]
+    async fn project_roles_allow_up_to_their_level(pool: PgPool) {
+        let f = fixture(&pool).await;
+        let service = caller(f.workspace_id, None);
+        let mut callers = vec![(f.owner.clone(), Role::Owner), (service, Role::Owner)];
+        for role in ROLES {
+            let member = user(&pool, f.workspace_id).await;
+            share(&pool, &f, &member, Some(f.project_id), None, role).await;
+            callers.push((member, role));
+        }
+
+        for (ctx, held) in &callers {
+            assert_boundary(
+                |min| authorize_project(&pool, ctx, f.project_id, min),
+                *held,
+            )
+            .await;
+        }
+
+        let stranger = user(&pool, f.workspace_id).await;
+        for min in ROLES {
+            let result = authorize_project(&pool, &stranger, f.project_id, min).await;
+            assert!(matches!(result, Err(AppError::Forbidden)));
+        }
+        let result = authorize_project(&pool, &f.owner, Uuid::new_v4(), Role::Viewer).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[sqlx::test]
+    async fn todo_roles_allow_up_to_their_level(pool: PgPool) {
+        let f = fixture(&pool).await;
+        let service = caller(f.workspace_id, None);
+        let mut callers = vec![(f.owner.clone(), Role::Owner), (service, Role::Owner)];
+        for role in ROLES {
+            let member = user(&pool, f.workspace_id).await;
+            share(&pool, &f, &member, None, Some(f.todo_id), role).await;
+            callers.push((member, role));
+        }
+        // A role on the project carries over to its todos.
+        let project_editor = user(&pool, f.workspace_id).await;
+        share(
+            &pool,
+            &f,
+            &project_editor,
+            Some(f.project_id),
+            None,
+            Role::Editor,
+        )
+        .await;
+        callers.push((project_editor, Role::Editor));
+        // The higher of a project and a todo share wins.
+        let both = user(&pool, f.workspace_id).await;
+        share(&pool, &f, &both, Some(f.project_id), None, Role::Viewer).await;
+        share(&pool, &f, &both, None, Some(f.todo_id), Role::Owner).await;
+        callers.push((both, Role::Owner));
+
+        for (ctx, held) in &callers {
+            assert_boundary(|min| authorize_todo(&pool, ctx, f.todo_id, min), *held).await;
+        }
+
+        let stranger = user(&pool, f.workspace_id).await;
+        for min in ROLES {
+            let result = authorize_todo(&pool, &stranger, f.todo_id, min).await;
+            assert!(matches!(result, Err(AppError::Forbidden)));
+        }
+        let result = authorize_todo(&pool, &f.owner, Uuid::new_v4(), Role::Viewer).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+}
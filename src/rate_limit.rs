@@ -0,0 +1,166 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{Days, Utc};
+
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    services::rate_limit::{Decision, RateLimiter},
+    state::AppState,
+};
+
+/// Rate limit for every route except those calling the AI model.
+pub async fn limit_api(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let key = client_key(&req);
+    limit(&state.rate_limits.api, &key, req, next).await
+}
+
+/// Rate limit for routes that call the AI model. Must run after
+/// [`crate::auth::authenticate`]. The daily quota is charged by the handler,
+/// with [`charge_ai_quota`], once the request is about to reach the model.
+pub async fn limit_ai(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let key = client_key(&req);
+    limit(&state.rate_limits.ai, &key, req, next).await
+}
+
+async fn limit(limiter: &RateLimiter, key: &str, req: Request, next: Next) -> Response {
+    let decision = limiter.check(key);
+    if !decision.allowed {
+        return rejected(decision);
+    }
+
+    let mut response = next.run(req).await;
+    insert_headers(response.headers_mut(), decision);
+    response
+}
+
+/// [`limit_api`] for authenticated calls that do not go through the router,
+/// such as gRPC.
+pub fn check_api(state: &AppState, ctx: &AuthContext) -> Result<(), AppError> {
+    check(&state.rate_limits.api, &caller_key(ctx))
+}
+
+/// [`limit_ai`] for authenticated calls that do not go through the router.
+pub fn check_ai(state: &AppState, ctx: &AuthContext) -> Result<(), AppError> {
+    check(&state.rate_limits.ai, &caller_key(ctx))
+}
+
+fn check(limiter: &RateLimiter, key: &str) -> Result<(), AppError> {
+    let decision = limiter.check(key);
+    if decision.allowed {
+        return Ok(());
+    }
+    Err(AppError::TooManyRequests {
+        message: "Rate limit exceeded".to_string(),
+        retry_after: decision.retry_after,
+    })
+}
+
+/// Authenticated requests are limited per user, or per token for tokens not
+/// tied to a user; anonymous requests per client IP.
+fn client_key(req: &Request) -> String {
+    if let Some(ctx) = req.extensions().get::<AuthContext>() {
+        return caller_key(ctx);
+    }
+
+    match req.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+fn caller_key(ctx: &AuthContext) -> String {
+    match (ctx.user_id, ctx.token_id) {
+        (Some(user_id), _) => format!("user:{user_id}"),
+        (None, Some(token_id)) => format!("token:{token_id}"),
+        (None, None) => "admin".to_string(),
+    }
+}
+
+/// Counts one AI call against the caller's quota for the current UTC day.
+/// Called only once a request has been validated, so that requests rejected
+/// before reaching the model are free.
+pub async fn charge_ai_quota(state: &AppState, ctx: &AuthContext) -> Result<(), AppError> {
+    let key = caller_key(ctx);
+    let now = Utc::now();
+    let today = now.date_naive();
+
+    // The conditional update leaves the counter alone once the quota is used
+    // up, in which case no row is returned.
+    let calls = match &state.sqlite {
+        Some(pool) => {
+            sqlx::query_scalar(
+                r#"
+                INSERT INTO ai_usage (client, day, calls) VALUES (?1, ?2, 1)
+                ON CONFLICT (client, day) DO UPDATE SET calls = ai_usage.calls + 1
+                WHERE ai_usage.calls < ?3
+                RETURNING calls
+                "#,
+            )
+            .bind(key)
+            .bind(today)
+            .bind(state.rate_limits.ai_daily_quota)
+            .fetch_optional(pool)
+            .await
+        }
+        None => {
+            sqlx::query_scalar!(
+                r#"
+                INSERT INTO ai_usage (client, day, calls) VALUES ($1, $2, 1)
+                ON CONFLICT (client, day) DO UPDATE SET calls = ai_usage.calls + 1
+                WHERE ai_usage.calls < $3
+                RETURNING calls
+                "#,
+                key,
+                today,
+                state.rate_limits.ai_daily_quota
+            )
+            .fetch_optional(&state.pool)
+            .await
+        }
+    }
+    .map_err(|e| {
+        tracing::error!("Failed to record AI usage: {:?}", e);
+        AppError::Internal("failed to check AI quota".into())
+    })?;
+
+    if calls.is_some_and(|calls| calls <= state.rate_limits.ai_daily_quota) {
+        return Ok(());
+    }
+
+    let tomorrow = today
+        .checked_add_days(Days::new(1))
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc())
+        .unwrap_or(now);
+
+    Err(AppError::TooManyRequests {
+        message: format!(
+            "Daily AI quota of {} calls exceeded",
+            state.rate_limits.ai_daily_quota
+        ),
+        retry_after: (tomorrow - now).num_seconds().max(1) as u64,
+    })
+}
+
+fn rejected(decision: Decision) -> Response {
+    let mut response = AppError::TooManyRequests {
+        message: "Rate limit exceeded".to_string(),
+        retry_after: decision.retry_after,
+    }
+    .into_response();
+    insert_headers(response.headers_mut(), decision);
+    response
+}
+
+fn insert_headers(headers: &mut HeaderMap, decision: Decision) {
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(decision.reset));
+}
@@ -0,0 +1,143 @@
+//! A todo store that lives in memory, for tests.
+
+use std::{cmp::Reverse, sync::Mutex};
+
+use axum::async_trait;
+use uuid::Uuid;
+
+use super::{TodoRepository, apply_changes, new_todo, owns};
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    models::todo::{Priority, SuggestedTodo, Todo, TodoSource},
+    routes::todos::{CreateTodo, UpdateTodo},
+};
+
+/// Holds todos for any number of workspaces, but has no projects, shares or
+/// members: users see only the todos they own, and naming a project fails as
+/// if it did not exist. Records no events and queues no webhooks.
+#[derive(Default)]
+pub struct MemoryTodoRepository {
+    todos: Mutex<Vec<Stored>>,
+}
+
+struct Stored {
+    workspace_id: Uuid,
+    todo: Todo,
+}
+
+impl MemoryTodoRepository {
+    /// Runs `f` on a todo the caller owns.
+    fn with_owned<T>(
+        &self,
+        ctx: &AuthContext,
+        id: Uuid,
+        f: impl FnOnce(&mut Vec<Stored>, usize) -> T,
+    ) -> Result<T, AppError> {
+        let mut todos = self.todos.lock().unwrap();
+        let index = todos
+            .iter()
+            .position(|s| s.workspace_id == ctx.workspace_id && s.todo.id == id)
+            .ok_or(AppError::NotFound)?;
+        if !owns(ctx, &todos[index].todo) {
+            return Err(AppError::Forbidden);
+        }
+        Ok(f(&mut todos, index))
+    }
+
+    fn insert(&self, ctx: &AuthContext, todos: &[Todo]) {
+        self.todos
+            .lock()
+            .unwrap()
+            .extend(todos.iter().map(|todo| Stored {
+                workspace_id: ctx.workspace_id,
+                todo: todo.clone(),
+            }));
+    }
+}
+
+#[async_trait]
+impl TodoRepository for MemoryTodoRepository {
+    async fn create(&self, ctx: &AuthContext, todo: CreateTodo) -> Result<Todo, AppError> {
+        if todo.project_id.is_some() {
+            return Err(AppError::NotFound);
+        }
+
+        let todo = new_todo(
+            ctx,
+            todo.title,
+            todo.description,
+            todo.priority.unwrap_or(Priority::Medium),
+            TodoSource::Manual,
+        );
+        self.insert(ctx, std::slice::from_ref(&todo));
+        Ok(todo)
+    }
+
+    async fn list(
+        &self,
+        ctx: &AuthContext,
+        project_id: Option<Uuid>,
+    ) -> Result<Vec<Todo>, AppError> {
+        let mut todos: Vec<Todo> = self
+            .todos
+            .lock()
+            .unwrap()
+            .iter()
+            // Newest first even among todos created in the same instant.
+            .rev()
+            .filter(|s| s.workspace_id == ctx.workspace_id && owns(ctx, &s.todo))
+            .filter(|s| project_id.is_none_or(|id| s.todo.project_id == Some(id)))
+            .map(|s| s.todo.clone())
+            .collect();
+        todos.sort_by_key(|t| Reverse(t.created_at));
+        Ok(todos)
+    }
+
+    async fn get(&self, ctx: &AuthContext, id: Uuid) -> Result<Todo, AppError> {
+        self.with_owned(ctx, id, |todos, i| todos[i].todo.clone())
+    }
+
+    async fn update(
+        &self,
+        ctx: &AuthContext,
+        id: Uuid,
+        changes: UpdateTodo,
+    ) -> Result<Todo, AppError> {
+        self.with_owned(ctx, id, |todos, i| {
+            let todo = &mut todos[i].todo;
+            apply_changes(todo, changes)?;
+            Ok(todo.clone())
+        })?
+    }
+
+    async fn delete(&self, ctx: &AuthContext, id: Uuid) -> Result<Todo, AppError> {
+        self.with_owned(ctx, id, |todos, i| todos.remove(i).todo)
+    }
+
+    async fn confirm(
+        &self,
+        ctx: &AuthContext,
+        project_id: Option<Uuid>,
+        tasks: Vec<SuggestedTodo>,
+    ) -> Result<Vec<Todo>, AppError> {
+        if project_id.is_some() {
+            return Err(AppError::NotFound);
+        }
+
+        let todos: Vec<Todo> = tasks
+            .into_iter()
+            .map(|task| {
+                new_todo(
+                    ctx,
+                    task.title,
+                    task.description,
+                    task.priority,
+                    TodoSource::Audio,
+                )
+            })
+            .collect();
+        self.insert(ctx, &todos);
+        Ok(todos)
+    }
+}
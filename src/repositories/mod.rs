@@ -0,0 +1,118 @@
+//! Storage behind the todo routes. Handlers reach todos through
+//! [`AppState::todos`](crate::state::AppState::todos) rather than the pool, so
+//! that they can be tested against [`memory::MemoryTodoRepository`] without a
+//! database, and so that todos can be kept in [`sqlite`] instead of
+//! [`postgres`].
+
+use axum::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    models::todo::{Priority, SuggestedTodo, Todo, TodoSource, TodoStatus},
+    routes::todos::{CreateTodo, UpdateTodo},
+};
+
+pub mod memory;
+pub mod postgres;
+pub mod sqlite;
+
+/// Todos in the caller's workspace. Methods taking an ID fail with `NotFound`
+/// when the workspace has no such todo, and with `Forbidden` when the
+/// caller's role on it falls short.
+#[async_trait]
+pub trait TodoRepository: Send + Sync {
+    /// Creates a todo owned by the caller, in `project_id` if given, which
+    /// the caller must be able to edit.
+    async fn create(&self, ctx: &AuthContext, todo: CreateTodo) -> Result<Todo, AppError>;
+
+    /// The todos the caller can see, newest first, optionally only those in
+    /// one project.
+    async fn list(
+        &self,
+        ctx: &AuthContext,
+        project_id: Option<Uuid>,
+    ) -> Result<Vec<Todo>, AppError>;
+
+    async fn get(&self, ctx: &AuthContext, id: Uuid) -> Result<Todo, AppError>;
+
+    /// Applies the fields set in `changes`. Requires the editor role.
+    async fn update(
+        &self,
+        ctx: &AuthContext,
+        id: Uuid,
+        changes: UpdateTodo,
+    ) -> Result<Todo, AppError>;
+
+    /// Deletes a todo and returns it as it was. Requires the owner role.
+    async fn delete(&self, ctx: &AuthContext, id: Uuid) -> Result<Todo, AppError>;
+
+    /// Creates a todo from each suggestion the caller accepted, all or none,
+    /// in `project_id` if given, which the caller must be able to edit.
+    async fn confirm(
+        &self,
+        ctx: &AuthContext,
+        project_id: Option<Uuid>,
+        tasks: Vec<SuggestedTodo>,
+    ) -> Result<Vec<Todo>, AppError>;
+}
+
+// Shared by the stores that have no projects or shares, where the caller's
+// only possible role is owner.
+
+/// Service accounts own everything, as in PostgreSQL.
+fn owns(ctx: &AuthContext, todo: &Todo) -> bool {
+    ctx.user_id
+        .is_none_or(|user_id| todo.owner_id == Some(user_id))
+}
+
+/// A new todo owned by the caller, outside any project.
+fn new_todo(
+    ctx: &AuthContext,
+    title: String,
+    description: Option<String>,
+    priority: Priority,
+    source: TodoSource,
+) -> Todo {
+    let now = Utc::now();
+    Todo {
+        id: Uuid::new_v4(),
+        title,
+        description,
+        status: TodoStatus::Todo,
+        priority,
+        source,
+        owner_id: ctx.user_id,
+        project_id: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+/// Applies the fields set in `changes`, bumping `updated_at` if anything
+/// changed. Moving to a project fails as if the project did not exist.
+fn apply_changes(todo: &mut Todo, changes: UpdateTodo) -> Result<(), AppError> {
+    if changes.project_id.is_some() {
+        return Err(AppError::NotFound);
+    }
+
+    let original = todo.clone();
+    if let Some(title) = changes.title {
+        todo.title = title;
+    }
+    if let Some(description) = changes.description {
+        todo.description = Some(description);
+    }
+    if let Some(status) = changes.status {
+        todo.status = status;
+    }
+    if let Some(priority) = changes.priority {
+        todo.priority = priority;
+    }
+    if *todo != original {
+        todo.updated_at = Utc::now();
+    }
+    Ok(())
+}
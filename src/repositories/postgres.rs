@@ -0,0 +1,517 @@
+//! The todo store in PostgreSQL. Each [`TodoRepository`] call runs in its own
+//! tenant transaction; the functions below take the caller's transaction
+//! instead, for the sync, gRPC and GraphQL code that groups several changes
+//! into one.
+
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+use super::TodoRepository;
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    models::{
+        event::TodoEventKind,
+        share::Role,
+        todo::{Priority, SuggestedTodo, Todo, TodoSource, TodoStatus},
+        webhook::WebhookEvent,
+    },
+    permissions,
+    routes::todos::{CreateTodo, UpdateTodo},
+    services::{
+        crdt::{CrdtState, FieldValue, HlcClock, Op, OpKind},
+        events, webhooks,
+    },
+    state,
+};
+
+pub struct PgTodoRepository {
+    pool: PgPool,
+    clock: HlcClock,
+}
+
+impl PgTodoRepository {
+    /// `clock` stamps the CRDT operations updates become; pass the server's,
+    /// so that they are ordered with those made elsewhere.
+    pub fn new(pool: PgPool, clock: HlcClock) -> Self {
+        Self { pool, clock }
+    }
+}
+
+async fn commit(tx: Transaction<'static, Postgres>, action: &str) -> Result<(), AppError> {
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit {} todo: {:?}", action, e);
+        AppError::Internal(format!("failed to {action} todo"))
+    })
+}
+
+#[async_trait]
+impl TodoRepository for PgTodoRepository {
+    async fn create(&self, ctx: &AuthContext, todo: CreateTodo) -> Result<Todo, AppError> {
+        let mut tx = state::begin_tenant(&self.pool, ctx).await?;
+        let todo = insert_todo(&mut tx, ctx, Uuid::new_v4(), todo).await?;
+        commit(tx, "create").await?;
+        Ok(todo)
+    }
+
+    async fn list(
+        &self,
+        ctx: &AuthContext,
+        project_id: Option<Uuid>,
+    ) -> Result<Vec<Todo>, AppError> {
+        let mut tx = state::begin_tenant(&self.pool, ctx).await?;
+
+        sqlx::query_as!(
+            Todo,
+            r#"
+            SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+            FROM todos
+            WHERE ($1::uuid IS NULL OR todo_role(id, $1) IS NOT NULL)
+              AND ($2::uuid IS NULL OR project_id = $2)
+            ORDER BY created_at DESC
+            "#,
+            ctx.user_id,
+            project_id
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list todos: {:?}", e);
+            AppError::Internal("failed to list todos".into())
+        })
+    }
+
+    async fn get(&self, ctx: &AuthContext, id: Uuid) -> Result<Todo, AppError> {
+        let mut tx = state::begin_tenant(&self.pool, ctx).await?;
+        fetch_todo(&mut tx, ctx, id).await
+    }
+
+    async fn update(
+        &self,
+        ctx: &AuthContext,
+        id: Uuid,
+        changes: UpdateTodo,
+    ) -> Result<Todo, AppError> {
+        let mut tx = state::begin_tenant(&self.pool, ctx).await?;
+        let todo = apply_update(&mut tx, ctx, &self.clock, id, changes).await?;
+        commit(tx, "update").await?;
+        Ok(todo)
+    }
+
+    async fn delete(&self, ctx: &AuthContext, id: Uuid) -> Result<Todo, AppError> {
+        let mut tx = state::begin_tenant(&self.pool, ctx).await?;
+        let todo = remove_todo(&mut tx, ctx, id).await?;
+        commit(tx, "delete").await?;
+        Ok(todo)
+    }
+
+    async fn confirm(
+        &self,
+        ctx: &AuthContext,
+        project_id: Option<Uuid>,
+        tasks: Vec<SuggestedTodo>,
+    ) -> Result<Vec<Todo>, AppError> {
+        let mut tx = state::begin_tenant(&self.pool, ctx).await?;
+        if let Some(project_id) = project_id {
+            permissions::authorize_project(&mut *tx, ctx, project_id, Role::Editor).await?;
+        }
+
+        let mut todos = Vec::with_capacity(tasks.len());
+        for suggested in tasks {
+            let id = Uuid::new_v4();
+            let now = Utc::now();
+
+            let status = TodoStatus::Todo.to_string();
+            let priority = suggested.priority.to_string();
+            let source = TodoSource::Audio.to_string();
+
+            let todo = sqlx::query_as!(
+                Todo,
+                r#"
+                INSERT INTO todos (id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                RETURNING id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+                "#,
+                id,
+                suggested.title,
+                suggested.description,
+                status,
+                priority,
+                source,
+                ctx.user_id,
+                project_id,
+                now,
+                now
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+            events::record(&mut tx, TodoEventKind::Created, &todo).await?;
+            webhooks::enqueue(&mut tx, WebhookEvent::TodoCreated, &todo).await?;
+            todos.push(todo);
+        }
+
+        let event = AudioConfirmedEvent {
+            project_id,
+            todos: &todos,
+        };
+        webhooks::enqueue(&mut tx, WebhookEvent::AudioConfirmed, &event).await?;
+
+        tx.commit().await.map_err(|e| {
+            tracing::error!("Failed to commit confirm tasks: {:?}", e);
+            AppError::Internal("failed to confirm tasks".into())
+        })?;
+
+        Ok(todos)
+    }
+}
+
+/// Payload of the `audio.confirmed` webhook event.
+#[derive(Debug, Serialize)]
+struct AudioConfirmedEvent<'a> {
+    project_id: Option<Uuid>,
+    todos: &'a [Todo],
+}
+
+/// Creates a todo owned by the caller, records the change and queues
+/// webhooks. Shared by the REST and sync endpoints; runs on the caller's
+/// tenant transaction.
+pub(crate) async fn insert_todo(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    id: Uuid,
+    payload: CreateTodo,
+) -> Result<Todo, AppError> {
+    if let Some(project_id) = payload.project_id {
+        permissions::authorize_project(&mut *conn, ctx, project_id, Role::Editor).await?;
+    }
+
+    let now = Utc::now();
+    let new_todo = Todo {
+        id,
+        title: payload.title,
+        description: payload.description,
+        status: TodoStatus::Todo,
+        priority: payload.priority.unwrap_or(Priority::Medium),
+        source: TodoSource::Manual,
+        owner_id: ctx.user_id,
+        project_id: payload.project_id,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let todo = sqlx::query_as!(
+        Todo,
+        r#"
+        INSERT INTO todos (id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+        "#,
+        new_todo.id,
+        new_todo.title,
+        new_todo.description,
+        new_todo.status.to_string(),
+        new_todo.priority.to_string(),
+        new_todo.source.to_string(),
+        new_todo.owner_id,
+        new_todo.project_id,
+        new_todo.created_at,
+        new_todo.updated_at
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| match e {
+        // Sync clients choose their own ids; one may already be taken by a
+        // todo the caller cannot see.
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::Conflict("a todo with this id already exists".into())
+        }
+        e => {
+            tracing::error!("Failed to create todo: {:?}", e);
+            AppError::Internal("failed to create todo".into())
+        }
+    })?;
+
+    events::record(conn, TodoEventKind::Created, &todo).await?;
+    webhooks::enqueue(conn, WebhookEvent::TodoCreated, &todo).await?;
+
+    Ok(todo)
+}
+
+/// Loads a todo the caller can view.
+pub(crate) async fn fetch_todo(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    id: Uuid,
+) -> Result<Todo, AppError> {
+    permissions::authorize_todo(&mut *conn, ctx, id, Role::Viewer).await?;
+
+    sqlx::query_as!(
+        Todo,
+        r#"
+        SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+        FROM todos WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get todo: {:?}", e);
+        AppError::Internal("failed to get todo".into())
+    })?
+    .ok_or(AppError::NotFound)
+}
+
+/// One page of the todos the caller can see, newest first, starting after
+/// the `(created_at, id)` of the last todo of the previous page.
+pub(crate) async fn list_page(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    project_id: Option<Uuid>,
+    status: Option<TodoStatus>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    limit: i64,
+) -> Result<Vec<Todo>, AppError> {
+    let (after_created_at, after_id) = after.unzip();
+
+    sqlx::query_as!(
+        Todo,
+        r#"
+        SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+        FROM todos
+        WHERE ($1::uuid IS NULL OR todo_role(id, $1) IS NOT NULL)
+          AND ($2::uuid IS NULL OR project_id = $2)
+          AND ($3::text IS NULL OR status = $3)
+          AND ($4::timestamptz IS NULL OR (created_at, id) < ($4, $5))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $6
+        "#,
+        ctx.user_id,
+        project_id,
+        status.map(|s| s.to_string()),
+        after_created_at,
+        after_id,
+        limit
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list todos: {:?}", e);
+        AppError::Internal("failed to list todos".into())
+    })
+}
+
+/// Applies a partial update, records the change and queues webhooks. Each
+/// changed field becomes a CRDT operation stamped with the server's clock,
+/// so the update merges with concurrent offline edits.
+pub(crate) async fn apply_update(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    clock: &HlcClock,
+    id: Uuid,
+    payload: UpdateTodo,
+) -> Result<Todo, AppError> {
+    let (todo, _) = merge_ops(conn, ctx, id, |state| {
+        let mut ops = Vec::new();
+        let mut set = |value| {
+            ops.push(Op {
+                hlc: clock.now(),
+                kind: OpKind::Set(value),
+            })
+        };
+        if let Some(title) = payload.title {
+            set(FieldValue::Title(title));
+        }
+        if let Some(status) = payload.status {
+            set(FieldValue::Status(status));
+        }
+        if let Some(priority) = payload.priority {
+            set(FieldValue::Priority(priority));
+        }
+        if let Some(project_id) = payload.project_id {
+            set(FieldValue::ProjectId(Some(project_id)));
+        }
+        if let Some(description) = payload.description {
+            ops.extend(state.replace_description(clock, &description));
+        }
+        ops
+    })
+    .await?;
+
+    Ok(todo)
+}
+
+/// Merges CRDT operations into a todo and stores them in its operation log.
+/// `build` receives the current merge state and returns the operations;
+/// operations already applied are skipped. When the merged todo differs, it
+/// is saved, the change recorded and webhooks queued.
+pub(crate) async fn merge_ops(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    id: Uuid,
+    build: impl FnOnce(&CrdtState) -> Vec<Op>,
+) -> Result<(Todo, CrdtState), AppError> {
+    let db_err = |e: sqlx::Error| {
+        tracing::error!("Failed to merge todo operations: {:?}", e);
+        AppError::Internal("failed to update todo".into())
+    };
+
+    permissions::authorize_todo(&mut *conn, ctx, id, Role::Editor).await?;
+    // Makes the operation log readable in `seq` order, like the event log.
+    events::lock_workspace(conn).await?;
+
+    let original = sqlx::query_as!(
+        Todo,
+        "SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at FROM todos WHERE id = $1 FOR UPDATE",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_err)?
+    .ok_or(AppError::NotFound)?;
+
+    let mut state = sqlx::query_scalar!(
+        r#"SELECT state AS "state: sqlx::types::Json<CrdtState>" FROM todo_crdt WHERE todo_id = $1"#,
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_err)?
+    .map(|state| state.0)
+    .unwrap_or_else(|| CrdtState::from_todo(&original));
+
+    let mut ops = build(&state);
+    ops.sort_by_key(|op| op.hlc);
+
+    let now = Utc::now();
+    let mut todo = original.clone();
+    for op in &ops {
+        let logged = sqlx::query_scalar!(
+            r#"
+            INSERT INTO todo_ops (todo_id, hlc, op, created_by, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (todo_id, hlc) DO NOTHING
+            RETURNING seq
+            "#,
+            id,
+            op.hlc.to_string(),
+            sqlx::types::Json(op) as _,
+            ctx.user_id,
+            now
+        )
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(db_err)?;
+
+        if logged.is_some() {
+            state.apply(&mut todo, op)?;
+        }
+    }
+
+    if todo
+        .description
+        .as_ref()
+        .is_some_and(|d| d.chars().count() > 500)
+    {
+        return Err(AppError::invalid_field(
+            "description",
+            "description must be at most 500 characters",
+        ));
+    }
+    if todo.project_id != original.project_id
+        && let Some(project_id) = todo.project_id
+    {
+        permissions::authorize_project(&mut *conn, ctx, project_id, Role::Editor).await?;
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_crdt (todo_id, state, updated_at) VALUES ($1, $2, $3)
+        ON CONFLICT (todo_id) DO UPDATE SET state = EXCLUDED.state, updated_at = EXCLUDED.updated_at
+        "#,
+        id,
+        sqlx::types::Json(&state) as _,
+        now
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    if todo == original {
+        return Ok((todo, state));
+    }
+
+    let updated_todo = sqlx::query_as!(
+        Todo,
+        r#"
+        UPDATE todos 
+        SET title = $1, description = $2, status = $3, priority = $4, project_id = $5, updated_at = $6
+        WHERE id = $7
+        RETURNING id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+        "#,
+        todo.title,
+        todo.description,
+        todo.status.to_string(),
+        todo.priority.to_string(),
+        todo.project_id,
+        now,
+        id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update todo: {:?}", e);
+        AppError::Internal("failed to update todo".into())
+    })?;
+
+    events::record(conn, TodoEventKind::Updated, &updated_todo).await?;
+    webhooks::enqueue(conn, WebhookEvent::TodoUpdated, &updated_todo).await?;
+    if original.status != TodoStatus::Done && updated_todo.status == TodoStatus::Done {
+        webhooks::enqueue(conn, WebhookEvent::TodoCompleted, &updated_todo).await?;
+    }
+
+    Ok((updated_todo, state))
+}
+
+/// Deletes a todo, leaving a tombstone for sync clients, records the change
+/// and queues webhooks. Returns the todo as it was.
+pub(crate) async fn remove_todo(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    id: Uuid,
+) -> Result<Todo, AppError> {
+    permissions::authorize_todo(&mut *conn, ctx, id, Role::Owner).await?;
+
+    let todo = sqlx::query_as!(
+        Todo,
+        "SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at FROM todos WHERE id = $1",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to fetch todo for delete: {:?}", e);
+        AppError::Internal("failed to delete todo".into())
+    })?
+    .ok_or(AppError::NotFound)?;
+
+    // Recorded first: the event captures who could see the todo.
+    events::record(conn, TodoEventKind::Deleted, &todo).await?;
+
+    sqlx::query!("DELETE FROM todos WHERE id = $1", id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete todo: {:?}", e);
+            AppError::Internal("failed to delete todo".into())
+        })?;
+
+    webhooks::enqueue(conn, WebhookEvent::TodoDeleted, &todo).await?;
+
+    Ok(todo)
+}
@@ -0,0 +1,364 @@
+//! The todo store in SQLite, for single-user deployments without
+//! PostgreSQL. The schema is in `migrations_sqlite/`. There are no projects,
+//! shares, events or webhooks; updates overwrite fields rather than merging
+//! CRDT operations, since there are no sync clients to merge with.
+//!
+//! Queries are checked at run time: the query macros only know the
+//! PostgreSQL schema.
+
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::{Uuid, fmt::Hyphenated};
+
+use super::{TodoRepository, apply_changes, new_todo, owns};
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    models::todo::{Priority, SuggestedTodo, Todo, TodoSource},
+    routes::todos::{CreateTodo, UpdateTodo},
+};
+
+pub struct SqliteTodoRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteTodoRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+/// A row of `todos`. IDs are stored as hyphenated text so that the file can
+/// be read with the `sqlite3` shell.
+#[derive(sqlx::FromRow)]
+struct TodoRow {
+    id: Hyphenated,
+    title: String,
+    description: Option<String>,
+    status: String,
+    priority: String,
+    source: String,
+    owner_id: Option<Hyphenated>,
+    project_id: Option<Hyphenated>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<TodoRow> for Todo {
+    fn from(row: TodoRow) -> Self {
+        Todo {
+            id: row.id.into_uuid(),
+            title: row.title,
+            description: row.description,
+            status: row.status.into(),
+            priority: row.priority.into(),
+            source: row.source.into(),
+            owner_id: row.owner_id.map(Hyphenated::into_uuid),
+            project_id: row.project_id.map(Hyphenated::into_uuid),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+fn db_err(action: &'static str) -> impl Fn(sqlx::Error) -> AppError {
+    move |e| {
+        tracing::error!("Failed to {} todo: {:?}", action, e);
+        AppError::Internal(format!("failed to {action} todo"))
+    }
+}
+
+async fn insert(
+    conn: &mut SqliteConnection,
+    ctx: &AuthContext,
+    todo: &Todo,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO todos (id, workspace_id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(todo.id.hyphenated())
+    .bind(ctx.workspace_id.hyphenated())
+    .bind(&todo.title)
+    .bind(&todo.description)
+    .bind(todo.status.to_string())
+    .bind(todo.priority.to_string())
+    .bind(todo.source.to_string())
+    .bind(todo.owner_id.map(Uuid::hyphenated))
+    .bind(todo.project_id.map(Uuid::hyphenated))
+    .bind(todo.created_at)
+    .bind(todo.updated_at)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Loads a todo the caller owns.
+async fn fetch_owned(
+    conn: &mut SqliteConnection,
+    ctx: &AuthContext,
+    id: Uuid,
+    action: &'static str,
+) -> Result<Todo, AppError> {
+    let todo: Todo = sqlx::query_as::<_, TodoRow>(
+        r#"
+        SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+        FROM todos WHERE id = ? AND workspace_id = ?
+        "#,
+    )
+    .bind(id.hyphenated())
+    .bind(ctx.workspace_id.hyphenated())
+    .fetch_optional(conn)
+    .await
+    .map_err(db_err(action))?
+    .ok_or(AppError::NotFound)?
+    .into();
+
+    if !owns(ctx, &todo) {
+        return Err(AppError::Forbidden);
+    }
+    Ok(todo)
+}
+
+#[async_trait]
+impl TodoRepository for SqliteTodoRepository {
+    async fn create(&self, ctx: &AuthContext, todo: CreateTodo) -> Result<Todo, AppError> {
+        if todo.project_id.is_some() {
+            return Err(AppError::NotFound);
+        }
+
+        let todo = new_todo(
+            ctx,
+            todo.title,
+            todo.description,
+            todo.priority.unwrap_or(Priority::Medium),
+            TodoSource::Manual,
+        );
+        let mut conn = self.pool.acquire().await.map_err(db_err("create"))?;
+        insert(&mut conn, ctx, &todo)
+            .await
+            .map_err(db_err("create"))?;
+        Ok(todo)
+    }
+
+    async fn list(
+        &self,
+        ctx: &AuthContext,
+        project_id: Option<Uuid>,
+    ) -> Result<Vec<Todo>, AppError> {
+        let rows = sqlx::query_as::<_, TodoRow>(
+            r#"
+            SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at
+            FROM todos
+            WHERE workspace_id = ?1
+              AND (?2 IS NULL OR owner_id = ?2)
+              AND (?3 IS NULL OR project_id = ?3)
+            ORDER BY created_at DESC, rowid DESC
+            "#,
+        )
+        .bind(ctx.workspace_id.hyphenated())
+        .bind(ctx.user_id.map(Uuid::hyphenated))
+        .bind(project_id.map(Uuid::hyphenated))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list todos: {:?}", e);
+            AppError::Internal("failed to list todos".into())
+        })?;
+
+        Ok(rows.into_iter().map(Todo::from).collect())
+    }
+
+    async fn get(&self, ctx: &AuthContext, id: Uuid) -> Result<Todo, AppError> {
+        let mut conn = self.pool.acquire().await.map_err(db_err("get"))?;
+        fetch_owned(&mut conn, ctx, id, "get").await
+    }
+
+    async fn update(
+        &self,
+        ctx: &AuthContext,
+        id: Uuid,
+        changes: UpdateTodo,
+    ) -> Result<Todo, AppError> {
+        let mut tx = self.pool.begin().await.map_err(db_err("update"))?;
+        let mut todo = fetch_owned(&mut tx, ctx, id, "update").await?;
+        let original = todo.clone();
+        apply_changes(&mut todo, changes)?;
+        if todo == original {
+            return Ok(todo);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE todos
+            SET title = ?, description = ?, status = ?, priority = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&todo.title)
+        .bind(&todo.description)
+        .bind(todo.status.to_string())
+        .bind(todo.priority.to_string())
+        .bind(todo.updated_at)
+        .bind(id.hyphenated())
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err("update"))?;
+
+        tx.commit().await.map_err(db_err("update"))?;
+        Ok(todo)
+    }
+
+    async fn delete(&self, ctx: &AuthContext, id: Uuid) -> Result<Todo, AppError> {
+        let mut tx = self.pool.begin().await.map_err(db_err("delete"))?;
+        let todo = fetch_owned(&mut tx, ctx, id, "delete").await?;
+
+        sqlx::query("DELETE FROM todos WHERE id = ?")
+            .bind(id.hyphenated())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_err("delete"))?;
+
+        tx.commit().await.map_err(db_err("delete"))?;
+        Ok(todo)
+    }
+
+    async fn confirm(
+        &self,
+        ctx: &AuthContext,
+        project_id: Option<Uuid>,
+        tasks: Vec<SuggestedTodo>,
+    ) -> Result<Vec<Todo>, AppError> {
+        if project_id.is_some() {
+            return Err(AppError::NotFound);
+        }
+
+        let db_err = |e: sqlx::Error| {
+            tracing::error!("Failed to confirm tasks: {:?}", e);
+            AppError::Internal("failed to confirm tasks".into())
+        };
+
+        let mut tx = self.pool.begin().await.map_err(db_err)?;
+        let mut todos = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            let todo = new_todo(
+                ctx,
+                task.title,
+                task.description,
+                task.priority,
+                TodoSource::Audio,
+            );
+            insert(&mut tx, ctx, &todo).await.map_err(db_err)?;
+            todos.push(todo);
+        }
+        tx.commit().await.map_err(db_err)?;
+
+        Ok(todos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::migrate::SQLITE_MIGRATOR,
+        models::{todo::TodoStatus, token::Scopes, workspace::DEFAULT_WORKSPACE_ID},
+    };
+
+    async fn repository() -> SqliteTodoRepository {
+        // Each connection to `:memory:` gets its own database.
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        SQLITE_MIGRATOR.run(&pool).await.unwrap();
+        SqliteTodoRepository::new(pool)
+    }
+
+    /// The bootstrap token, the only caller SQLite storage serves.
+    fn admin() -> AuthContext {
+        AuthContext {
+            token_id: None,
+            user_id: None,
+            workspace_id: DEFAULT_WORKSPACE_ID,
+            scopes: Scopes::all(),
+        }
+    }
+
+    fn create(title: &str) -> CreateTodo {
+        CreateTodo {
+            title: title.into(),
+            description: Some("details".into()),
+            priority: Some(Priority::High),
+            project_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn todos_round_trip() {
+        let todos = repository().await;
+        let ctx = admin();
+        let first = todos.create(&ctx, create("Buy milk")).await.unwrap();
+        let second = todos.create(&ctx, create("Call Bob")).await.unwrap();
+
+        assert_eq!(todos.get(&ctx, first.id).await.unwrap(), first);
+        assert_eq!(
+            todos.list(&ctx, None).await.unwrap(),
+            vec![second, first.clone()]
+        );
+
+        let changes = UpdateTodo {
+            status: Some(TodoStatus::Done),
+            ..Default::default()
+        };
+        let updated = todos.update(&ctx, first.id, changes).await.unwrap();
+        assert_eq!(updated.status, TodoStatus::Done);
+        assert_eq!(updated.title, first.title);
+        assert_eq!(todos.get(&ctx, first.id).await.unwrap(), updated);
+
+        assert_eq!(todos.delete(&ctx, first.id).await.unwrap(), updated);
+        assert!(matches!(
+            todos.get(&ctx, first.id).await,
+            Err(AppError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn confirmed_suggestions_become_audio_todos() {
+        let todos = repository().await;
+        let ctx = admin();
+        let tasks = vec![SuggestedTodo {
+            title: "Book flights".into(),
+            description: None,
+            priority: Priority::Low,
+        }];
+
+        let confirmed = todos.confirm(&ctx, None, tasks).await.unwrap();
+
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].source, TodoSource::Audio);
+        assert_eq!(confirmed[0].priority, Priority::Low);
+        assert_eq!(todos.list(&ctx, None).await.unwrap(), confirmed);
+    }
+
+    #[tokio::test]
+    async fn projects_do_not_exist() {
+        let todos = repository().await;
+        let ctx = admin();
+        let mut payload = create("Buy milk");
+        payload.project_id = Some(Uuid::new_v4());
+
+        assert!(matches!(
+            todos.create(&ctx, payload).await,
+            Err(AppError::NotFound)
+        ));
+        assert!(matches!(
+            todos.confirm(&ctx, Some(Uuid::new_v4()), Vec::new()).await,
+            Err(AppError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,49 @@
+//! Request IDs, for correlating a response with the logs and traces of the
+//! request that produced it.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied ID that is kept rather than replaced.
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Uses the request's `X-Request-Id`, or a new UUID if it has none or an
+/// unusable one, for the rest of the request, and echoes it in the response.
+/// Must be the outermost layer so that everything else sees the ID.
+pub async fn assign(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+    // Only visible ASCII gets here, so this cannot fail.
+    let header = HeaderValue::from_str(&id).unwrap();
+    // Rewritten for the layers inside, such as the trace span.
+    req.headers_mut().insert(X_REQUEST_ID, header.clone());
+
+    let mut response = REQUEST_ID.scope(id, next.run(req)).await;
+    response.headers_mut().insert(X_REQUEST_ID, header);
+    response
+}
+
+/// The ID of the request being handled, if any. Not available in tasks the
+/// request spawns, nor to gRPC calls.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
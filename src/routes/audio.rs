@@ -1,23 +1,55 @@
+use std::time::Instant;
+
 use axum::{
-    Json,
+    Extension, Json,
     extract::{Multipart, State},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-use crate::{error::AppError, state::AppState};
+use crate::{
+    auth::AuthContext,
+    error::{AppError, ErrorResponse},
+    rate_limit,
+    state::AppState,
+};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ConfirmTasksRequest {
     pub tasks: Vec<crate::models::todo::SuggestedTodo>,
+    /// Project to add the confirmed todos to, if any.
+    pub project_id: Option<uuid::Uuid>,
+}
+
+/// Multipart body of `POST /audio/suggest`. Only documents the form; the
+/// handler reads the fields itself.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct AudioUpload {
+    /// The recording. Its content type defaults to `audio/mpeg`.
+    #[schema(value_type = String, format = Binary)]
+    audio: Vec<u8>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SuggestedTasksResponse {
     pub tasks: Vec<crate::models::todo::SuggestedTodo>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/audio/suggest",
+    tag = "audio",
+    request_body(content = AudioUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Suggested todos; nothing is created yet", body = SuggestedTasksResponse),
+        (status = 429, description = "Rate limit or daily AI quota exceeded", body = ErrorResponse),
+        (status = 500, description = "No audio was sent or the model failed", body = ErrorResponse),
+    ),
+)]
 pub async fn suggest_tasks(
     State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
     mut multipart: Multipart,
 ) -> Result<Json<SuggestedTasksResponse>, AppError> {
     let mut audio_data = Vec::new();
@@ -39,44 +71,114 @@ pub async fn suggest_tasks(
         }
     }
 
+    let tasks = suggest(&state, &ctx, audio_data, &mime_type).await?;
+
+    Ok(Json(SuggestedTasksResponse { tasks }))
+}
+
+/// Asks the model for todos mentioned in a recording, charging the caller's
+/// daily AI quota. Shared by the REST and gRPC endpoints.
+pub(crate) async fn suggest(
+    state: &AppState,
+    ctx: &AuthContext,
+    audio_data: Vec<u8>,
+    mime_type: &str,
+) -> Result<Vec<crate::models::todo::SuggestedTodo>, AppError> {
     if audio_data.is_empty() {
         return Err(AppError::Internal("No audio data provided".to_string()));
     }
+    rate_limit::charge_ai_quota(state, ctx).await?;
+    state.metrics.observe_audio_upload(audio_data.len());
 
-    let tasks = state.gemini.suggest_tasks(audio_data, &mime_type).await?;
-
-    Ok(Json(SuggestedTasksResponse { tasks }))
+    let started = Instant::now();
+    let result = state.gemini.suggest_tasks(audio_data, mime_type).await;
+    state
+        .metrics
+        .observe_gemini(result.is_ok(), started.elapsed());
+    if let Ok(tasks) = &result {
+        state.metrics.add_suggested(tasks.len());
+    }
+    result
 }
 
+#[utoipa::path(
+    post,
+    path = "/audio/confirm",
+    tag = "audio",
+    request_body = ConfirmTasksRequest,
+    responses(
+        (status = 201, description = "The todos were created"),
+        (status = 403, description = "Not an editor of the project", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
 pub async fn confirm_tasks(
     State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
     Json(req): Json<ConfirmTasksRequest>,
 ) -> Result<StatusCode, AppError> {
-    for suggested in req.tasks {
-        let id = uuid::Uuid::new_v4();
-        let now = chrono::Utc::now();
+    let todos = state.todos.confirm(&ctx, req.project_id, req.tasks).await?;
+    state.metrics.add_confirmed(todos.len());
+
+    Ok(StatusCode::CREATED)
+}
+
+use axum::http::StatusCode;
 
-        let status = crate::models::todo::TodoStatus::Todo.to_string();
-        let priority = suggested.priority.to_string();
-        let source = crate::models::todo::TodoSource::Audio.to_string();
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+    use uuid::Uuid;
 
+    use super::*;
+    use crate::{config::Config, models::token::Scopes};
+
+    fn state(pool: PgPool, ai_daily_quota: i32) -> AppState {
+        let mut config = Config::default();
+        config.gemini.api_key = Some("unused".into());
+        config.rate_limits.ai_daily_quota = ai_daily_quota;
+        AppState::new(pool, config).unwrap()
+    }
+
+    fn admin() -> AuthContext {
+        AuthContext {
+            token_id: None,
+            user_id: None,
+            workspace_id: Uuid::new_v4(),
+            scopes: Scopes::all(),
+        }
+    }
+
+    async fn charged(pool: &PgPool) -> i64 {
+        sqlx::query_scalar!(r#"SELECT COALESCE(SUM(calls), 0) AS "calls!" FROM ai_usage"#)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn empty_uploads_are_free(pool: PgPool) {
+        let state = state(pool.clone(), 10);
+
+        let result = suggest(&state, &admin(), Vec::new(), "audio/mpeg").await;
+        assert!(result.is_err());
+        assert_eq!(charged(&pool).await, 0);
+    }
+
+    #[sqlx::test]
+    async fn calls_over_the_quota_are_free(pool: PgPool) {
+        let state = state(pool.clone(), 1);
         sqlx::query!(
-            "INSERT INTO todos (id, title, description, status, priority, source, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
-            id,
-            suggested.title,
-            suggested.description,
-            status,
-            priority,
-            source,
-            now,
-            now
+            "INSERT INTO ai_usage (client, day, calls) VALUES ('admin', $1, 1)",
+            chrono::Utc::now().date_naive()
         )
-        .execute(&state.pool)
+        .execute(&pool)
         .await
-        .map_err(|e| AppError::Internal(e.to_string()))?;
-    }
+        .unwrap();
 
-    Ok(StatusCode::CREATED)
+        // Refused before the model is called.
+        let result = suggest(&state, &admin(), vec![0; 16], "audio/mpeg").await;
+        assert!(matches!(result, Err(AppError::TooManyRequests { .. })));
+        assert_eq!(charged(&pool).await, 1);
+    }
 }
-
-use axum::http::StatusCode;
@@ -0,0 +1,362 @@
+use axum::{
+    Extension, Json,
+    extract::{Query, State},
+    http::StatusCode,
+    response::Redirect,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    auth::{self, AuthContext},
+    error::{AppError, ErrorResponse},
+    models::user::User,
+    routes::totp,
+    services::oidc::{IdTokenClaims, LoginChallenge},
+    state::AppState,
+};
+
+/// How long a user has to complete the login at the identity provider.
+const LOGIN_STATE_TTL: Duration = Duration::minutes(10);
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Session {
+        token: String,
+        expires_at: DateTime<Utc>,
+        user: User,
+    },
+    /// The user has two-factor authentication enabled; the login must be
+    /// completed with `POST /auth/totp/verify`.
+    MfaRequired {
+        mfa_required: bool,
+        mfa_token: String,
+        expires_at: DateTime<Utc>,
+    },
+}
+
+/// Starts an authorization-code-with-PKCE login by redirecting to the
+/// identity provider.
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/login",
+    tag = "auth",
+    responses(
+        (status = 303, description = "Redirect to the identity provider"),
+        (status = 404, description = "Single sign-on is not configured", body = ErrorResponse),
+    ),
+    security(()),
+)]
+pub async fn oidc_login(State(state): State<AppState>) -> Result<Redirect, AppError> {
+    let oidc = state.oidc.as_ref().ok_or(AppError::NotFound)?;
+    let challenge = LoginChallenge::generate();
+    let url = oidc.authorization_url(&challenge).await?;
+    let now = Utc::now();
+
+    // Abandoned logins are cleaned up opportunistically.
+    sqlx::query!(
+        "DELETE FROM oidc_login_states WHERE created_at < $1",
+        now - LOGIN_STATE_TTL
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to prune login states: {:?}", e);
+        AppError::Internal("failed to start login".into())
+    })?;
+
+    sqlx::query!(
+        "INSERT INTO oidc_login_states (state, nonce, code_verifier, created_at) VALUES ($1, $2, $3, $4)",
+        challenge.state,
+        challenge.nonce,
+        challenge.code_verifier,
+        now
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to store login state: {:?}", e);
+        AppError::Internal("failed to start login".into())
+    })?;
+
+    Ok(Redirect::to(&url))
+}
+
+/// Completes the login: validates the returned code and ID token, then
+/// provisions or links the local user and issues a session token.
+#[utoipa::path(
+    get,
+    path = "/auth/oidc/callback",
+    tag = "auth",
+    params(
+        CallbackParams,
+    ),
+    responses(
+        (status = 200, description = "A session, or a second factor is required", body = LoginResponse),
+        (status = 400, description = "The login failed or expired", body = ErrorResponse),
+        (status = 404, description = "Single sign-on is not configured", body = ErrorResponse),
+    ),
+    security(()),
+)]
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    Query(params): Query<CallbackParams>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let oidc = state.oidc.as_ref().ok_or(AppError::NotFound)?;
+
+    if let Some(error) = params.error {
+        let message = params.error_description.unwrap_or(error);
+        return Err(AppError::invalid_field("error", &message));
+    }
+    let code = params
+        .code
+        .ok_or_else(|| AppError::invalid_field("code", "code is required"))?;
+    let login_state = params
+        .state
+        .ok_or_else(|| AppError::invalid_field("state", "state is required"))?;
+
+    // States are single-use: consume it whether or not the exchange succeeds.
+    let login = sqlx::query!(
+        "DELETE FROM oidc_login_states WHERE state = $1 AND created_at > $2 RETURNING nonce, code_verifier",
+        login_state,
+        Utc::now() - LOGIN_STATE_TTL
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to load login state: {:?}", e);
+        AppError::Internal("failed to complete login".into())
+    })?
+    .ok_or_else(|| AppError::invalid_field("state", "unknown or expired login state"))?;
+
+    let claims = oidc
+        .exchange_code(&code, &login.code_verifier, &login.nonce)
+        .await?;
+    let user = provision_user(&state.pool, &claims).await?;
+
+    if user.totp_enabled {
+        let (mfa_token, expires_at) = totp::start_challenge(&state.pool, user.id).await?;
+        return Ok(Json(LoginResponse::MfaRequired {
+            mfa_required: true,
+            mfa_token,
+            expires_at,
+        }));
+    }
+
+    let (token, expires_at) = auth::create_session(&state.pool, user.id).await?;
+
+    tracing::info!(user_id = %user.id, "User logged in via OIDC");
+
+    Ok(Json(LoginResponse::Session {
+        token,
+        expires_at,
+        user,
+    }))
+}
+
+/// Finds the user linked to the identity in `claims`. Unknown identities are
+/// linked to an existing user with the same verified email, or get a new user.
+async fn provision_user(pool: &sqlx::PgPool, claims: &IdTokenClaims) -> Result<User, AppError> {
+    let db_err = |e: sqlx::Error| {
+        tracing::error!("Failed to provision user: {:?}", e);
+        AppError::Internal("failed to provision user".into())
+    };
+
+    let mut tx = pool.begin().await.map_err(db_err)?;
+    let now = Utc::now();
+
+    let linked = sqlx::query_as!(
+        User,
+        r#"
+        SELECT u.id, u.email, u.display_name, u.totp_enabled_at IS NOT NULL AS "totp_enabled!",
+               u.created_at, u.updated_at
+        FROM user_identities i JOIN users u ON u.id = i.user_id
+        WHERE i.issuer = $1 AND i.subject = $2
+        "#,
+        claims.iss,
+        claims.sub
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    if let Some(user) = linked {
+        return Ok(user);
+    }
+
+    // Only trust the provider's email for linking when it says it verified it.
+    let email = claims
+        .email
+        .as_deref()
+        .filter(|_| claims.email_verified)
+        .map(str::to_lowercase);
+
+    let existing = match &email {
+        Some(email) => sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, email, display_name, totp_enabled_at IS NOT NULL AS "totp_enabled!", created_at, updated_at
+            FROM users WHERE email = $1
+            "#,
+            email
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_err)?,
+        None => None,
+    };
+
+    let user = match existing {
+        Some(user) => user,
+        None => create_user(&mut tx, email, claims.name.as_deref(), now).await?,
+    };
+
+    sqlx::query!(
+        "INSERT INTO user_identities (issuer, subject, user_id, created_at) VALUES ($1, $2, $3, $4)",
+        claims.iss,
+        claims.sub,
+        user.id,
+        now
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    Ok(user)
+}
+
+/// Creates a user along with a personal workspace they administer.
+async fn create_user(
+    conn: &mut sqlx::PgConnection,
+    email: Option<String>,
+    display_name: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<User, AppError> {
+    let db_err = |e: sqlx::Error| {
+        tracing::error!("Failed to create user: {:?}", e);
+        AppError::Internal("failed to provision user".into())
+    };
+
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        INSERT INTO users (id, email, display_name, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $4)
+        RETURNING id, email, display_name, totp_enabled_at IS NOT NULL AS "totp_enabled!",
+                  created_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        email,
+        display_name,
+        now
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    let workspace_id = Uuid::new_v4();
+    sqlx::query!(
+        "INSERT INTO workspaces (id, name, created_at) VALUES ($1, 'Personal', $2)",
+        workspace_id,
+        now
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    sqlx::query!(
+        "INSERT INTO workspace_members (workspace_id, user_id, role, created_at) VALUES ($1, $2, 'Admin', $3)",
+        workspace_id,
+        user.id,
+        now
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    Ok(user)
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/me",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The current user", body = User),
+        (status = 404, description = "The caller is not a user", body = ErrorResponse),
+    ),
+)]
+pub async fn me(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<User>, AppError> {
+    let user_id = ctx.user_id.ok_or(AppError::NotFound)?;
+
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        SELECT id, email, display_name, totp_enabled_at IS NOT NULL AS "totp_enabled!", created_at, updated_at
+        FROM users WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get user: {:?}", e);
+        AppError::Internal("failed to get user".into())
+    })?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(user))
+}
+
+/// Revokes the session token used for this request. Personal access tokens
+/// must be revoked through `DELETE /tokens/:id` instead.
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    responses(
+        (status = 204, description = "The session was revoked"),
+        (status = 404, description = "The caller is not using a session token", body = ErrorResponse),
+    ),
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    let token_id = ctx.token_id.ok_or(AppError::NotFound)?;
+
+    let result = sqlx::query!(
+        "UPDATE api_tokens SET revoked_at = $1 WHERE id = $2 AND kind = 'session'",
+        Utc::now(),
+        token_id
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to revoke session: {:?}", e);
+        AppError::Internal("failed to log out".into())
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
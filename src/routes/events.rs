@@ -0,0 +1,188 @@
+use std::{collections::VecDeque, convert::Infallible, time::Duration};
+
+use axum::{
+    Extension,
+    extract::{Query, State},
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::{Stream, stream};
+use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthContext,
+    error::AppError,
+    models::{event::TodoEvent, todo::TodoStatus},
+    services::events,
+    state::AppState,
+};
+
+/// Events read from the log per query.
+const BATCH_SIZE: i64 = 100;
+
+/// Streams also re-check the log this often, in case a notification was lost.
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StreamQuery {
+    pub project_id: Option<Uuid>,
+    /// Only events whose todo has this status after the change.
+    pub status: Option<TodoStatus>,
+    /// Alternative to the `Last-Event-ID` header, for clients that cannot
+    /// set headers on the first connection.
+    pub last_event_id: Option<i64>,
+}
+
+/// Position in the caller's event log, shared by the SSE stream and the
+/// GraphQL subscription.
+pub(crate) struct Cursor {
+    state: AppState,
+    ctx: AuthContext,
+    query: StreamQuery,
+    after: i64,
+    pending: VecDeque<TodoEvent>,
+    notifications: broadcast::Receiver<Uuid>,
+}
+
+/// Streams changes to the todos the caller can see as Server-Sent Events.
+/// Without a `Last-Event-ID` only changes made after connecting are sent.
+#[utoipa::path(
+    get,
+    path = "/todos/events",
+    tag = "todos",
+    params(
+        StreamQuery,
+        ("Last-Event-ID" = Option<i64>, Header, description = "Resume after this event"),
+    ),
+    responses(
+        (status = 200, description = "A `text/event-stream` of todo events", body = TodoEvent, content_type = "text/event-stream"),
+    ),
+)]
+pub async fn stream_todo_events(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(query): Query<StreamQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let last_event_id = match headers.get("last-event-id") {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .ok_or_else(|| AppError::invalid_field("Last-Event-ID", "must be an event ID"))?,
+        ),
+        None => query.last_event_id,
+    };
+
+    let cursor = Cursor::open(state, ctx, query, last_event_id).await?;
+    let stream = stream::unfold(cursor, |mut cursor| async move {
+        let event = cursor.next().await?;
+        let sse = Event::default()
+            .id(event.seq.to_string())
+            .event(event.kind.to_string())
+            .json_data(&event)
+            .ok()?;
+        Some((Ok(sse), cursor))
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+impl Cursor {
+    /// Starts after `last_event_id`, or at the end of the log.
+    pub(crate) async fn open(
+        state: AppState,
+        ctx: AuthContext,
+        query: StreamQuery,
+        last_event_id: Option<i64>,
+    ) -> Result<Self, AppError> {
+        // Subscribe before reading the log so nothing committed in between is missed.
+        let notifications = state.todo_events.subscribe();
+        let after = match last_event_id {
+            Some(id) => id,
+            None => events::latest_seq(&mut *state.tenant(&ctx).await?).await?,
+        };
+
+        Ok(Self {
+            state,
+            ctx,
+            query,
+            after,
+            pending: VecDeque::new(),
+            notifications,
+        })
+    }
+
+    /// Waits for the next matching event. `None` ends the stream, after which
+    /// the client reconnects with `Last-Event-ID`.
+    pub(crate) async fn next(&mut self) -> Option<TodoEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            match self.fetch().await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::error!("Failed to read event log: {:?}", e);
+                    return None;
+                }
+            }
+
+            self.wait().await?;
+        }
+    }
+
+    /// Loads the next batch of events after the cursor. Returns whether any
+    /// event was read, matching the filters or not.
+    async fn fetch(&mut self) -> Result<bool, AppError> {
+        let mut tx = self.state.tenant(&self.ctx).await?;
+        let (next, logged) =
+            events::read_after(&mut tx, self.ctx.user_id, self.after, BATCH_SIZE).await?;
+        if next == self.after {
+            return Ok(false);
+        }
+        self.after = next;
+
+        let status = self.query.status.as_ref().map(ToString::to_string);
+        self.pending.extend(
+            logged
+                .into_iter()
+                .filter(|e| {
+                    self.query.project_id.is_none() || e.project_id == self.query.project_id
+                })
+                .filter(|e| status.is_none() || status.as_ref() == Some(&e.status))
+                .map(|e| e.event),
+        );
+
+        Ok(true)
+    }
+
+    /// Sleeps until an event may have been committed in the caller's
+    /// workspace. Returns `None` if the server is shutting down.
+    async fn wait(&mut self) -> Option<()> {
+        let workspace_id = self.ctx.workspace_id;
+        let notified = async {
+            loop {
+                match self.notifications.recv().await {
+                    Ok(id) if id == workspace_id => return Some(()),
+                    Ok(_) => {}
+                    // Missed notifications just mean re-reading the log.
+                    Err(RecvError::Lagged(_)) => return Some(()),
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        };
+
+        match tokio::time::timeout(POLL_INTERVAL, notified).await {
+            Ok(result) => result,
+            Err(_) => Some(()),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::str::FromStr;
+
+use async_graphql::{
+    ErrorExtensions,
+    http::{ALL_WEBSOCKET_PROTOCOLS, GraphiQLSource, WebSocketProtocols, WsMessage},
+};
+use axum::{
+    Extension, Json,
+    extract::{
+        State,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+    },
+    http::{HeaderMap, header},
+    response::{Html, Response},
+};
+use futures::{SinkExt, StreamExt, future};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    auth::{self, AuthContext},
+    error::AppError,
+    graphql::{self, AppSchema},
+    models::token::Scope,
+    state::AppState,
+};
+
+/// `connection_init` payload for clients that cannot set headers on the
+/// WebSocket handshake.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConnectionParams {
+    token: String,
+    workspace_id: Option<Uuid>,
+}
+
+/// Runs a query or mutation for the authenticated caller. Errors are
+/// reported in the response body with status 200, as GraphQL clients expect.
+pub async fn execute(
+    State(state): State<AppState>,
+    Extension(schema): Extension<AppSchema>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(mut request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    request.data = graphql::request_data(&state, ctx);
+    Json(schema.execute(request).await)
+}
+
+/// GraphiQL IDE; the token is entered under "Headers".
+pub async fn playground() -> Html<String> {
+    Html(
+        GraphiQLSource::build()
+            .endpoint("/v1/graphql")
+            .subscription_endpoint("/v1/graphql/ws")
+            .finish(),
+    )
+}
+
+/// Upgrades to a `graphql-transport-ws` (or legacy `graphql-ws`) connection
+/// for subscriptions. Clients authenticate with `Authorization` or with
+/// `{"token", "workspaceId"}` in their `connection_init` payload.
+pub async fn subscribe(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Extension(schema): Extension<AppSchema>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let protocol = headers
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            v.split(',')
+                .find_map(|p| WebSocketProtocols::from_str(p.trim()).ok())
+        })
+        .ok_or_else(|| {
+            AppError::invalid_field(
+                "Sec-WebSocket-Protocol",
+                "must be graphql-transport-ws or graphql-ws",
+            )
+        })?;
+
+    let ctx = match auth::bearer_token(&headers) {
+        Some(token) => {
+            let workspace_id = auth::requested_workspace(&headers)?;
+            let ctx = auth::authenticate_token(&state, token, workspace_id).await?;
+            if !ctx.has_scope(Scope::TodosRead) {
+                return Err(AppError::InsufficientScope(Scope::TodosRead));
+            }
+            Some(ctx)
+        }
+        None => None,
+    };
+
+    Ok(ws
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| serve(socket, schema, state, ctx, protocol)))
+}
+
+async fn serve(
+    socket: WebSocket,
+    schema: AppSchema,
+    state: AppState,
+    ctx: Option<AuthContext>,
+    protocol: WebSocketProtocols,
+) {
+    let (mut sink, stream) = socket.split();
+    let input = stream
+        .take_while(|message| future::ready(message.is_ok()))
+        .filter_map(|message| {
+            future::ready(match message {
+                Ok(message @ (Message::Text(_) | Message::Binary(_))) => Some(message.into_data()),
+                _ => None,
+            })
+        });
+
+    let mut output = async_graphql::http::WebSocket::new(schema, input, protocol)
+        .on_connection_init(move |payload| async move {
+            let ctx = match ctx {
+                Some(ctx) => ctx,
+                None => connection_init_auth(&state, payload).await?,
+            };
+            Ok(graphql::request_data(&state, ctx))
+        });
+
+    while let Some(message) = output.next().await {
+        let message = match message {
+            WsMessage::Text(text) => Message::Text(text),
+            WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame {
+                code,
+                reason: reason.into(),
+            })),
+        };
+        if sink.send(message).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn connection_init_auth(
+    state: &AppState,
+    payload: serde_json::Value,
+) -> async_graphql::Result<AuthContext> {
+    let params: ConnectionParams =
+        serde_json::from_value(payload).map_err(|_| AppError::Unauthorized.extend())?;
+    let ctx = auth::authenticate_token(state, &params.token, params.workspace_id)
+        .await
+        .map_err(|e| e.extend())?;
+    if !ctx.has_scope(Scope::TodosRead) {
+        return Err(AppError::InsufficientScope(Scope::TodosRead).extend());
+    }
+    Ok(ctx)
+}
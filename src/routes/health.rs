@@ -0,0 +1,285 @@
+//! Probes for load balancers and orchestrators, and a status report for
+//! operators. Mounted at the root rather than under `/v1`, and exempt from
+//! authentication and rate limiting. The status report calls Gemini and the
+//! SSO provider, so it is cached rather than made anew for every caller.
+//! Failures are logged, and reported only as `unavailable`: error messages
+//! can name internal hosts.
+
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{Json, extract::State, http::StatusCode};
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::{commands::migrate, state::AppState};
+
+/// How long a single check may take before its dependency counts as down.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a status report is served to later callers before the checks
+/// are made again.
+const STATUS_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckState {
+    Up,
+    Down,
+    Disabled,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Check {
+    pub state: CheckState,
+    /// Only reported by checks that make a round trip.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Check {
+    fn up(detail: impl Into<String>) -> Self {
+        Self {
+            state: CheckState::Up,
+            latency_ms: None,
+            detail: Some(detail.into()),
+        }
+    }
+
+    fn down(detail: impl Into<String>) -> Self {
+        Self {
+            state: CheckState::Down,
+            latency_ms: None,
+            detail: Some(detail.into()),
+        }
+    }
+
+    fn disabled() -> Self {
+        Self {
+            state: CheckState::Disabled,
+            latency_ms: None,
+            detail: None,
+        }
+    }
+
+    fn is_down(&self) -> bool {
+        matches!(self.state, CheckState::Down)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Health {
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<&'static str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub checks: BTreeMap<&'static str, Check>,
+}
+
+/// Liveness: the process is up and serving requests. Touches no
+/// dependencies, so a database outage does not get the server restarted.
+pub async fn healthz() -> Json<Health> {
+    Json(Health {
+        status: "ok",
+        version: None,
+        checks: BTreeMap::new(),
+    })
+}
+
+/// Readiness: the database answers and its schema matches this build.
+/// `503` until both hold.
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<Health>) {
+    let (database, migrations) = tokio::join!(check_database(&state), check_migrations(&state));
+
+    let checks = BTreeMap::from([("database", database), ("migrations", migrations)]);
+    if checks.values().any(Check::is_down) {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(Health {
+                status: "unavailable",
+                version: None,
+                checks,
+            }),
+        )
+    } else {
+        (
+            StatusCode::OK,
+            Json(Health {
+                status: "ready",
+                version: None,
+                checks,
+            }),
+        )
+    }
+}
+
+/// The last status report and when it was made. Its lock is held while the
+/// checks run, so concurrent callers wait for one report instead of each
+/// making their own.
+#[derive(Clone, Default)]
+pub struct StatusCache(Arc<Mutex<Option<(Instant, StatusCode, Health)>>>);
+
+/// Every dependency's state and latency, including the external services
+/// that readiness does not call. `degraded` when only an external service is
+/// down; `503` with `down` when the database or its schema is. Up to
+/// [`STATUS_TTL`] old.
+pub async fn status(State(state): State<AppState>) -> (StatusCode, Json<Health>) {
+    let mut cached = state.status_cache.0.lock().await;
+    if let Some((made_at, code, health)) = &*cached
+        && made_at.elapsed() < STATUS_TTL
+    {
+        return (*code, Json(health.clone()));
+    }
+
+    let (code, health) = check_all(&state).await;
+    *cached = Some((Instant::now(), code, health.clone()));
+    (code, Json(health))
+}
+
+async fn check_all(state: &AppState) -> (StatusCode, Health) {
+    let (database, migrations, gemini, oidc) = tokio::join!(
+        check_database(state),
+        check_migrations(state),
+        timed(async {
+            state
+                .gemini
+                .check()
+                .await
+                .map(|()| format!("model {}", state.config.gemini.model))
+                .map_err(|e| unavailable("Gemini", &e))
+        }),
+        async {
+            match &state.oidc {
+                Some(oidc) => {
+                    timed(async {
+                        oidc.check()
+                            .await
+                            .map(|()| "reachable".to_string())
+                            .map_err(|e| unavailable("SSO provider", &e))
+                    })
+                    .await
+                }
+                None => Check::disabled(),
+            }
+        },
+    );
+
+    let (code, status) = if database.is_down() || migrations.is_down() {
+        (StatusCode::SERVICE_UNAVAILABLE, "down")
+    } else if gemini.is_down() || oidc.is_down() {
+        (StatusCode::OK, "degraded")
+    } else {
+        (StatusCode::OK, "ok")
+    };
+    (
+        code,
+        Health {
+            status,
+            version: Some(env!("CARGO_PKG_VERSION")),
+            checks: BTreeMap::from([
+                ("database", database),
+                ("migrations", migrations),
+                ("gemini", gemini),
+                ("oidc", oidc),
+            ]),
+        },
+    )
+}
+
+async fn check_database(state: &AppState) -> Check {
+    timed(async {
+        let result = match &state.sqlite {
+            Some(pool) => sqlx::query("SELECT 1").execute(pool).await.map(|_| ()),
+            None => sqlx::query_scalar!(r#"SELECT 1 AS "one!""#)
+                .fetch_one(&state.pool)
+                .await
+                .map(|_| ()),
+        };
+        result
+            .map(|()| "connected".to_string())
+            .map_err(|e| unavailable("Database", &e))
+    })
+    .await
+}
+
+/// Compares the newest applied migration with the newest one in this build.
+/// A database ahead of the build, as during a rolling deploy, still counts
+/// as up.
+async fn check_migrations(state: &AppState) -> Check {
+    let migrator = match state.sqlite {
+        Some(_) => &migrate::SQLITE_MIGRATOR,
+        None => &migrate::MIGRATOR,
+    };
+    let expected = migrate::up_migrations(migrator)
+        .map(|m| m.version)
+        .max()
+        .unwrap_or(0);
+    let applied = tokio::time::timeout(CHECK_TIMEOUT, latest_migration(state)).await;
+
+    match applied {
+        Err(_) => Check::down("timed out"),
+        // sqlx creates the table with the first migration.
+        Ok(Err(sqlx::Error::Database(e))) if e.code().as_deref() == Some("42P01") => {
+            Check::down("no migrations applied; run `ai-todo migrate up`")
+        }
+        Ok(Err(e)) => Check::down(unavailable("Migrations", &e)),
+        Ok(Ok((_, false))) => Check::down("a migration failed partway"),
+        Ok(Ok((latest, _))) if latest < expected => Check::down(format!(
+            "database at {latest}, this build needs {expected}; run `ai-todo migrate up`"
+        )),
+        Ok(Ok((latest, _))) => Check::up(format!("at {latest}")),
+    }
+}
+
+/// The newest applied migration, and whether every applied one succeeded.
+async fn latest_migration(state: &AppState) -> Result<(i64, bool), sqlx::Error> {
+    match &state.sqlite {
+        // Migrated at startup, so the table always exists.
+        Some(pool) => {
+            sqlx::query_as(
+                "SELECT COALESCE(MAX(version), 0), COALESCE(MIN(success), 1) FROM _sqlx_migrations",
+            )
+            .fetch_one(pool)
+            .await
+        }
+        None => {
+            let row = sqlx::query!(
+                r#"
+                SELECT COALESCE(MAX(version), 0) AS "latest!", COALESCE(bool_and(success), true) AS "clean!"
+                FROM _sqlx_migrations
+                "#
+            )
+            .fetch_one(&state.pool)
+            .await?;
+            Ok((row.latest, row.clean))
+        }
+    }
+}
+
+/// Logs why `dependency` failed its check and returns the detail to report
+/// instead.
+fn unavailable(dependency: &str, error: &dyn std::fmt::Debug) -> String {
+    tracing::error!("{} health check failed: {:?}", dependency, error);
+    "unavailable".to_string()
+}
+
+/// Runs a check with [`CHECK_TIMEOUT`], recording how long it took.
+async fn timed(check: impl Future<Output = Result<String, String>>) -> Check {
+    let started = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, check).await;
+    // Rounded to tenths of a millisecond.
+    let latency_ms = (started.elapsed().as_secs_f64() * 10_000.0).round() / 10.0;
+
+    let mut check = match result {
+        Ok(Ok(detail)) => Check::up(detail),
+        Ok(Err(error)) => Check::down(error),
+        Err(_) => Check::down("timed out"),
+    };
+    check.latency_ms = Some(latency_ms);
+    check
+}
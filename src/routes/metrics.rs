@@ -0,0 +1,81 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::header, response::IntoResponse};
+use tokio::sync::Mutex;
+
+use crate::{error::AppError, state::AppState};
+
+/// Database role that may read the status of every todo, whatever its
+/// workspace.
+const METRICS_ROLE: &str = "ai_todo_metrics";
+
+/// How long todo counts are reported before the todos are counted again.
+const COUNTS_TTL: Duration = Duration::from_secs(15);
+
+/// Todos per status.
+type TodoCounts = Vec<(String, i64)>;
+
+/// The last todo counts and when they were made. As for the status report,
+/// the lock is held while counting, so concurrent scrapes share one count.
+#[derive(Clone, Default)]
+pub struct TodoCountCache(Arc<Mutex<Option<(Instant, TodoCounts)>>>);
+
+/// Every metric in the Prometheus text format. Pool usage is read at scrape
+/// time and todo counts at most [`COUNTS_TTL`] before; the rest accumulate as
+/// requests are handled.
+pub async fn export(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
+    match &state.sqlite {
+        Some(pool) => state.metrics.set_pool(pool),
+        None => state.metrics.set_pool(&state.pool),
+    }
+    let counts = cached_todo_counts(&state).await.map_err(|e| {
+        tracing::error!("Failed to count todos for metrics: {:?}", e);
+        AppError::Internal("database unavailable".into())
+    })?;
+    state.metrics.set_todos(&counts);
+
+    Ok((
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        state.metrics.encode(),
+    ))
+}
+
+async fn cached_todo_counts(state: &AppState) -> Result<TodoCounts, sqlx::Error> {
+    let mut cached = state.todo_counts.0.lock().await;
+    if let Some((counted_at, counts)) = &*cached
+        && counted_at.elapsed() < COUNTS_TTL
+    {
+        return Ok(counts.clone());
+    }
+
+    let counts = match &state.sqlite {
+        Some(pool) => {
+            sqlx::query_as("SELECT status, COUNT(*) FROM todos GROUP BY status")
+                .fetch_all(pool)
+                .await?
+        }
+        None => todo_counts(state).await?,
+    };
+    *cached = Some((Instant::now(), counts.clone()));
+    Ok(counts)
+}
+
+/// Todos by status across every workspace, counted in one query as
+/// [`METRICS_ROLE`], which row-level security lets see every row.
+async fn todo_counts(state: &AppState) -> Result<TodoCounts, sqlx::Error> {
+    let mut tx = state.pool.begin().await?;
+    sqlx::query!("SELECT set_config('role', $1, true) AS role", METRICS_ROLE)
+        .fetch_one(&mut *tx)
+        .await?;
+    let rows = sqlx::query!(r#"SELECT status, COUNT(*) AS "count!" FROM todos GROUP BY status"#)
+        .fetch_all(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.status, row.count))
+        .collect())
+}
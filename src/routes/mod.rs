@@ -1,2 +1,15 @@
 pub mod audio;
+pub mod auth;
+pub mod events;
+pub mod graphql;
+pub mod health;
+pub mod metrics;
+pub mod projects;
+pub mod realtime;
+pub mod shares;
+pub mod sync;
 pub mod todos;
+pub mod tokens;
+pub mod totp;
+pub mod webhooks;
+pub mod workspaces;
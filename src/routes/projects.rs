@@ -0,0 +1,246 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    auth::AuthContext,
+    error::{AppError, ErrorResponse},
+    models::{project::Project, share::Role},
+    permissions,
+    state::AppState,
+    validator::ValidatedJson,
+};
+
+#[derive(serde::Deserialize, Validate, utoipa::ToSchema)]
+pub struct CreateProject {
+    #[validate(length(min = 1, max = 200, message = "name must be 1-200 characters"))]
+    pub name: String,
+}
+
+#[derive(serde::Deserialize, Validate, utoipa::ToSchema)]
+pub struct UpdateProject {
+    #[validate(length(min = 1, max = 200, message = "name must be 1-200 characters"))]
+    pub name: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects",
+    tag = "projects",
+    request_body = CreateProject,
+    responses(
+        (status = 201, description = "The created project", body = Project),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    ),
+)]
+pub async fn create_project(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<CreateProject>,
+) -> Result<(StatusCode, Json<Project>), AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+
+    let now = Utc::now();
+
+    let project = sqlx::query_as!(
+        Project,
+        r#"
+        INSERT INTO projects (id, name, owner_id, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $4)
+        RETURNING id, name, owner_id, 'Owner' AS "role!", created_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        payload.name,
+        ctx.user_id,
+        now
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create project: {:?}", e);
+        AppError::Internal("failed to create project".into())
+    })?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit create project: {:?}", e);
+        AppError::Internal("failed to create project".into())
+    })?;
+
+    Ok((StatusCode::CREATED, Json(project)))
+}
+
+/// Lists the projects the caller owns or that have been shared with them.
+#[utoipa::path(
+    get,
+    path = "/projects",
+    tag = "projects",
+    responses(
+        (status = 200, description = "Projects", body = Vec<Project>),
+    ),
+)]
+pub async fn list_projects(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<Project>>, AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+
+    let projects = sqlx::query_as!(
+        Project,
+        r#"
+        SELECT id, name, owner_id, COALESCE(project_role(id, $1), 'Owner') AS "role!", created_at, updated_at
+        FROM projects
+        WHERE $1::uuid IS NULL OR project_role(id, $1) IS NOT NULL
+        ORDER BY created_at DESC
+        "#,
+        ctx.user_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list projects: {:?}", e);
+        AppError::Internal("failed to list projects".into())
+    })?;
+
+    Ok(Json(projects))
+}
+
+#[utoipa::path(
+    get,
+    path = "/projects/{id}",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+    ),
+    responses(
+        (status = 200, description = "The project", body = Project),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn get_project(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Project>, AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+
+    let role = permissions::authorize_project(&mut *tx, &ctx, id, Role::Viewer).await?;
+
+    let project = sqlx::query_as!(
+        Project,
+        r#"
+        SELECT id, name, owner_id, $2 AS "role!", created_at, updated_at
+        FROM projects WHERE id = $1
+        "#,
+        id,
+        role.to_string()
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to get project: {:?}", e);
+        AppError::Internal("failed to get project".into())
+    })?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(project))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/projects/{id}",
+    tag = "projects",
+    request_body = UpdateProject,
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+    ),
+    responses(
+        (status = 200, description = "The updated project", body = Project),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn update_project(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<UpdateProject>,
+) -> Result<Json<Project>, AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+
+    let role = permissions::authorize_project(&mut *tx, &ctx, id, Role::Owner).await?;
+
+    let project = sqlx::query_as!(
+        Project,
+        r#"
+        UPDATE projects SET name = $1, updated_at = $2
+        WHERE id = $3
+        RETURNING id, name, owner_id, $4 AS "role!", created_at, updated_at
+        "#,
+        payload.name,
+        Utc::now(),
+        id,
+        role.to_string()
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to update project: {:?}", e);
+        AppError::Internal("failed to update project".into())
+    })?
+    .ok_or(AppError::NotFound)?;
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit update project: {:?}", e);
+        AppError::Internal("failed to update project".into())
+    })?;
+
+    Ok(Json(project))
+}
+
+/// Deletes a project. Its todos are kept and become unassigned.
+#[utoipa::path(
+    delete,
+    path = "/projects/{id}",
+    tag = "projects",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+    ),
+    responses(
+        (status = 204, description = "The project was deleted"),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn delete_project(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+    permissions::authorize_project(&mut *tx, &ctx, id, Role::Owner).await?;
+
+    let result = sqlx::query!("DELETE FROM projects WHERE id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete project: {:?}", e);
+            AppError::Internal("failed to delete project".into())
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tx.commit().await.map_err(|e| {
+        tracing::error!("Failed to commit delete project: {:?}", e);
+        AppError::Internal("failed to delete project".into())
+    })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
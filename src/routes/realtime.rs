@@ -0,0 +1,523 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{
+        State,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+    },
+    http::HeaderMap,
+    response::Response,
+};
+use chrono::Utc;
+use futures::{SinkExt, StreamExt, stream::SplitSink};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::{
+    auth::{self, AuthContext},
+    error::AppError,
+    models::{event::TodoEvent, share::Role, token::Scope},
+    permissions,
+    services::{
+        events,
+        realtime::{Presence, PresenceState, Signal, Typing},
+    },
+    state::AppState,
+};
+
+/// Time a client has to send its `auth` message.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// Connections that have sent nothing, not even a pong, for this long are closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(75);
+/// How long a connection trusts its answer to "can I see this todo?".
+const VISIBILITY_TTL: Duration = Duration::from_secs(60);
+const EVENT_BATCH_SIZE: i64 = 100;
+
+const CLOSE_UNAUTHORIZED: u16 = 4401;
+const CLOSE_FORBIDDEN: u16 = 4403;
+const CLOSE_TIMEOUT: u16 = 4408;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Auth {
+        token: String,
+        workspace_id: Option<Uuid>,
+    },
+    /// Omitting `project_id` subscribes to every todo the caller can see.
+    Subscribe {
+        project_id: Option<Uuid>,
+    },
+    Unsubscribe {
+        project_id: Option<Uuid>,
+    },
+    Presence {
+        todo_id: Uuid,
+        state: PresenceState,
+    },
+    Typing {
+        todo_id: Uuid,
+        field: Option<String>,
+    },
+    Ping,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    Ready {
+        connection_id: Uuid,
+        user_id: Option<Uuid>,
+        workspace_id: Uuid,
+    },
+    Subscribed {
+        project_id: Option<Uuid>,
+        /// Who is currently viewing or editing todos in the subscription.
+        presence: Vec<Presence>,
+    },
+    Unsubscribed {
+        project_id: Option<Uuid>,
+    },
+    Todo {
+        event: &'a TodoEvent,
+    },
+    Presence(&'a Presence),
+    Typing(&'a Typing),
+    Pong,
+    Error {
+        message: String,
+    },
+}
+
+/// Upgrades to a WebSocket. Clients that can set headers authenticate with
+/// `Authorization` (and `X-Workspace-Id`); browsers send an `auth` message
+/// first instead.
+pub async fn connect(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let ctx = match auth::bearer_token(&headers) {
+        Some(token) => {
+            let workspace_id = auth::requested_workspace(&headers)?;
+            let ctx = auth::authenticate_token(&state, token, workspace_id).await?;
+            if !ctx.has_scope(Scope::TodosRead) {
+                return Err(AppError::InsufficientScope(Scope::TodosRead));
+            }
+            Some(ctx)
+        }
+        None => None,
+    };
+
+    Ok(ws.on_upgrade(move |socket| run(socket, state, ctx)))
+}
+
+type Sink = SplitSink<WebSocket, Message>;
+
+async fn send(sink: &mut Sink, message: &ServerMessage<'_>) -> bool {
+    match serde_json::to_string(message) {
+        Ok(text) => sink.send(Message::Text(text)).await.is_ok(),
+        Err(e) => {
+            tracing::error!("Failed to serialize realtime message: {:?}", e);
+            true
+        }
+    }
+}
+
+async fn close(sink: &mut Sink, code: u16, reason: &'static str) {
+    let _ = sink
+        .send(Message::Close(Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        })))
+        .await;
+}
+
+async fn run(socket: WebSocket, state: AppState, ctx: Option<AuthContext>) {
+    let (mut sink, mut stream) = socket.split();
+
+    let ctx = match ctx {
+        Some(ctx) => ctx,
+        None => match first_message_auth(&state, &mut stream).await {
+            Ok(ctx) => ctx,
+            Err((code, reason)) => {
+                close(&mut sink, code, reason).await;
+                return;
+            }
+        },
+    };
+
+    let mut connection = match Connection::open(state, ctx).await {
+        Ok(connection) => connection,
+        Err(e) => {
+            tracing::error!("Failed to open realtime connection: {:?}", e);
+            close(&mut sink, 1011, "internal error").await;
+            return;
+        }
+    };
+
+    let ready = ServerMessage::Ready {
+        connection_id: connection.id,
+        user_id: connection.ctx.user_id,
+        workspace_id: connection.ctx.workspace_id,
+    };
+    if send(&mut sink, &ready).await {
+        connection.serve(&mut sink, &mut stream).await;
+    }
+
+    connection
+        .state
+        .realtime
+        .disconnect(connection.ctx.workspace_id, connection.id);
+}
+
+async fn first_message_auth(
+    state: &AppState,
+    stream: &mut futures::stream::SplitStream<WebSocket>,
+) -> Result<AuthContext, (u16, &'static str)> {
+    let message = tokio::time::timeout(AUTH_TIMEOUT, stream.next())
+        .await
+        .map_err(|_| (CLOSE_TIMEOUT, "authentication timed out"))?;
+
+    let Some(Ok(Message::Text(text))) = message else {
+        return Err((CLOSE_UNAUTHORIZED, "expected an auth message"));
+    };
+    let Ok(ClientMessage::Auth {
+        token,
+        workspace_id,
+    }) = serde_json::from_str(&text)
+    else {
+        return Err((CLOSE_UNAUTHORIZED, "expected an auth message"));
+    };
+
+    let ctx = match auth::authenticate_token(state, &token, workspace_id).await {
+        Ok(ctx) => ctx,
+        Err(AppError::Forbidden) => return Err((CLOSE_FORBIDDEN, "workspace not allowed")),
+        Err(_) => return Err((CLOSE_UNAUTHORIZED, "invalid token")),
+    };
+    if !ctx.has_scope(Scope::TodosRead) {
+        return Err((CLOSE_FORBIDDEN, "missing required scope: todos:read"));
+    }
+
+    Ok(ctx)
+}
+
+struct Connection {
+    id: Uuid,
+    state: AppState,
+    ctx: AuthContext,
+    display_name: Option<String>,
+    /// Subscribed projects; `None` stands for every visible todo.
+    subscriptions: HashSet<Option<Uuid>>,
+    /// Position in the workspace's event log.
+    after: i64,
+    visibility: HashMap<Uuid, (bool, Instant)>,
+    last_seen: Instant,
+}
+
+impl Connection {
+    async fn open(state: AppState, ctx: AuthContext) -> Result<Self, AppError> {
+        let display_name = match ctx.user_id {
+            Some(user_id) => sqlx::query_scalar!(
+                "SELECT COALESCE(display_name, email) FROM users WHERE id = $1",
+                user_id
+            )
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load user: {:?}", e);
+                AppError::Internal("failed to load user".into())
+            })?
+            .flatten(),
+            None => None,
+        };
+        let after = events::latest_seq(&mut *state.tenant(&ctx).await?).await?;
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            state,
+            ctx,
+            display_name,
+            subscriptions: HashSet::new(),
+            after,
+            visibility: HashMap::new(),
+            last_seen: Instant::now(),
+        })
+    }
+
+    async fn serve(
+        &mut self,
+        sink: &mut Sink,
+        stream: &mut futures::stream::SplitStream<WebSocket>,
+    ) {
+        let mut todo_events = self.state.todo_events.subscribe();
+        let mut signals = self.state.realtime.subscribe();
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        heartbeat.reset();
+
+        loop {
+            tokio::select! {
+                message = stream.next() => {
+                    let Some(Ok(message)) = message else { return };
+                    self.last_seen = Instant::now();
+                    let text = match message {
+                        Message::Text(text) => text,
+                        Message::Close(_) => return,
+                        _ => continue,
+                    };
+                    if !self.handle(sink, &text).await {
+                        return;
+                    }
+                }
+                workspace_id = todo_events.recv() => {
+                    match workspace_id {
+                        Ok(id) if id != self.ctx.workspace_id => continue,
+                        Err(RecvError::Closed) => return,
+                        _ => {}
+                    }
+                    if !self.forward_events(sink).await {
+                        return;
+                    }
+                }
+                signal = signals.recv() => {
+                    let (workspace_id, signal) = match signal {
+                        Ok(signal) => signal,
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return,
+                    };
+                    if workspace_id != self.ctx.workspace_id
+                        || signal.connection_id() == self.id
+                        || !self.is_subscribed(signal.project_id())
+                        || !self.can_view(signal.todo_id()).await
+                    {
+                        continue;
+                    }
+                    let message = match &signal {
+                        Signal::Presence(p) => ServerMessage::Presence(p),
+                        Signal::Typing(t) => ServerMessage::Typing(t),
+                    };
+                    if !send(sink, &message).await {
+                        return;
+                    }
+                }
+                _ = heartbeat.tick() => {
+                    if self.last_seen.elapsed() > IDLE_TIMEOUT {
+                        close(sink, CLOSE_TIMEOUT, "heartbeat timed out").await;
+                        return;
+                    }
+                    if sink.send(Message::Ping(Vec::new())).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handles one client message. Returns `false` once the socket is gone.
+    async fn handle(&mut self, sink: &mut Sink, text: &str) -> bool {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => {
+                let message = format!("invalid message: {e}");
+                return send(sink, &ServerMessage::Error { message }).await;
+            }
+        };
+
+        let result = match message {
+            ClientMessage::Auth { .. } => Err(AppError::invalid_field(
+                "type",
+                "connection is already authenticated",
+            )),
+            ClientMessage::Subscribe { project_id } => {
+                return self.subscribe(sink, project_id).await;
+            }
+            ClientMessage::Unsubscribe { project_id } => {
+                self.subscriptions.remove(&project_id);
+                return send(sink, &ServerMessage::Unsubscribed { project_id }).await;
+            }
+            ClientMessage::Presence { todo_id, state } => {
+                self.publish_presence(todo_id, state).await
+            }
+            ClientMessage::Typing { todo_id, field } => self.publish_typing(todo_id, field).await,
+            ClientMessage::Ping => return send(sink, &ServerMessage::Pong).await,
+        };
+
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                let message = error_message(e);
+                send(sink, &ServerMessage::Error { message }).await
+            }
+        }
+    }
+
+    async fn subscribe(&mut self, sink: &mut Sink, project_id: Option<Uuid>) -> bool {
+        if let Some(project_id) = project_id {
+            let authorized = match self.state.tenant(&self.ctx).await {
+                Ok(mut tx) => {
+                    permissions::authorize_project(&mut *tx, &self.ctx, project_id, Role::Viewer)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = authorized {
+                let message = error_message(e);
+                return send(sink, &ServerMessage::Error { message }).await;
+            }
+        }
+        self.subscriptions.insert(project_id);
+
+        let mut presence = Vec::new();
+        for p in self.state.realtime.presences(self.ctx.workspace_id) {
+            if (project_id.is_none() || p.project_id == project_id)
+                && p.connection_id != self.id
+                && self.can_view(p.todo_id).await
+            {
+                presence.push(p);
+            }
+        }
+
+        send(
+            sink,
+            &ServerMessage::Subscribed {
+                project_id,
+                presence,
+            },
+        )
+        .await
+    }
+
+    fn is_subscribed(&self, project_id: Option<Uuid>) -> bool {
+        self.subscriptions.contains(&None)
+            || (project_id.is_some() && self.subscriptions.contains(&project_id))
+    }
+
+    /// Whether the caller may see the todo. Cached briefly, since presence
+    /// and typing signals arrive far more often than sharing changes.
+    async fn can_view(&mut self, todo_id: Uuid) -> bool {
+        if let Some((visible, checked_at)) = self.visibility.get(&todo_id)
+            && checked_at.elapsed() < VISIBILITY_TTL
+        {
+            return *visible;
+        }
+
+        let visible = match self.state.tenant(&self.ctx).await {
+            Ok(mut tx) => permissions::authorize_todo(&mut *tx, &self.ctx, todo_id, Role::Viewer)
+                .await
+                .is_ok(),
+            Err(_) => false,
+        };
+        self.visibility.insert(todo_id, (visible, Instant::now()));
+        visible
+    }
+
+    /// Looks up the todo's project after checking the caller holds `min`.
+    async fn todo_project(&self, todo_id: Uuid, min: Role) -> Result<Option<Uuid>, AppError> {
+        let mut tx = self.state.tenant(&self.ctx).await?;
+        permissions::authorize_todo(&mut *tx, &self.ctx, todo_id, min).await?;
+
+        sqlx::query_scalar!("SELECT project_id FROM todos WHERE id = $1", todo_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to load todo: {:?}", e);
+                AppError::Internal("failed to load todo".into())
+            })
+    }
+
+    async fn publish_presence(&self, todo_id: Uuid, state: PresenceState) -> Result<(), AppError> {
+        // Announcing an edit requires being able to edit.
+        let min = match state {
+            PresenceState::Editing => Role::Editor,
+            PresenceState::Viewing | PresenceState::Idle => Role::Viewer,
+        };
+        let project_id = self.todo_project(todo_id, min).await?;
+
+        self.state.realtime.set_presence(
+            self.ctx.workspace_id,
+            Presence {
+                connection_id: self.id,
+                user_id: self.ctx.user_id,
+                display_name: self.display_name.clone(),
+                todo_id,
+                project_id,
+                state,
+                since: Utc::now(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn publish_typing(&self, todo_id: Uuid, field: Option<String>) -> Result<(), AppError> {
+        let project_id = self.todo_project(todo_id, Role::Editor).await?;
+
+        self.state.realtime.typing(
+            self.ctx.workspace_id,
+            Typing {
+                connection_id: self.id,
+                user_id: self.ctx.user_id,
+                display_name: self.display_name.clone(),
+                todo_id,
+                project_id,
+                field,
+            },
+        );
+        Ok(())
+    }
+
+    /// Sends subscribed events committed since the last call.
+    async fn forward_events(&mut self, sink: &mut Sink) -> bool {
+        loop {
+            let read = match self.state.tenant(&self.ctx).await {
+                Ok(mut tx) => {
+                    events::read_after(&mut tx, self.ctx.user_id, self.after, EVENT_BATCH_SIZE)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            let (next, logged) = match read {
+                Ok(read) => read,
+                Err(e) => {
+                    tracing::error!("Failed to read event log: {:?}", e);
+                    return true;
+                }
+            };
+            if next == self.after {
+                return true;
+            }
+            self.after = next;
+
+            for logged in logged {
+                if self.is_subscribed(logged.project_id)
+                    && !send(
+                        sink,
+                        &ServerMessage::Todo {
+                            event: &logged.event,
+                        },
+                    )
+                    .await
+                {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+fn error_message(e: AppError) -> String {
+    match e {
+        AppError::InvalidInput(errors) => errors
+            .into_values()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("; "),
+        AppError::NotFound => "Resource not found".to_string(),
+        AppError::Forbidden => "You do not have permission to perform this action".to_string(),
+        e => e.to_string(),
+    }
+}
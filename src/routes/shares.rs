@@ -0,0 +1,741 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    auth::AuthContext,
+    error::{AppError, ErrorResponse},
+    models::share::{Invitation, Member, Role},
+    permissions,
+    state::AppState,
+    validator::ValidatedJson,
+};
+
+const INVITATION_TTL: Duration = Duration::days(7);
+
+/// What is being shared. Projects and individual todos share the same
+/// membership and invitation handling.
+#[derive(Debug, Clone, Copy)]
+enum ShareTarget {
+    Project(Uuid),
+    Todo(Uuid),
+}
+
+impl ShareTarget {
+    /// `(project_id, todo_id)` as stored in `shares` and `invitations`.
+    fn columns(self) -> (Option<Uuid>, Option<Uuid>) {
+        match self {
+            ShareTarget::Project(id) => (Some(id), None),
+            ShareTarget::Todo(id) => (None, Some(id)),
+        }
+    }
+
+    async fn authorize(
+        self,
+        conn: &mut sqlx::PgConnection,
+        ctx: &AuthContext,
+        min: Role,
+    ) -> Result<Role, AppError> {
+        match self {
+            ShareTarget::Project(id) => permissions::authorize_project(conn, ctx, id, min).await,
+            ShareTarget::Todo(id) => permissions::authorize_todo(conn, ctx, id, min).await,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Validate, utoipa::ToSchema)]
+pub struct CreateInvitation {
+    #[validate(email(message = "email must be a valid email address"))]
+    pub email: String,
+    pub role: Role,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct UpdateMember {
+    pub role: Role,
+}
+
+fn db_err(context: &'static str) -> impl Fn(sqlx::Error) -> AppError {
+    move |e| {
+        tracing::error!("Failed to {}: {:?}", context, e);
+        AppError::Internal(format!("failed to {context}"))
+    }
+}
+
+async fn list_members(
+    state: &AppState,
+    ctx: &AuthContext,
+    target: ShareTarget,
+) -> Result<Json<Vec<Member>>, AppError> {
+    let mut tx = state.tenant(ctx).await?;
+    target.authorize(&mut tx, ctx, Role::Viewer).await?;
+    let (project_id, todo_id) = target.columns();
+
+    let members = sqlx::query_as!(
+        Member,
+        r#"
+        SELECT user_id AS "user_id!", email AS "email?", display_name AS "display_name?", role AS "role!"
+        FROM (
+            SELECT true AS is_owner, u.id AS user_id, u.email, u.display_name, 'Owner' AS role
+            FROM users u
+            WHERE u.id = COALESCE(
+                (SELECT owner_id FROM projects WHERE id = $1),
+                (SELECT owner_id FROM todos WHERE id = $2)
+            )
+            UNION ALL
+            SELECT false, u.id, u.email, u.display_name, s.role
+            FROM shares s JOIN users u ON u.id = s.user_id
+            WHERE s.project_id = $1 OR s.todo_id = $2
+        ) members
+        ORDER BY is_owner DESC, role_rank(role) DESC, email
+        "#,
+        project_id,
+        todo_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err("list members"))?;
+
+    Ok(Json(members))
+}
+
+async fn invite(
+    state: &AppState,
+    ctx: &AuthContext,
+    target: ShareTarget,
+    payload: CreateInvitation,
+) -> Result<(StatusCode, Json<Invitation>), AppError> {
+    let mut tx = state.tenant(ctx).await?;
+    target.authorize(&mut tx, ctx, Role::Owner).await?;
+    let (project_id, todo_id) = target.columns();
+    let email = payload.email.trim().to_lowercase();
+    let now = Utc::now();
+
+    // The invitee can only see and accept the invitation from inside the
+    // workspace.
+    let is_member = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM workspace_members m JOIN users u ON u.id = m.user_id
+            WHERE m.workspace_id = $1 AND u.email = $2
+        ) AS "exists!"
+        "#,
+        ctx.workspace_id,
+        email
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err("create invitation"))?;
+    if !is_member {
+        return Err(AppError::invalid_field(
+            "email",
+            "no member of this workspace has this email",
+        ));
+    }
+
+    let invitation = sqlx::query_as!(
+        Invitation,
+        r#"
+        INSERT INTO invitations (id, project_id, todo_id, email, role, invited_by, created_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, project_id, todo_id, email, role, invited_by, created_at, expires_at, accepted_at
+        "#,
+        Uuid::new_v4(),
+        project_id,
+        todo_id,
+        email,
+        payload.role.to_string(),
+        ctx.user_id,
+        now,
+        now + INVITATION_TTL
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err("create invitation"))?;
+
+    tx.commit().await.map_err(db_err("create invitation"))?;
+
+    Ok((StatusCode::CREATED, Json(invitation)))
+}
+
+async fn update_member(
+    state: &AppState,
+    ctx: &AuthContext,
+    target: ShareTarget,
+    user_id: Uuid,
+    role: Role,
+) -> Result<Json<Member>, AppError> {
+    let mut tx = state.tenant(ctx).await?;
+    target.authorize(&mut tx, ctx, Role::Owner).await?;
+    let (project_id, todo_id) = target.columns();
+
+    let member = sqlx::query_as!(
+        Member,
+        r#"
+        WITH updated AS (
+            UPDATE shares SET role = $3
+            WHERE user_id = $4 AND (project_id = $1 OR todo_id = $2)
+            RETURNING user_id, role
+        )
+        SELECT u.id AS "user_id!", u.email, u.display_name, updated.role AS "role!"
+        FROM updated JOIN users u ON u.id = updated.user_id
+        "#,
+        project_id,
+        todo_id,
+        role.to_string(),
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err("update member"))?
+    .ok_or(AppError::NotFound)?;
+
+    tx.commit().await.map_err(db_err("update member"))?;
+
+    Ok(Json(member))
+}
+
+/// Owners can remove anyone; every member can remove themselves.
+async fn remove_member(
+    state: &AppState,
+    ctx: &AuthContext,
+    target: ShareTarget,
+    user_id: Uuid,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.tenant(ctx).await?;
+    if ctx.user_id == Some(user_id) {
+        target.authorize(&mut tx, ctx, Role::Viewer).await?;
+    } else {
+        target.authorize(&mut tx, ctx, Role::Owner).await?;
+    }
+    let (project_id, todo_id) = target.columns();
+
+    let result = sqlx::query!(
+        "DELETE FROM shares WHERE user_id = $3 AND (project_id = $1 OR todo_id = $2)",
+        project_id,
+        todo_id,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err("remove member"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tx.commit().await.map_err(db_err("remove member"))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/projects/{id}/members",
+    tag = "sharing",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+    ),
+    responses(
+        (status = 200, description = "Members: the owner, then by role and email", body = Vec<Member>),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn list_project_members(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<Member>>, AppError> {
+    list_members(&state, &ctx, ShareTarget::Project(id)).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/todos/{id}/members",
+    tag = "sharing",
+    params(
+        ("id" = Uuid, Path, description = "Todo ID"),
+    ),
+    responses(
+        (status = 200, description = "Members: the owner, then by role and email", body = Vec<Member>),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn list_todo_members(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<Member>>, AppError> {
+    list_members(&state, &ctx, ShareTarget::Todo(id)).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/projects/{id}/invitations",
+    tag = "sharing",
+    request_body = CreateInvitation,
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+    ),
+    responses(
+        (status = 201, description = "The invitation", body = Invitation),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn invite_to_project(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<CreateInvitation>,
+) -> Result<(StatusCode, Json<Invitation>), AppError> {
+    invite(&state, &ctx, ShareTarget::Project(id), payload).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/todos/{id}/invitations",
+    tag = "sharing",
+    request_body = CreateInvitation,
+    params(
+        ("id" = Uuid, Path, description = "Todo ID"),
+    ),
+    responses(
+        (status = 201, description = "The invitation", body = Invitation),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn invite_to_todo(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<CreateInvitation>,
+) -> Result<(StatusCode, Json<Invitation>), AppError> {
+    invite(&state, &ctx, ShareTarget::Todo(id), payload).await
+}
+
+#[utoipa::path(
+    patch,
+    path = "/projects/{id}/members/{user_id}",
+    tag = "sharing",
+    request_body = UpdateMember,
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 200, description = "The updated member", body = Member),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn update_project_member(
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(payload): Json<UpdateMember>,
+) -> Result<Json<Member>, AppError> {
+    update_member(
+        &state,
+        &ctx,
+        ShareTarget::Project(id),
+        user_id,
+        payload.role,
+    )
+    .await
+}
+
+#[utoipa::path(
+    patch,
+    path = "/todos/{id}/members/{user_id}",
+    tag = "sharing",
+    request_body = UpdateMember,
+    params(
+        ("id" = Uuid, Path, description = "Todo ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 200, description = "The updated member", body = Member),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn update_todo_member(
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(payload): Json<UpdateMember>,
+) -> Result<Json<Member>, AppError> {
+    update_member(&state, &ctx, ShareTarget::Todo(id), user_id, payload.role).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/projects/{id}/members/{user_id}",
+    tag = "sharing",
+    params(
+        ("id" = Uuid, Path, description = "Project ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 204, description = "The member was removed"),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn remove_project_member(
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    remove_member(&state, &ctx, ShareTarget::Project(id), user_id).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/todos/{id}/members/{user_id}",
+    tag = "sharing",
+    params(
+        ("id" = Uuid, Path, description = "Todo ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 204, description = "The member was removed"),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn remove_todo_member(
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    remove_member(&state, &ctx, ShareTarget::Todo(id), user_id).await
+}
+
+/// Email of the calling user. Invitations are matched on it, and users only
+/// have an email once their identity provider has verified it.
+async fn caller_email(state: &AppState, ctx: &AuthContext) -> Result<String, AppError> {
+    let user_id = ctx.user_id.ok_or(AppError::Forbidden)?;
+
+    sqlx::query_scalar!("SELECT email FROM users WHERE id = $1", user_id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(db_err("load user"))?
+        .flatten()
+        .ok_or(AppError::Forbidden)
+}
+
+/// Lists pending invitations addressed to the caller's email.
+#[utoipa::path(
+    get,
+    path = "/invitations",
+    tag = "sharing",
+    responses(
+        (status = 200, description = "Pending invitations", body = Vec<Invitation>),
+        (status = 403, description = "The caller has no verified email", body = ErrorResponse),
+    ),
+)]
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<Invitation>>, AppError> {
+    let email = caller_email(&state, &ctx).await?;
+    let mut tx = state.tenant(&ctx).await?;
+
+    let invitations = sqlx::query_as!(
+        Invitation,
+        r#"
+        SELECT id, project_id, todo_id, email, role, invited_by, created_at, expires_at, accepted_at
+        FROM invitations
+        WHERE email = $1 AND accepted_at IS NULL AND expires_at > $2
+        ORDER BY created_at DESC
+        "#,
+        email,
+        Utc::now()
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err("list invitations"))?;
+
+    Ok(Json(invitations))
+}
+
+#[utoipa::path(
+    post,
+    path = "/invitations/{id}/accept",
+    tag = "sharing",
+    params(
+        ("id" = Uuid, Path, description = "Invitation ID"),
+    ),
+    responses(
+        (status = 200, description = "The accepted invitation", body = Invitation),
+        (status = 403, description = "The caller has no verified email", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn accept_invitation(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Invitation>, AppError> {
+    let email = caller_email(&state, &ctx).await?;
+    let user_id = ctx.user_id.ok_or(AppError::Forbidden)?;
+    let now = Utc::now();
+
+    let mut tx = state.tenant(&ctx).await?;
+
+    let invitation = sqlx::query_as!(
+        Invitation,
+        r#"
+        UPDATE invitations SET accepted_at = $1
+        WHERE id = $2 AND accepted_at IS NULL AND expires_at > $1
+        RETURNING id, project_id, todo_id, email, role, invited_by, created_at, expires_at, accepted_at
+        "#,
+        now,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err("accept invitation"))?
+    .ok_or(AppError::NotFound)?;
+
+    if invitation.email != email {
+        return Err(AppError::Forbidden);
+    }
+
+    // Re-inviting an existing member changes their role.
+    if invitation.project_id.is_some() {
+        sqlx::query!(
+            r#"
+            INSERT INTO shares (id, user_id, project_id, role, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (project_id, user_id) WHERE project_id IS NOT NULL
+            DO UPDATE SET role = EXCLUDED.role
+            "#,
+            Uuid::new_v4(),
+            user_id,
+            invitation.project_id,
+            invitation.role.to_string(),
+            now
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err("accept invitation"))?;
+    } else {
+        sqlx::query!(
+            r#"
+            INSERT INTO shares (id, user_id, todo_id, role, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (todo_id, user_id) WHERE todo_id IS NOT NULL
+            DO UPDATE SET role = EXCLUDED.role
+            "#,
+            Uuid::new_v4(),
+            user_id,
+            invitation.todo_id,
+            invitation.role.to_string(),
+            now
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err("accept invitation"))?;
+    }
+
+    tx.commit().await.map_err(db_err("accept invitation"))?;
+
+    Ok(Json(invitation))
+}
+
+#[utoipa::path(
+    post,
+    path = "/invitations/{id}/decline",
+    tag = "sharing",
+    params(
+        ("id" = Uuid, Path, description = "Invitation ID"),
+    ),
+    responses(
+        (status = 204, description = "The invitation was declined"),
+        (status = 403, description = "The caller has no verified email", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn decline_invitation(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    let email = caller_email(&state, &ctx).await?;
+    let mut tx = state.tenant(&ctx).await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM invitations WHERE id = $1 AND email = $2 AND accepted_at IS NULL",
+        id,
+        email
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err("decline invitation"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tx.commit().await.map_err(db_err("decline invitation"))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{config::Config, models::token::Scopes};
+
+    fn state(pool: PgPool) -> AppState {
+        let mut config = Config::default();
+        config.gemini.api_key = Some("unused".into());
+        AppState::new(pool, config).unwrap()
+    }
+
+    async fn workspace(pool: &PgPool) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO workspaces (id, name, created_at) VALUES ($1, 'Team', now())",
+            id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    async fn user(pool: &PgPool, email: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO users (id, email, created_at, updated_at) VALUES ($1, $2, now(), now())",
+            id,
+            email
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    async fn member(pool: &PgPool, workspace_id: Uuid, email: &str) -> AuthContext {
+        let user_id = user(pool, email).await;
+        sqlx::query!(
+            "INSERT INTO workspace_members (workspace_id, user_id, role, created_at) VALUES ($1, $2, 'Member', now())",
+            workspace_id,
+            user_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        AuthContext {
+            token_id: Some(Uuid::new_v4()),
+            user_id: Some(user_id),
+            workspace_id,
+            scopes: Scopes::all(),
+        }
+    }
+
+    async fn project(pool: &PgPool, owner: &AuthContext) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO projects (id, name, owner_id, workspace_id, created_at, updated_at) VALUES ($1, 'Launch', $2, $3, now(), now())",
+            id,
+            owner.user_id,
+            owner.workspace_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    async fn share(pool: &PgPool, project_id: Uuid, member: &AuthContext, role: Role) {
+        sqlx::query!(
+            "INSERT INTO shares (id, user_id, project_id, role, workspace_id, created_at) VALUES ($1, $2, $3, $4, $5, now())",
+            Uuid::new_v4(),
+            member.user_id,
+            project_id,
+            role.to_string(),
+            member.workspace_id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    fn invitation(email: &str) -> CreateInvitation {
+        CreateInvitation {
+            email: email.into(),
+            role: Role::Editor,
+        }
+    }
+
+    #[sqlx::test]
+    async fn only_workspace_members_can_be_invited(pool: PgPool) {
+        let state = state(pool.clone());
+        let team = workspace(&pool).await;
+        let owner = member(&pool, team, "owner@example.com").await;
+        member(&pool, team, "colleague@example.com").await;
+        member(&pool, workspace(&pool).await, "outsider@example.com").await;
+        user(&pool, "stranger@example.com").await;
+        let target = ShareTarget::Project(project(&pool, &owner).await);
+
+        for email in ["outsider@example.com", "stranger@example.com"] {
+            let result = invite(&state, &owner, target, invitation(email)).await;
+            assert!(matches!(result, Err(AppError::InvalidInput(_))), "{email}");
+        }
+        let (status, _) = invite(&state, &owner, target, invitation("Colleague@example.com"))
+            .await
+            .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[sqlx::test]
+    async fn members_are_listed_by_role_then_email(pool: PgPool) {
+        let state = state(pool.clone());
+        let team = workspace(&pool).await;
+        let owner = member(&pool, team, "zoe@example.com").await;
+        let project_id = project(&pool, &owner).await;
+        for (email, role) in [
+            ("bob@example.com", Role::Viewer),
+            ("carol@example.com", Role::Editor),
+            ("alice@example.com", Role::Viewer),
+            ("dave@example.com", Role::Owner),
+        ] {
+            share(&pool, project_id, &member(&pool, team, email).await, role).await;
+        }
+
+        let Json(members) = list_members(&state, &owner, ShareTarget::Project(project_id))
+            .await
+            .unwrap();
+        let emails: Vec<_> = members.iter().filter_map(|m| m.email.as_deref()).collect();
+        assert_eq!(
+            emails,
+            [
+                "zoe@example.com",
+                "dave@example.com",
+                "carol@example.com",
+                "alice@example.com",
+                "bob@example.com",
+            ]
+        );
+    }
+}
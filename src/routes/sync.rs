@@ -0,0 +1,715 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, PgConnection};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::{
+    auth::AuthContext,
+    error::{AppError, ErrorResponse},
+    models::{
+        share::Role,
+        sync::{LoggedOp, MutationStatus, SyncedTodo, Tombstone},
+        todo::Todo,
+    },
+    permissions,
+    repositories::postgres,
+    routes::todos::{CreateTodo, UpdateTodo},
+    services::crdt::{CrdtState, HlcClock, Op},
+    state::AppState,
+    validator::{ValidatedJson, validation_error},
+};
+
+const DEFAULT_PULL_LIMIT: i64 = 500;
+const MAX_PULL_LIMIT: i64 = 1000;
+const MAX_PUSH_MUTATIONS: usize = 500;
+
+fn db_err(context: &'static str) -> impl Fn(sqlx::Error) -> AppError {
+    move |e| {
+        tracing::error!("Failed to {}: {:?}", context, e);
+        AppError::Internal(format!("failed to {context}"))
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PullQuery {
+    /// Cursor returned by the previous pull; 0 fetches everything.
+    #[serde(default)]
+    pub since: i64,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PullResponse {
+    /// Created or changed todos, oldest change first.
+    pub changes: Vec<SyncedTodo>,
+    pub deleted: Vec<Tombstone>,
+    /// Pass as `since` on the next pull.
+    pub cursor: i64,
+    /// More changes are waiting; pull again right away.
+    pub has_more: bool,
+}
+
+/// Returns the todos created, changed or deleted after `since` that the
+/// caller can see, along with a cursor for the next pull.
+#[utoipa::path(
+    get,
+    path = "/sync",
+    tag = "sync",
+    params(
+        PullQuery,
+    ),
+    responses(
+        (status = 200, description = "Changes after the cursor", body = PullResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    ),
+)]
+pub async fn pull(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(query): Query<PullQuery>,
+) -> Result<Json<PullResponse>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PULL_LIMIT);
+    if !(1..=MAX_PULL_LIMIT).contains(&limit) {
+        return Err(AppError::invalid_field(
+            "limit",
+            &format!("limit must be between 1 and {MAX_PULL_LIMIT}"),
+        ));
+    }
+    let mut tx = state.tenant(&ctx).await?;
+
+    // Writes become visible in `change_seq` order, so everything up to the
+    // current maximum is committed. Reading below it keeps later statements
+    // from picking up a write that commits in between.
+    let upper = sqlx::query_scalar!(
+        r#"
+        SELECT GREATEST(
+            (SELECT COALESCE(MAX(change_seq), 0) FROM todos),
+            (SELECT COALESCE(MAX(change_seq), 0) FROM todo_tombstones)
+        ) AS "upper!"
+        "#
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err("read changes"))?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, title, description, status, priority, source, owner_id, project_id,
+               created_at, updated_at, change_seq
+        FROM todos
+        WHERE change_seq > $1 AND change_seq <= $2
+          AND ($3::uuid IS NULL OR todo_role(id, $3) IS NOT NULL)
+        ORDER BY change_seq
+        LIMIT $4
+        "#,
+        query.since,
+        upper,
+        ctx.user_id,
+        limit + 1
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err("read changes"))?;
+
+    let mut changes: Vec<SyncedTodo> = rows
+        .into_iter()
+        .map(|row| SyncedTodo {
+            todo: Todo {
+                id: row.id,
+                title: row.title,
+                description: row.description,
+                status: row.status.into(),
+                priority: row.priority.into(),
+                source: row.source.into(),
+                owner_id: row.owner_id,
+                project_id: row.project_id,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            },
+            change_seq: row.change_seq,
+        })
+        .collect();
+
+    let mut deleted = sqlx::query_as!(
+        Tombstone,
+        r#"
+        SELECT todo_id AS id, project_id, change_seq, deleted_at
+        FROM todo_tombstones
+        WHERE change_seq > $1 AND change_seq <= $2
+          AND ($3::uuid IS NULL OR $3 = ANY (visible_to))
+        ORDER BY change_seq
+        LIMIT $4
+        "#,
+        query.since,
+        upper,
+        ctx.user_id,
+        limit + 1
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err("read changes"))?;
+
+    // Keep the `limit` oldest changes across both lists. The cursor can only
+    // move past what was returned, unless nothing is left, in which case it
+    // also skips changes the caller cannot see.
+    let mut seqs: Vec<i64> = changes
+        .iter()
+        .map(|t| t.change_seq)
+        .chain(deleted.iter().map(|t| t.change_seq))
+        .collect();
+    seqs.sort_unstable();
+
+    let has_more = seqs.len() as i64 > limit;
+    let cursor = if has_more {
+        let last = seqs[limit as usize - 1];
+        changes.retain(|t| t.change_seq <= last);
+        deleted.retain(|t| t.change_seq <= last);
+        last
+    } else {
+        upper.max(query.since)
+    };
+
+    Ok(Json(PullResponse {
+        changes,
+        deleted,
+        cursor,
+        has_more,
+    }))
+}
+
+/// A change made offline. Todo IDs are generated by the client so that later
+/// mutations in the same batch can refer to todos it creates.
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Mutation {
+    Create {
+        id: Uuid,
+        #[serde(flatten)]
+        todo: CreateTodo,
+    },
+    Update {
+        id: Uuid,
+        /// `change_seq` of the version the client edited. When set and the
+        /// todo has changed since, the update is reported as a conflict
+        /// instead of overwriting the newer version.
+        base_seq: Option<i64>,
+        #[serde(flatten)]
+        changes: UpdateTodo,
+    },
+    Delete {
+        id: Uuid,
+        base_seq: Option<i64>,
+    },
+}
+
+impl Mutation {
+    fn id(&self) -> Uuid {
+        match self {
+            Mutation::Create { id, .. }
+            | Mutation::Update { id, .. }
+            | Mutation::Delete { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PushRequest {
+    pub mutations: Vec<Mutation>,
+}
+
+// Written out because the derived length check needs `Mutation: Serialize`.
+impl Validate for PushRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if !(1..=MAX_PUSH_MUTATIONS).contains(&self.mutations.len()) {
+            let error = ValidationError::new("length")
+                .with_message(format!("send between 1 and {MAX_PUSH_MUTATIONS} mutations").into());
+            errors.add("mutations", error);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MutationResult {
+    pub id: Uuid,
+    pub status: MutationStatus,
+    /// The todo's sequence number after an applied mutation, or the server's
+    /// current one on a conflict.
+    pub change_seq: Option<i64>,
+    /// The todo after an applied create or update, or the server's version on
+    /// a conflict. `null` once deleted.
+    pub todo: Option<SyncedTodo>,
+    pub error: Option<ErrorResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PushResponse {
+    /// One result per mutation, in request order.
+    pub results: Vec<MutationResult>,
+}
+
+/// Result of one mutation that did not fail outright.
+enum Outcome {
+    Applied(Option<SyncedTodo>, i64),
+    Conflict(Option<SyncedTodo>, i64, &'static str),
+}
+
+/// Applies a batch of offline changes in order. Each mutation succeeds or
+/// fails on its own; a conflict or rejection does not undo the others.
+#[utoipa::path(
+    post,
+    path = "/sync",
+    tag = "sync",
+    request_body = PushRequest,
+    responses(
+        (status = 200, description = "One result per mutation, in order", body = PushResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    ),
+)]
+pub async fn push(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<PushRequest>,
+) -> Result<Json<PushResponse>, AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+    let mut results = Vec::with_capacity(payload.mutations.len());
+
+    for mutation in payload.mutations {
+        let id = mutation.id();
+
+        // A savepoint per mutation, so a rejected one leaves no trace.
+        let mut savepoint = tx.begin().await.map_err(db_err("apply changes"))?;
+        let outcome = apply(&mut savepoint, &ctx, &state.clock, mutation).await;
+
+        let result = match outcome {
+            Ok(Outcome::Applied(todo, change_seq)) => {
+                savepoint.commit().await.map_err(db_err("apply changes"))?;
+                MutationResult {
+                    id,
+                    status: MutationStatus::Applied,
+                    change_seq: Some(change_seq),
+                    todo,
+                    error: None,
+                }
+            }
+            Ok(Outcome::Conflict(todo, change_seq, message)) => {
+                savepoint
+                    .rollback()
+                    .await
+                    .map_err(db_err("apply changes"))?;
+                MutationResult {
+                    id,
+                    status: MutationStatus::Conflict,
+                    change_seq: Some(change_seq),
+                    todo,
+                    error: Some(ErrorResponse::from(AppError::Conflict(message.into()))),
+                }
+            }
+            // The database failing is not the mutation's fault.
+            Err(e @ AppError::Internal(_)) => return Err(e),
+            Err(e) => {
+                savepoint
+                    .rollback()
+                    .await
+                    .map_err(db_err("apply changes"))?;
+                MutationResult {
+                    id,
+                    status: MutationStatus::Rejected,
+                    change_seq: None,
+                    todo: None,
+                    error: Some(ErrorResponse::from(e)),
+                }
+            }
+        };
+        results.push(result);
+    }
+
+    tx.commit().await.map_err(db_err("apply changes"))?;
+
+    Ok(Json(PushResponse { results }))
+}
+
+async fn apply(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    clock: &HlcClock,
+    mutation: Mutation,
+) -> Result<Outcome, AppError> {
+    match mutation {
+        Mutation::Create { id, todo } => {
+            todo.validate().map_err(validation_error)?;
+
+            if let Some(change_seq) = tombstone_seq(conn, ctx, id).await? {
+                return Ok(Outcome::Conflict(None, change_seq, "todo was deleted"));
+            }
+            if let Some(current) = current_seq(conn, id).await? {
+                // Most likely a retry of a create whose response was lost.
+                let todo = visible_todo(conn, ctx, id).await?;
+                return Ok(Outcome::Conflict(todo, current, "todo already exists"));
+            }
+
+            let todo = postgres::insert_todo(conn, ctx, id, todo).await?;
+            Ok(applied(synced(conn, todo).await?))
+        }
+        Mutation::Update {
+            id,
+            base_seq,
+            changes,
+        } => {
+            changes.validate().map_err(validation_error)?;
+
+            let Some(current) = current_seq(conn, id).await? else {
+                return match tombstone_seq(conn, ctx, id).await? {
+                    Some(change_seq) => Ok(Outcome::Conflict(None, change_seq, "todo was deleted")),
+                    None => Err(AppError::NotFound),
+                };
+            };
+            if base_seq.is_some_and(|base| base != current) {
+                let todo = visible_todo(conn, ctx, id)
+                    .await?
+                    .ok_or(AppError::NotFound)?;
+                return Ok(Outcome::Conflict(
+                    Some(todo),
+                    current,
+                    "todo was changed since base_seq",
+                ));
+            }
+
+            let todo = postgres::apply_update(conn, ctx, clock, id, changes).await?;
+            Ok(applied(synced(conn, todo).await?))
+        }
+        Mutation::Delete { id, base_seq } => {
+            let Some(current) = current_seq(conn, id).await? else {
+                // Deleting twice is harmless.
+                return match tombstone_seq(conn, ctx, id).await? {
+                    Some(change_seq) => Ok(Outcome::Applied(None, change_seq)),
+                    None => Err(AppError::NotFound),
+                };
+            };
+            if base_seq.is_some_and(|base| base != current) {
+                let todo = visible_todo(conn, ctx, id)
+                    .await?
+                    .ok_or(AppError::NotFound)?;
+                return Ok(Outcome::Conflict(
+                    Some(todo),
+                    current,
+                    "todo was changed since base_seq",
+                ));
+            }
+
+            postgres::remove_todo(conn, ctx, id).await?;
+            let change_seq = tombstone_seq(conn, ctx, id)
+                .await?
+                .ok_or_else(|| AppError::Internal("failed to delete todo".into()))?;
+            Ok(Outcome::Applied(None, change_seq))
+        }
+    }
+}
+
+/// Locks the todo for the rest of the mutation and returns its sequence
+/// number.
+async fn current_seq(conn: &mut PgConnection, id: Uuid) -> Result<Option<i64>, AppError> {
+    sqlx::query_scalar!("SELECT change_seq FROM todos WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(conn)
+        .await
+        .map_err(db_err("apply changes"))
+}
+
+/// Sequence number of the todo's deletion, if it was deleted and the caller
+/// could see it.
+async fn tombstone_seq(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    id: Uuid,
+) -> Result<Option<i64>, AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT change_seq FROM todo_tombstones
+        WHERE todo_id = $1 AND ($2::uuid IS NULL OR $2 = ANY (visible_to))
+        "#,
+        id,
+        ctx.user_id
+    )
+    .fetch_optional(conn)
+    .await
+    .map_err(db_err("apply changes"))
+}
+
+/// The server's version of a todo, if the caller can see it.
+async fn visible_todo(
+    conn: &mut PgConnection,
+    ctx: &AuthContext,
+    id: Uuid,
+) -> Result<Option<SyncedTodo>, AppError> {
+    match permissions::authorize_todo(&mut *conn, ctx, id, Role::Viewer).await {
+        Ok(_) => {}
+        Err(AppError::NotFound) => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let todo = sqlx::query_as!(
+        Todo,
+        "SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at FROM todos WHERE id = $1",
+        id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_err("apply changes"))?;
+
+    synced(conn, todo).await.map(Some)
+}
+
+async fn synced(conn: &mut PgConnection, todo: Todo) -> Result<SyncedTodo, AppError> {
+    let change_seq = sqlx::query_scalar!("SELECT change_seq FROM todos WHERE id = $1", todo.id)
+        .fetch_one(conn)
+        .await
+        .map_err(db_err("apply changes"))?;
+
+    Ok(SyncedTodo { todo, change_seq })
+}
+
+fn applied(todo: SyncedTodo) -> Outcome {
+    let change_seq = todo.change_seq;
+    Outcome::Applied(Some(todo), change_seq)
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct PushOps {
+    /// Each operation needs a timestamp that is unique for the todo.
+    #[validate(length(min = 1, max = 500, message = "send between 1 and 500 operations"))]
+    pub ops: Vec<Op>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MergeResponse {
+    /// The todo after merging.
+    pub todo: SyncedTodo,
+    /// Merge state to continue editing from.
+    pub state: CrdtState,
+}
+
+/// Merges a device's operations into a todo. Operations already merged are
+/// ignored, so a device can resend its whole log after a lost response.
+#[utoipa::path(
+    post,
+    path = "/todos/{id}/ops",
+    tag = "sync",
+    request_body = PushOps,
+    params(
+        ("id" = Uuid, Path, description = "Todo ID"),
+    ),
+    responses(
+        (status = 200, description = "The merged todo and its merge state", body = MergeResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn push_ops(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<PushOps>,
+) -> Result<Json<MergeResponse>, AppError> {
+    for op in &payload.ops {
+        op.validate(&state.clock)?;
+    }
+
+    let mut tx = state.tenant(&ctx).await?;
+    let (todo, crdt) = postgres::merge_ops(&mut tx, &ctx, id, |_| payload.ops).await?;
+    let todo = synced(&mut tx, todo).await?;
+
+    tx.commit().await.map_err(db_err("merge operations"))?;
+
+    Ok(Json(MergeResponse { todo, state: crdt }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OpsQuery {
+    #[serde(default)]
+    pub since: i64,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct OpLog {
+    /// Operations in the order the server merged them.
+    pub ops: Vec<LoggedOp>,
+    pub cursor: i64,
+    pub has_more: bool,
+    /// The todo's current merge state.
+    pub state: CrdtState,
+}
+
+/// Returns the operations merged into a todo after `since`, for devices to
+/// merge into their local copy.
+#[utoipa::path(
+    get,
+    path = "/todos/{id}/ops",
+    tag = "sync",
+    params(
+        ("id" = Uuid, Path, description = "Todo ID"),
+        OpsQuery,
+    ),
+    responses(
+        (status = 200, description = "Operations after the cursor", body = OpLog),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn list_ops(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(query): Query<OpsQuery>,
+) -> Result<Json<OpLog>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PULL_LIMIT);
+    if !(1..=MAX_PULL_LIMIT).contains(&limit) {
+        return Err(AppError::invalid_field(
+            "limit",
+            &format!("limit must be between 1 and {MAX_PULL_LIMIT}"),
+        ));
+    }
+
+    let mut tx = state.tenant(&ctx).await?;
+    permissions::authorize_todo(&mut *tx, &ctx, id, Role::Viewer).await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT seq, op AS "op: sqlx::types::Json<Op>", created_by, created_at
+        FROM todo_ops
+        WHERE todo_id = $1 AND seq > $2
+        ORDER BY seq
+        LIMIT $3
+        "#,
+        id,
+        query.since,
+        limit + 1
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err("read operations"))?;
+
+    let has_more = rows.len() as i64 > limit;
+    let ops: Vec<LoggedOp> = rows
+        .into_iter()
+        .take(limit as usize)
+        .map(|row| LoggedOp {
+            seq: row.seq,
+            op: row.op.0,
+            created_by: row.created_by,
+            created_at: row.created_at,
+        })
+        .collect();
+    let cursor = ops.last().map_or(query.since, |op| op.seq);
+
+    let stored = sqlx::query_scalar!(
+        r#"SELECT state AS "state: sqlx::types::Json<CrdtState>" FROM todo_crdt WHERE todo_id = $1"#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err("read operations"))?;
+    let crdt = match stored {
+        Some(stored) => stored.0,
+        None => {
+            let todo = sqlx::query_as!(
+                Todo,
+                "SELECT id, title, description, status, priority, source, owner_id, project_id, created_at, updated_at FROM todos WHERE id = $1",
+                id
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_err("read operations"))?;
+            CrdtState::from_todo(&todo)
+        }
+    };
+
+    Ok(Json(OpLog {
+        ops,
+        cursor,
+        has_more,
+        state: crdt,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{config::Config, models::token::Scopes};
+
+    fn state(pool: PgPool) -> AppState {
+        let mut config = Config::default();
+        config.gemini.api_key = Some("unused".into());
+        AppState::new(pool, config).unwrap()
+    }
+
+    async fn workspace(pool: &PgPool) -> AuthContext {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO workspaces (id, name, created_at) VALUES ($1, 'Team', now())",
+            id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        AuthContext {
+            token_id: None,
+            user_id: None,
+            workspace_id: id,
+            scopes: Scopes::all(),
+        }
+    }
+
+    fn create(id: Uuid) -> Mutation {
+        Mutation::Create {
+            id,
+            todo: CreateTodo {
+                title: "Buy milk".into(),
+                description: None,
+                priority: None,
+                project_id: None,
+            },
+        }
+    }
+
+    #[sqlx::test]
+    async fn ids_taken_in_another_workspace_are_rejected(pool: PgPool) {
+        let state = state(pool.clone());
+        let a = workspace(&pool).await;
+        let b = workspace(&pool).await;
+        let taken = Uuid::new_v4();
+
+        let push_to = |ctx: AuthContext, mutations| {
+            push(
+                State(state.clone()),
+                Extension(ctx),
+                ValidatedJson(PushRequest { mutations }),
+            )
+        };
+        let Json(response) = push_to(a, vec![create(taken)]).await.unwrap();
+        assert_eq!(response.results[0].status, MutationStatus::Applied);
+
+        let fresh = Uuid::new_v4();
+        let Json(response) = push_to(b, vec![create(taken), create(fresh)])
+            .await
+            .unwrap();
+        assert_eq!(response.results[0].status, MutationStatus::Rejected);
+        assert_eq!(response.results[0].error.as_ref().unwrap().status, 409);
+        assert_eq!(response.results[1].status, MutationStatus::Applied);
+    }
+}
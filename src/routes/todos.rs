@@ -1,19 +1,19 @@
 use axum::{
-    Json,
-    extract::{Path, State},
+    Extension, Json,
+    extract::{Path, Query, State},
 };
-use chrono::Utc;
 use uuid::Uuid;
 
 use crate::{
-    error::AppError,
-    models::todo::{Priority, Todo, TodoSource, TodoStatus},
+    auth::AuthContext,
+    error::{AppError, ErrorResponse},
+    models::todo::{Priority, Todo, TodoStatus},
     state::AppState,
     validator::ValidatedJson,
 };
 use validator::Validate;
 
-#[derive(serde::Deserialize, Validate)]
+#[derive(serde::Serialize, serde::Deserialize, Validate, utoipa::ToSchema)]
 pub struct CreateTodo {
     #[validate(length(min = 1, message = "title cannot be empty"))]
     pub title: String,
@@ -22,93 +22,80 @@ pub struct CreateTodo {
     pub description: Option<String>,
 
     pub priority: Option<Priority>,
+
+    pub project_id: Option<Uuid>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/todos",
+    tag = "todos",
+    request_body = CreateTodo,
+    responses(
+        (status = 200, description = "The created todo", body = Todo),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not an editor of the project", body = ErrorResponse),
+    ),
+)]
 pub async fn create_todo(
     State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
     ValidatedJson(payload): ValidatedJson<CreateTodo>,
 ) -> Result<Json<Todo>, AppError> {
-    let now = Utc::now();
-    let new_todo = Todo {
-        id: Uuid::new_v4(),
-        title: payload.title,
-        description: payload.description,
-        status: TodoStatus::Todo,
-        priority: payload.priority.unwrap_or(Priority::Medium),
-        source: TodoSource::Manual,
-        created_at: now,
-        updated_at: now,
-    };
-
-    let todo = sqlx::query_as!(
-        Todo,
-        r#"
-        INSERT INTO todos (id, title, description, status, priority, source, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        RETURNING id, title, description, status, priority, source, created_at, updated_at
-        "#,
-        new_todo.id,
-        new_todo.title,
-        new_todo.description,
-        new_todo.status.to_string(),
-        new_todo.priority.to_string(),
-        new_todo.source.to_string(),
-        new_todo.created_at,
-        new_todo.updated_at
-    )
-    .fetch_one(&state.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to create todo: {:?}", e);
-        AppError::Internal("failed to create todo".into())
-    })?;
-
+    let todo = state.todos.create(&ctx, payload).await?;
     Ok(Json(todo))
 }
 
-pub async fn list_todos(State(state): State<AppState>) -> Result<Json<Vec<Todo>>, AppError> {
-    let todos = sqlx::query_as!(
-        Todo,
-        r#"
-        SELECT id, title, description, status, priority, source, created_at, updated_at
-        FROM todos
-        ORDER BY created_at DESC
-        "#
-    )
-    .fetch_all(&state.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to list todos: {:?}", e);
-        AppError::Internal("failed to list todos".into())
-    })?;
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListTodosQuery {
+    pub project_id: Option<Uuid>,
+}
 
+/// Lists the todos the caller can see: their own, and those shared with them
+/// directly or through a project.
+#[utoipa::path(
+    get,
+    path = "/todos",
+    tag = "todos",
+    params(
+        ListTodosQuery,
+    ),
+    responses(
+        (status = 200, description = "Todos, newest first", body = Vec<Todo>),
+    ),
+)]
+pub async fn list_todos(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Query(query): Query<ListTodosQuery>,
+) -> Result<Json<Vec<Todo>>, AppError> {
+    let todos = state.todos.list(&ctx, query.project_id).await?;
     Ok(Json(todos))
 }
 
+#[utoipa::path(
+    get,
+    path = "/todos/{id}",
+    tag = "todos",
+    params(
+        ("id" = Uuid, Path, description = "Todo ID"),
+    ),
+    responses(
+        (status = 200, description = "The todo", body = Todo),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
 pub async fn get_todo(
     Path(id): Path<Uuid>,
     State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
 ) -> Result<Json<Todo>, AppError> {
-    let todo = sqlx::query_as!(
-        Todo,
-        r#"
-        SELECT id, title, description, status, priority, source, created_at, updated_at
-        FROM todos WHERE id = $1
-        "#,
-        id
-    )
-    .fetch_optional(&state.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to get todo: {:?}", e);
-        AppError::Internal("failed to get todo".into())
-    })?
-    .ok_or(AppError::NotFound)?;
-
+    let todo = state.todos.get(&ctx, id).await?;
     Ok(Json(todo))
 }
 
-#[derive(serde::Deserialize, Validate)]
+#[derive(Default, serde::Serialize, serde::Deserialize, Validate, utoipa::ToSchema)]
 pub struct UpdateTodo {
     #[validate(length(min = 1))]
     pub title: Option<String>,
@@ -118,82 +105,199 @@ pub struct UpdateTodo {
 
     pub status: Option<TodoStatus>,
     pub priority: Option<Priority>,
+
+    /// Moves the todo into another project the caller can edit.
+    pub project_id: Option<Uuid>,
 }
 
+#[utoipa::path(
+    patch,
+    path = "/todos/{id}",
+    tag = "todos",
+    request_body = UpdateTodo,
+    params(
+        ("id" = Uuid, Path, description = "Todo ID"),
+    ),
+    responses(
+        (status = 200, description = "The updated todo", body = Todo),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
 pub async fn update_todo(
     Path(id): Path<Uuid>,
     State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
     ValidatedJson(payload): ValidatedJson<UpdateTodo>,
 ) -> Result<Json<Todo>, AppError> {
-    // Fetch first to apply partial updates
-    let mut todo = sqlx::query_as!(
-        Todo,
-        "SELECT id, title, description, status, priority, source, created_at, updated_at FROM todos WHERE id = $1",
-        id
-    )
-    .fetch_optional(&state.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to fetch todo for update: {:?}", e);
-        AppError::Internal("failed to fetch todo".into())
-    })?
-    .ok_or(AppError::NotFound)?;
-
-    if let Some(title) = payload.title {
-        todo.title = title;
-    }
-    if let Some(desc) = payload.description {
-        todo.description = Some(desc);
-    }
-    if let Some(status) = payload.status {
-        todo.status = status;
-    }
-    if let Some(priority) = payload.priority {
-        todo.priority = priority;
-    }
-
-    todo.updated_at = Utc::now();
-
-    let updated_todo = sqlx::query_as!(
-        Todo,
-        r#"
-        UPDATE todos 
-        SET title = $1, description = $2, status = $3, priority = $4, updated_at = $5
-        WHERE id = $6
-        RETURNING id, title, description, status, priority, source, created_at, updated_at
-        "#,
-        todo.title,
-        todo.description,
-        todo.status.to_string(),
-        todo.priority.to_string(),
-        todo.updated_at,
-        id
-    )
-    .fetch_one(&state.pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to update todo: {:?}", e);
-        AppError::Internal("failed to update todo".into())
-    })?;
-
+    let updated_todo = state.todos.update(&ctx, id, payload).await?;
     Ok(Json(updated_todo))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/todos/{id}",
+    tag = "todos",
+    params(
+        ("id" = Uuid, Path, description = "Todo ID"),
+    ),
+    responses(
+        (status = 200, description = "The todo was deleted"),
+        (status = 403, description = "Not allowed for the caller's role", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
 pub async fn delete_todo(
     Path(id): Path<Uuid>,
     State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
 ) -> Result<(), AppError> {
-    let result = sqlx::query!("DELETE FROM todos WHERE id = $1", id)
-        .execute(&state.pool)
+    state.todos.delete(&ctx, id).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config, models::token::Scopes, repositories::memory::MemoryTodoRepository,
+    };
+
+    /// State whose pool never connects: the handlers must only touch the
+    /// in-memory store.
+    fn state() -> AppState {
+        let mut config = Config::default();
+        config.gemini.api_key = Some("unused".into());
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+        AppState::new(pool, config)
+            .unwrap()
+            .with_todos(MemoryTodoRepository::default())
+    }
+
+    fn user(workspace_id: Uuid) -> AuthContext {
+        AuthContext {
+            token_id: Some(Uuid::new_v4()),
+            user_id: Some(Uuid::new_v4()),
+            workspace_id,
+            scopes: Scopes::all(),
+        }
+    }
+
+    async fn create(state: &AppState, ctx: &AuthContext, title: &str) -> Todo {
+        let payload = CreateTodo {
+            title: title.into(),
+            description: None,
+            priority: None,
+            project_id: None,
+        };
+        create_todo(
+            State(state.clone()),
+            Extension(ctx.clone()),
+            ValidatedJson(payload),
+        )
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to delete todo: {:?}", e);
-            AppError::Internal("failed to delete todo".into())
-        })?;
+        .unwrap()
+        .0
+    }
 
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound);
+    #[tokio::test]
+    async fn created_todos_are_listed_newest_first() {
+        let state = state();
+        let alice = user(Uuid::new_v4());
+        let first = create(&state, &alice, "Buy milk").await;
+        let second = create(&state, &alice, "Call Bob").await;
+
+        assert_eq!(first.owner_id, alice.user_id);
+        assert_eq!(first.status, TodoStatus::Todo);
+        assert_eq!(first.priority, Priority::Medium);
+
+        let Json(todos) = list_todos(
+            State(state.clone()),
+            Extension(alice.clone()),
+            Query(ListTodosQuery { project_id: None }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(todos, vec![second, first]);
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn todos_are_private_to_their_owner_and_workspace() {
+        let state = state();
+        let alice = user(Uuid::new_v4());
+        let todo = create(&state, &alice, "Buy milk").await;
+
+        let bob = user(alice.workspace_id);
+        let Json(todos) = list_todos(
+            State(state.clone()),
+            Extension(bob.clone()),
+            Query(ListTodosQuery { project_id: None }),
+        )
+        .await
+        .unwrap();
+        assert!(todos.is_empty());
+        let result = get_todo(Path(todo.id), State(state.clone()), Extension(bob)).await;
+        assert!(matches!(result, Err(AppError::Forbidden)));
+
+        let mut elsewhere = alice.clone();
+        elsewhere.workspace_id = Uuid::new_v4();
+        let result = get_todo(Path(todo.id), State(state.clone()), Extension(elsewhere)).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn update_changes_only_the_given_fields() {
+        let state = state();
+        let alice = user(Uuid::new_v4());
+        let todo = create(&state, &alice, "Buy milk").await;
+
+        let changes = UpdateTodo {
+            status: Some(TodoStatus::Done),
+            ..Default::default()
+        };
+        let Json(updated) = update_todo(
+            Path(todo.id),
+            State(state.clone()),
+            Extension(alice.clone()),
+            ValidatedJson(changes),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(updated.status, TodoStatus::Done);
+        assert_eq!(updated.title, "Buy milk");
+        assert!(updated.updated_at > todo.updated_at);
+        let Json(fetched) = get_todo(Path(todo.id), State(state.clone()), Extension(alice))
+            .await
+            .unwrap();
+        assert_eq!(fetched, updated);
+    }
+
+    #[tokio::test]
+    async fn deleted_todos_are_gone() {
+        let state = state();
+        let alice = user(Uuid::new_v4());
+        let todo = create(&state, &alice, "Buy milk").await;
+
+        delete_todo(
+            Path(todo.id),
+            State(state.clone()),
+            Extension(alice.clone()),
+        )
+        .await
+        .unwrap();
+
+        let result = get_todo(
+            Path(todo.id),
+            State(state.clone()),
+            Extension(alice.clone()),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+        let result = delete_todo(Path(todo.id), State(state.clone()), Extension(alice)).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+    }
 }
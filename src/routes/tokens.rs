@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    auth::{self, AuthContext},
+    error::{AppError, ErrorResponse},
+    models::token::{ApiToken, Scope, Scopes},
+    state::AppState,
+    validator::ValidatedJson,
+};
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateToken {
+    #[validate(length(min = 1, max = 100, message = "name must be 1-100 characters"))]
+    pub name: String,
+
+    #[validate(length(min = 1, message = "at least one scope is required"))]
+    pub scopes: Vec<Scope>,
+
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedToken {
+    /// The plaintext token. It is not stored and cannot be retrieved again.
+    pub token: String,
+    #[serde(flatten)]
+    pub api_token: ApiToken,
+}
+
+#[utoipa::path(
+    post,
+    path = "/tokens",
+    tag = "tokens",
+    request_body = CreateToken,
+    responses(
+        (status = 201, description = "The token; its secret is only shown once", body = CreatedToken),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Requested scopes the caller does not have", body = ErrorResponse),
+    ),
+)]
+pub async fn create_token(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<CreateToken>,
+) -> Result<(StatusCode, Json<CreatedToken>), AppError> {
+    let now = Utc::now();
+
+    let mut errors = HashMap::new();
+    if payload.expires_at.is_some_and(|at| at <= now) {
+        errors.insert(
+            "expires_at".to_string(),
+            vec!["expires_at must be in the future".to_string()],
+        );
+    }
+    // A token can never grant more than its creator holds.
+    let escalated: Vec<String> = payload
+        .scopes
+        .iter()
+        .filter(|s| !ctx.has_scope(**s))
+        .map(ToString::to_string)
+        .collect();
+    if !escalated.is_empty() {
+        errors.insert(
+            "scopes".to_string(),
+            vec![format!(
+                "cannot grant scopes you do not hold: {}",
+                escalated.join(", ")
+            )],
+        );
+    }
+    if !errors.is_empty() {
+        return Err(AppError::InvalidInput(errors));
+    }
+
+    let mut scopes = Scopes::default();
+    for scope in payload.scopes {
+        if !scopes.contains(scope) {
+            scopes.0.push(scope);
+        }
+    }
+
+    let token = auth::generate_token();
+    let token_prefix: String = token.chars().take(12).collect();
+
+    let api_token = sqlx::query_as!(
+        ApiToken,
+        r#"
+        INSERT INTO api_tokens (id, name, token_hash, token_prefix, scopes, created_at, expires_at, user_id, workspace_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING id, name, token_prefix, scopes, created_at, expires_at, last_used_at, revoked_at
+        "#,
+        Uuid::new_v4(),
+        payload.name,
+        auth::hash_token(&token),
+        token_prefix,
+        &scopes.to_strings(),
+        now,
+        payload.expires_at,
+        ctx.user_id,
+        ctx.workspace_id
+    )
+    .fetch_one(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create api token: {:?}", e);
+        AppError::Internal("failed to create token".into())
+    })?;
+
+    tracing::info!(
+        token_id = %api_token.id,
+        created_by = ?ctx.token_id,
+        "Created api token"
+    );
+
+    Ok((StatusCode::CREATED, Json(CreatedToken { token, api_token })))
+}
+
+/// Lists the caller's personal access tokens in the current workspace.
+/// Service-account tokens see every personal token in their workspace; only
+/// the bootstrap token sees those of every workspace.
+#[utoipa::path(
+    get,
+    path = "/tokens",
+    tag = "tokens",
+    responses(
+        (status = 200, description = "Tokens", body = Vec<ApiToken>),
+    ),
+)]
+pub async fn list_tokens(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<ApiToken>>, AppError> {
+    let tokens = sqlx::query_as!(
+        ApiToken,
+        r#"
+        SELECT id, name, token_prefix, scopes, created_at, expires_at, last_used_at, revoked_at
+        FROM api_tokens
+        WHERE kind = 'personal'
+          AND ($1::uuid IS NULL OR user_id = $1)
+          AND ($2 OR workspace_id = $3)
+        ORDER BY created_at DESC
+        "#,
+        ctx.user_id,
+        ctx.token_id.is_none(),
+        ctx.workspace_id
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to list api tokens: {:?}", e);
+        AppError::Internal("failed to list tokens".into())
+    })?;
+
+    Ok(Json(tokens))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tokens/{id}",
+    tag = "tokens",
+    params(
+        ("id" = Uuid, Path, description = "Token ID"),
+    ),
+    responses(
+        (status = 204, description = "The token was revoked"),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn revoke_token(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE api_tokens SET revoked_at = $1
+        WHERE id = $2 AND revoked_at IS NULL
+          AND ($3::uuid IS NULL OR user_id = $3)
+          AND ($4 OR workspace_id = $5)
+        "#,
+        Utc::now(),
+        id,
+        ctx.user_id,
+        ctx.token_id.is_none(),
+        ctx.workspace_id
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to revoke api token: {:?}", e);
+        AppError::Internal("failed to revoke token".into())
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::config::Config;
+
+    fn state(pool: PgPool) -> AppState {
+        let mut config = Config::default();
+        config.gemini.api_key = Some("unused".into());
+        AppState::new(pool, config).unwrap()
+    }
+
+    async fn workspace(pool: &PgPool) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO workspaces (id, name, created_at) VALUES ($1, 'Team', now())",
+            id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    /// A service-account token created in `workspace_id`.
+    async fn service_token(state: &AppState, workspace_id: Uuid) -> (AuthContext, ApiToken) {
+        let admin = AuthContext {
+            token_id: None,
+            user_id: None,
+            workspace_id,
+            scopes: Scopes::all(),
+        };
+        let payload = CreateToken {
+            name: "ci".into(),
+            scopes: Scopes::all().0,
+            expires_at: None,
+        };
+        let (_, Json(created)) = create_token(
+            State(state.clone()),
+            Extension(admin),
+            ValidatedJson(payload),
+        )
+        .await
+        .unwrap();
+        let ctx = AuthContext {
+            token_id: Some(created.api_token.id),
+            user_id: None,
+            workspace_id,
+            scopes: Scopes::all(),
+        };
+        (ctx, created.api_token)
+    }
+
+    #[sqlx::test]
+    async fn service_tokens_stay_in_their_workspace(pool: PgPool) {
+        let state = state(pool.clone());
+        let (a, a_token) = service_token(&state, workspace(&pool).await).await;
+        let (b, _) = service_token(&state, workspace(&pool).await).await;
+
+        let Json(tokens) = list_tokens(State(state.clone()), Extension(b.clone()))
+            .await
+            .unwrap();
+        assert!(tokens.iter().all(|t| t.id != a_token.id));
+        let result = revoke_token(Path(a_token.id), State(state.clone()), Extension(b)).await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+
+        let Json(tokens) = list_tokens(State(state.clone()), Extension(a.clone()))
+            .await
+            .unwrap();
+        assert!(tokens.iter().any(|t| t.id == a_token.id));
+        revoke_token(Path(a_token.id), State(state), Extension(a))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,609 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, Transaction};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    auth::{self, AuthContext},
+    error::{AppError, ErrorResponse},
+    models::user::User,
+    routes::auth::LoginResponse,
+    services::totp,
+    state::AppState,
+};
+
+/// How long a user has to enter their second factor after the IdP login.
+const CHALLENGE_TTL: Duration = Duration::minutes(5);
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EnrollResponse {
+    /// Base32 secret for manual entry into an authenticator app.
+    pub secret: String,
+    /// `otpauth://totp/...` URI, suitable for rendering as a QR code.
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ActivateRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    /// Single-use codes. They are only shown once.
+    pub recovery_codes: Vec<String>,
+}
+
+/// A second factor: either a current TOTP code or an unused recovery code.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SecondFactor {
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyRequest {
+    pub mfa_token: String,
+    #[serde(flatten)]
+    pub factor: SecondFactor,
+}
+
+fn db_err(e: sqlx::Error) -> AppError {
+    tracing::error!("TOTP database error: {:?}", e);
+    AppError::Internal("failed to process two-factor request".into())
+}
+
+fn unix_now() -> u64 {
+    u64::try_from(Utc::now().timestamp()).unwrap_or_default()
+}
+
+/// Creates a pending login for a user who still has to present a second factor.
+pub async fn start_challenge(
+    pool: &sqlx::PgPool,
+    user_id: Uuid,
+) -> Result<(String, DateTime<Utc>), AppError> {
+    let token = auth::generate_token();
+    let now = Utc::now();
+
+    sqlx::query!(
+        "DELETE FROM mfa_challenges WHERE created_at < $1",
+        now - CHALLENGE_TTL
+    )
+    .execute(pool)
+    .await
+    .map_err(db_err)?;
+
+    sqlx::query!(
+        "INSERT INTO mfa_challenges (token_hash, user_id, created_at) VALUES ($1, $2, $3)",
+        auth::hash_token(&token),
+        user_id,
+        now
+    )
+    .execute(pool)
+    .await
+    .map_err(db_err)?;
+
+    Ok((token, now + CHALLENGE_TTL))
+}
+
+/// Checks a second factor for `user_id`, consuming it on success: TOTP codes
+/// cannot be replayed and recovery codes are marked used.
+async fn check_second_factor(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    factor: &SecondFactor,
+) -> Result<bool, AppError> {
+    if let Some(code) = &factor.code {
+        let user = sqlx::query!(
+            "SELECT totp_secret, totp_last_step FROM users WHERE id = $1 AND totp_enabled_at IS NOT NULL FOR UPDATE",
+            user_id
+        )
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(db_err)?;
+
+        let Some((secret, last_step)) = user.and_then(|u| Some((u.totp_secret?, u.totp_last_step)))
+        else {
+            return Ok(false);
+        };
+
+        let Some(step) = totp::verify(&secret, code, unix_now())? else {
+            return Ok(false);
+        };
+        let step = i64::try_from(step).unwrap_or(i64::MAX);
+        if last_step.is_some_and(|last| step <= last) {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            "UPDATE users SET totp_last_step = $1 WHERE id = $2",
+            step,
+            user_id
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(db_err)?;
+
+        return Ok(true);
+    }
+
+    if let Some(code) = &factor.recovery_code {
+        let used = sqlx::query!(
+            r#"
+            UPDATE recovery_codes SET used_at = $1
+            WHERE user_id = $2 AND code_hash = $3 AND used_at IS NULL
+            "#,
+            Utc::now(),
+            user_id,
+            auth::hash_token(&totp::normalize_recovery_code(code))
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(db_err)?;
+
+        return Ok(used.rows_affected() > 0);
+    }
+
+    Err(AppError::invalid_field(
+        "code",
+        "either code or recovery_code is required",
+    ))
+}
+
+/// Replaces the user's recovery codes with a fresh set and returns them.
+async fn issue_recovery_codes(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> Result<Vec<String>, AppError> {
+    let codes = totp::generate_recovery_codes();
+    let now = Utc::now();
+
+    sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1", user_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(db_err)?;
+
+    for code in &codes {
+        sqlx::query!(
+            "INSERT INTO recovery_codes (id, user_id, code_hash, created_at) VALUES ($1, $2, $3, $4)",
+            Uuid::new_v4(),
+            user_id,
+            auth::hash_token(code),
+            now
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(db_err)?;
+    }
+
+    Ok(codes)
+}
+
+/// Generates a new secret for the current user. 2FA is not enforced until
+/// the secret is confirmed with [`activate`].
+#[utoipa::path(
+    post,
+    path = "/auth/totp/enroll",
+    tag = "auth",
+    responses(
+        (status = 200, description = "A new secret to add to an authenticator app", body = EnrollResponse),
+        (status = 400, description = "2FA is already enabled", body = ErrorResponse),
+    ),
+)]
+pub async fn enroll(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<EnrollResponse>, AppError> {
+    let user_id = ctx.user_id.ok_or(AppError::NotFound)?;
+    let secret = totp::generate_secret();
+
+    let user = sqlx::query!(
+        r#"
+        UPDATE users SET totp_secret = $1, totp_last_step = NULL, updated_at = $2
+        WHERE id = $3 AND totp_enabled_at IS NULL
+        RETURNING email
+        "#,
+        secret,
+        Utc::now(),
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_err)?
+    .ok_or_else(|| {
+        AppError::invalid_field("totp", "two-factor authentication is already enabled")
+    })?;
+
+    let account_name = user.email.unwrap_or_else(|| user_id.to_string());
+    let otpauth_uri = totp::otpauth_uri(&secret, &account_name)?;
+
+    Ok(Json(EnrollResponse {
+        secret,
+        otpauth_uri,
+    }))
+}
+
+/// Confirms enrollment with a code from the authenticator app, enables 2FA
+/// and returns the recovery codes.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/activate",
+    tag = "auth",
+    request_body = ActivateRequest,
+    responses(
+        (status = 200, description = "Recovery codes; only shown once", body = RecoveryCodesResponse),
+        (status = 400, description = "No pending enrollment, or invalid code", body = ErrorResponse),
+    ),
+)]
+pub async fn activate(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(req): Json<ActivateRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let user_id = ctx.user_id.ok_or(AppError::NotFound)?;
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let secret = sqlx::query_scalar!(
+        "SELECT totp_secret FROM users WHERE id = $1 AND totp_enabled_at IS NULL FOR UPDATE",
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .flatten()
+    .ok_or_else(|| AppError::invalid_field("totp", "no pending enrollment; call enroll first"))?;
+
+    let step = totp::verify(&secret, &req.code, unix_now())?
+        .ok_or_else(|| AppError::invalid_field("code", "invalid code"))?;
+
+    let now = Utc::now();
+    sqlx::query!(
+        "UPDATE users SET totp_enabled_at = $1, totp_last_step = $2, updated_at = $1 WHERE id = $3",
+        now,
+        i64::try_from(step).unwrap_or(i64::MAX),
+        user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    let recovery_codes = issue_recovery_codes(&mut tx, user_id).await?;
+    tx.commit().await.map_err(db_err)?;
+
+    tracing::info!(user_id = %user_id, "Two-factor authentication enabled");
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Replaces the recovery codes; requires a valid second factor.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/recovery-codes",
+    tag = "auth",
+    request_body = SecondFactor,
+    responses(
+        (status = 200, description = "New recovery codes; only shown once", body = RecoveryCodesResponse),
+        (status = 400, description = "Neither code nor recovery_code was given", body = ErrorResponse),
+        (status = 401, description = "Invalid second factor, or 2FA is not enabled", body = ErrorResponse),
+    ),
+)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(factor): Json<SecondFactor>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let user_id = ctx.user_id.ok_or(AppError::NotFound)?;
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    if !check_second_factor(&mut tx, user_id, &factor).await? {
+        return Err(AppError::Unauthorized);
+    }
+
+    let recovery_codes = issue_recovery_codes(&mut tx, user_id).await?;
+    tx.commit().await.map_err(db_err)?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Turns 2FA off for the current user; requires a valid second factor.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/disable",
+    tag = "auth",
+    request_body = SecondFactor,
+    responses(
+        (status = 204, description = "2FA was disabled"),
+        (status = 400, description = "Neither code nor recovery_code was given", body = ErrorResponse),
+        (status = 401, description = "Invalid second factor, or 2FA is not enabled", body = ErrorResponse),
+    ),
+)]
+pub async fn disable(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    Json(factor): Json<SecondFactor>,
+) -> Result<StatusCode, AppError> {
+    let user_id = ctx.user_id.ok_or(AppError::NotFound)?;
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    if !check_second_factor(&mut tx, user_id, &factor).await? {
+        return Err(AppError::Unauthorized);
+    }
+
+    clear_totp(&mut tx, user_id).await?;
+    tx.commit().await.map_err(db_err)?;
+
+    tracing::info!(user_id = %user_id, "Two-factor authentication disabled");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn clear_totp(tx: &mut Transaction<'_, Postgres>, user_id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE users
+        SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL, updated_at = $1
+        WHERE id = $2
+        "#,
+        Utc::now(),
+        user_id
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(db_err)?;
+
+    sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1", user_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(db_err)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Completes a login that was answered with `mfa_required`.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/verify",
+    tag = "auth",
+    request_body = VerifyRequest,
+    responses(
+        (status = 200, description = "A session", body = LoginResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 401, description = "The challenge is invalid or expired", body = ErrorResponse),
+    ),
+    security(()),
+)]
+pub async fn verify(
+    State(state): State<AppState>,
+    Json(req): Json<VerifyRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    let challenge = sqlx::query!(
+        r#"
+        SELECT user_id, attempts FROM mfa_challenges
+        WHERE token_hash = $1 AND created_at > $2
+        FOR UPDATE
+        "#,
+        auth::hash_token(&req.mfa_token),
+        Utc::now() - CHALLENGE_TTL
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err)?
+    .filter(|c| c.attempts < MAX_CHALLENGE_ATTEMPTS)
+    .ok_or(AppError::Unauthorized)?;
+
+    if !check_second_factor(&mut tx, challenge.user_id, &req.factor).await? {
+        sqlx::query!(
+            "UPDATE mfa_challenges SET attempts = attempts + 1 WHERE token_hash = $1",
+            auth::hash_token(&req.mfa_token)
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err)?;
+        tx.commit().await.map_err(db_err)?;
+        return Err(AppError::Unauthorized);
+    }
+
+    sqlx::query!(
+        "DELETE FROM mfa_challenges WHERE token_hash = $1",
+        auth::hash_token(&req.mfa_token)
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        SELECT id, email, display_name, totp_enabled_at IS NOT NULL AS "totp_enabled!", created_at, updated_at
+        FROM users WHERE id = $1
+        "#,
+        challenge.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err)?;
+
+    tx.commit().await.map_err(db_err)?;
+
+    let (token, expires_at) = auth::create_session(&state.pool, user.id).await?;
+
+    tracing::info!(user_id = %user.id, "User logged in via OIDC with second factor");
+
+    Ok(Json(LoginResponse::Session {
+        token,
+        expires_at,
+        user,
+    }))
+}
+
+/// Administrative reset for users who lost both their authenticator and
+/// their recovery codes. Requires the `users:admin` scope, and that the user
+/// is a member of the caller's workspace unless the caller is the bootstrap
+/// token.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/totp/reset",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 204, description = "2FA was reset"),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn admin_reset(
+    Path(user_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.pool.begin().await.map_err(db_err)?;
+
+    if ctx.token_id.is_some() {
+        let is_member = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM workspace_members WHERE workspace_id = $1 AND user_id = $2) AS "exists!""#,
+            ctx.workspace_id,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_err)?;
+        if !is_member {
+            return Err(AppError::NotFound);
+        }
+    }
+
+    if !clear_totp(&mut tx, user_id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    tx.commit().await.map_err(db_err)?;
+
+    tracing::warn!(
+        user_id = %user_id,
+        reset_by = ?ctx.token_id,
+        "Two-factor authentication reset by administrator"
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{config::Config, models::token::Scopes};
+
+    fn state(pool: PgPool) -> AppState {
+        let mut config = Config::default();
+        config.gemini.api_key = Some("unused".into());
+        AppState::new(pool, config).unwrap()
+    }
+
+    async fn workspace(pool: &PgPool) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO workspaces (id, name, created_at) VALUES ($1, 'Team', now())",
+            id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    /// A member of `workspace_id` with 2FA enabled.
+    async fn enrolled_member(pool: &PgPool, workspace_id: Uuid) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO users (id, created_at, updated_at, totp_secret, totp_enabled_at)
+            VALUES ($1, now(), now(), $2, now())
+            "#,
+            id,
+            totp::generate_secret()
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query!(
+            "INSERT INTO workspace_members (workspace_id, user_id, role, created_at) VALUES ($1, $2, 'Member', now())",
+            workspace_id,
+            id
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    async fn totp_enabled(pool: &PgPool, user_id: Uuid) -> bool {
+        sqlx::query_scalar!(
+            r#"SELECT totp_enabled_at IS NOT NULL AS "enabled!" FROM users WHERE id = $1"#,
+            user_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    fn service_token(workspace_id: Uuid) -> AuthContext {
+        AuthContext {
+            token_id: Some(Uuid::new_v4()),
+            user_id: None,
+            workspace_id,
+            scopes: Scopes::all(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn admins_only_reset_members_of_their_workspace(pool: PgPool) {
+        let state = state(pool.clone());
+        let a = workspace(&pool).await;
+        let b = workspace(&pool).await;
+        let user = enrolled_member(&pool, b).await;
+
+        let result = admin_reset(
+            Path(user),
+            State(state.clone()),
+            Extension(service_token(a)),
+        )
+        .await;
+        assert!(matches!(result, Err(AppError::NotFound)));
+        assert!(totp_enabled(&pool, user).await);
+
+        admin_reset(
+            Path(user),
+            State(state.clone()),
+            Extension(service_token(b)),
+        )
+        .await
+        .unwrap();
+        assert!(!totp_enabled(&pool, user).await);
+    }
+
+    #[sqlx::test]
+    async fn the_bootstrap_token_resets_anyone(pool: PgPool) {
+        let state = state(pool.clone());
+        let user = enrolled_member(&pool, workspace(&pool).await).await;
+        let admin = AuthContext {
+            token_id: None,
+            ..service_token(workspace(&pool).await)
+        };
+
+        admin_reset(Path(user), State(state), Extension(admin))
+            .await
+            .unwrap();
+        assert!(!totp_enabled(&pool, user).await);
+    }
+}
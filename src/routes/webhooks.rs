@@ -0,0 +1,395 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    auth::AuthContext,
+    error::{AppError, ErrorResponse},
+    models::{
+        webhook::{Webhook, WebhookDelivery, WebhookEvent, WebhookEvents},
+        workspace::WorkspaceRole,
+    },
+    permissions,
+    services::webhooks,
+    state::AppState,
+    validator::ValidatedJson,
+};
+
+/// Number of deliveries returned by the delivery log.
+const DELIVERY_LOG_LIMIT: i64 = 50;
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct CreateWebhook {
+    #[validate(length(max = 2000, message = "url must be at most 2000 characters"))]
+    pub url: String,
+
+    #[validate(length(min = 1, message = "at least one event is required"))]
+    pub events: Vec<WebhookEvent>,
+
+    /// Generated when omitted.
+    #[validate(length(min = 16, max = 200, message = "secret must be 16-200 characters"))]
+    pub secret: Option<String>,
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct UpdateWebhook {
+    #[validate(length(max = 2000, message = "url must be at most 2000 characters"))]
+    pub url: Option<String>,
+
+    #[validate(length(min = 1, message = "at least one event is required"))]
+    pub events: Option<Vec<WebhookEvent>>,
+
+    pub active: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedWebhook {
+    /// Used to sign deliveries. It cannot be retrieved again.
+    pub secret: String,
+    #[serde(flatten)]
+    pub webhook: Webhook,
+}
+
+fn db_err(context: &'static str) -> impl Fn(sqlx::Error) -> AppError {
+    move |e| {
+        tracing::error!("Failed to {}: {:?}", context, e);
+        AppError::Internal(format!("failed to {context}"))
+    }
+}
+
+async fn validate_url(state: &AppState, url: &str) -> Result<(), AppError> {
+    webhooks::check_target(url, state.config.webhooks.allow_loopback)
+        .await
+        .map_err(|message| AppError::invalid_field("url", &message))
+}
+
+fn dedupe(events: Vec<WebhookEvent>) -> WebhookEvents {
+    let mut unique = WebhookEvents::default();
+    for event in events {
+        if !unique.0.contains(&event) {
+            unique.0.push(event);
+        }
+    }
+    unique
+}
+
+/// Webhooks receive every todo in the workspace, so managing them is
+/// reserved to workspace admins.
+async fn authorize(conn: &mut sqlx::PgConnection, ctx: &AuthContext) -> Result<(), AppError> {
+    permissions::authorize_workspace(conn, ctx, ctx.workspace_id, WorkspaceRole::Admin).await?;
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhook,
+    responses(
+        (status = 201, description = "The webhook; its signing secret is only shown once", body = CreatedWebhook),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    ),
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<CreateWebhook>,
+) -> Result<(StatusCode, Json<CreatedWebhook>), AppError> {
+    validate_url(&state, &payload.url).await?;
+
+    let mut tx = state.tenant(&ctx).await?;
+    authorize(&mut tx, &ctx).await?;
+
+    let secret = payload.secret.unwrap_or_else(webhooks::generate_secret);
+    let now = Utc::now();
+
+    let webhook = sqlx::query_as!(
+        Webhook,
+        r#"
+        INSERT INTO webhooks (id, url, secret, events, created_by, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $6)
+        RETURNING id, url, events, active, created_by, created_at, updated_at
+        "#,
+        Uuid::new_v4(),
+        payload.url,
+        secret,
+        &dedupe(payload.events).to_strings(),
+        ctx.user_id,
+        now
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err("create webhook"))?;
+
+    tx.commit().await.map_err(db_err("create webhook"))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedWebhook { secret, webhook }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Webhooks", body = Vec<Webhook>),
+    ),
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<Webhook>>, AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+    authorize(&mut tx, &ctx).await?;
+
+    let webhooks = sqlx::query_as!(
+        Webhook,
+        r#"
+        SELECT id, url, events, active, created_by, created_at, updated_at
+        FROM webhooks
+        ORDER BY created_at DESC
+        "#
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err("list webhooks"))?;
+
+    Ok(Json(webhooks))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = 200, description = "The webhook", body = Webhook),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn get_webhook(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Webhook>, AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+    authorize(&mut tx, &ctx).await?;
+
+    let webhook = sqlx::query_as!(
+        Webhook,
+        r#"
+        SELECT id, url, events, active, created_by, created_at, updated_at
+        FROM webhooks WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err("get webhook"))?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(webhook))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    request_body = UpdateWebhook,
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = 200, description = "The updated webhook", body = Webhook),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn update_webhook(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<UpdateWebhook>,
+) -> Result<Json<Webhook>, AppError> {
+    if let Some(url) = &payload.url {
+        validate_url(&state, url).await?;
+    }
+
+    let mut tx = state.tenant(&ctx).await?;
+    authorize(&mut tx, &ctx).await?;
+
+    let events = payload.events.map(|e| dedupe(e).to_strings());
+
+    let webhook = sqlx::query_as!(
+        Webhook,
+        r#"
+        UPDATE webhooks
+        SET url = COALESCE($2, url),
+            events = COALESCE($3, events),
+            active = COALESCE($4, active),
+            updated_at = $5
+        WHERE id = $1
+        RETURNING id, url, events, active, created_by, created_at, updated_at
+        "#,
+        id,
+        payload.url,
+        events.as_deref(),
+        payload.active,
+        Utc::now()
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err("update webhook"))?
+    .ok_or(AppError::NotFound)?;
+
+    tx.commit().await.map_err(db_err("update webhook"))?;
+
+    Ok(Json(webhook))
+}
+
+/// Deletes a webhook along with its delivery log.
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = 204, description = "The webhook was deleted"),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn delete_webhook(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+    authorize(&mut tx, &ctx).await?;
+
+    let result = sqlx::query!("DELETE FROM webhooks WHERE id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err("delete webhook"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tx.commit().await.map_err(db_err("delete webhook"))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists the most recent deliveries of a webhook, newest first.
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = 200, description = "Recent deliveries", body = Vec<WebhookDelivery>),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn list_deliveries(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+    authorize(&mut tx, &ctx).await?;
+
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM webhooks WHERE id = $1) AS "exists!""#,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err("list deliveries"))?;
+    if !exists {
+        return Err(AppError::NotFound);
+    }
+
+    let deliveries = sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        SELECT id, webhook_id, event_id, event, payload, status, attempts, response_status,
+               last_error, next_attempt_at, created_at, delivered_at
+        FROM webhook_deliveries
+        WHERE webhook_id = $1
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+        id,
+        DELIVERY_LOG_LIMIT
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_err("list deliveries"))?;
+
+    Ok(Json(deliveries))
+}
+
+/// Queues a new delivery of the same event, regardless of whether the
+/// original succeeded. Receivers can deduplicate on the payload's `id`.
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}/deliveries/{delivery_id}/redeliver",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+        ("delivery_id" = Uuid, Path, description = "Delivery ID"),
+    ),
+    responses(
+        (status = 201, description = "The new delivery", body = WebhookDelivery),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn redeliver(
+    Path((id, delivery_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<(StatusCode, Json<WebhookDelivery>), AppError> {
+    let mut tx = state.tenant(&ctx).await?;
+    authorize(&mut tx, &ctx).await?;
+    let now = Utc::now();
+
+    let delivery = sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        INSERT INTO webhook_deliveries (id, webhook_id, event_id, event, payload, status, next_attempt_at, created_at)
+        SELECT $3, webhook_id, event_id, event, payload, 'Pending', $4, $4
+        FROM webhook_deliveries
+        WHERE id = $2 AND webhook_id = $1
+        RETURNING id, webhook_id, event_id, event, payload, status, attempts, response_status,
+                  last_error, next_attempt_at, created_at, delivered_at
+        "#,
+        id,
+        delivery_id,
+        Uuid::new_v4(),
+        now
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_err("redeliver webhook"))?
+    .ok_or(AppError::NotFound)?;
+
+    tx.commit().await.map_err(db_err("redeliver webhook"))?;
+
+    Ok((StatusCode::ACCEPTED, Json(delivery)))
+}
@@ -0,0 +1,286 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    auth::AuthContext,
+    error::{AppError, ErrorResponse},
+    models::workspace::{Workspace, WorkspaceMember, WorkspaceRole},
+    permissions,
+    state::AppState,
+    validator::ValidatedJson,
+};
+
+#[derive(serde::Deserialize, Validate, utoipa::ToSchema)]
+pub struct CreateWorkspace {
+    #[validate(length(min = 1, max = 200, message = "name must be 1-200 characters"))]
+    pub name: String,
+}
+
+#[derive(serde::Deserialize, Validate, utoipa::ToSchema)]
+pub struct AddMember {
+    #[validate(email(message = "email must be a valid email address"))]
+    pub email: String,
+    pub role: WorkspaceRole,
+}
+
+fn db_err(context: &'static str) -> impl Fn(sqlx::Error) -> AppError {
+    move |e| {
+        tracing::error!("Failed to {}: {:?}", context, e);
+        AppError::Internal(format!("failed to {context}"))
+    }
+}
+
+/// Lists the workspaces the caller belongs to. The bootstrap token sees every
+/// workspace; other service-account tokens only see their own.
+#[utoipa::path(
+    get,
+    path = "/workspaces",
+    tag = "workspaces",
+    responses(
+        (status = 200, description = "Workspaces", body = Vec<Workspace>),
+    ),
+)]
+pub async fn list_workspaces(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<Workspace>>, AppError> {
+    let workspaces = sqlx::query_as!(
+        Workspace,
+        r#"
+        SELECT w.id, w.name, COALESCE(m.role, 'Admin') AS "role!", w.created_at
+        FROM workspaces w
+        LEFT JOIN workspace_members m ON m.workspace_id = w.id AND m.user_id = $1
+        WHERE CASE
+            WHEN $1::uuid IS NOT NULL THEN m.user_id IS NOT NULL
+            WHEN $2::uuid IS NOT NULL THEN w.id = $3
+            ELSE TRUE
+        END
+        ORDER BY w.created_at
+        "#,
+        ctx.user_id,
+        ctx.token_id,
+        ctx.workspace_id
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_err("list workspaces"))?;
+
+    Ok(Json(workspaces))
+}
+
+/// Creates a workspace. The calling user becomes its first admin.
+#[utoipa::path(
+    post,
+    path = "/workspaces",
+    tag = "workspaces",
+    request_body = CreateWorkspace,
+    responses(
+        (status = 201, description = "The created workspace", body = Workspace),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Only users can create workspaces", body = ErrorResponse),
+    ),
+)]
+pub async fn create_workspace(
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<CreateWorkspace>,
+) -> Result<(StatusCode, Json<Workspace>), AppError> {
+    if ctx.token_id.is_some() && ctx.user_id.is_none() {
+        return Err(AppError::Forbidden);
+    }
+
+    let now = Utc::now();
+    let mut tx = state
+        .pool
+        .begin()
+        .await
+        .map_err(db_err("create workspace"))?;
+
+    let workspace = sqlx::query_as!(
+        Workspace,
+        r#"
+        INSERT INTO workspaces (id, name, created_at)
+        VALUES ($1, $2, $3)
+        RETURNING id, name, 'Admin' AS "role!", created_at
+        "#,
+        Uuid::new_v4(),
+        payload.name,
+        now
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_err("create workspace"))?;
+
+    if let Some(user_id) = ctx.user_id {
+        sqlx::query!(
+            "INSERT INTO workspace_members (workspace_id, user_id, role, created_at) VALUES ($1, $2, 'Admin', $3)",
+            workspace.id,
+            user_id,
+            now
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db_err("create workspace"))?;
+    }
+
+    tx.commit().await.map_err(db_err("create workspace"))?;
+
+    Ok((StatusCode::CREATED, Json(workspace)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/workspaces/{id}/members",
+    tag = "workspaces",
+    params(
+        ("id" = Uuid, Path, description = "Workspace ID"),
+    ),
+    responses(
+        (status = 200, description = "Members", body = Vec<WorkspaceMember>),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn list_members(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<Json<Vec<WorkspaceMember>>, AppError> {
+    permissions::authorize_workspace(&state.pool, &ctx, id, WorkspaceRole::Member).await?;
+
+    let members = sqlx::query_as!(
+        WorkspaceMember,
+        r#"
+        SELECT u.id AS user_id, u.email, u.display_name, m.role
+        FROM workspace_members m JOIN users u ON u.id = m.user_id
+        WHERE m.workspace_id = $1
+        ORDER BY m.created_at
+        "#,
+        id
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(db_err("list workspace members"))?;
+
+    Ok(Json(members))
+}
+
+/// Adds an existing user to the workspace, or changes their role if they
+/// already belong to it.
+#[utoipa::path(
+    post,
+    path = "/workspaces/{id}/members",
+    tag = "workspaces",
+    request_body = AddMember,
+    params(
+        ("id" = Uuid, Path, description = "Workspace ID"),
+    ),
+    responses(
+        (status = 201, description = "The member", body = WorkspaceMember),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Not an admin of the workspace", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn add_member(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+    ValidatedJson(payload): ValidatedJson<AddMember>,
+) -> Result<(StatusCode, Json<WorkspaceMember>), AppError> {
+    permissions::authorize_workspace(&state.pool, &ctx, id, WorkspaceRole::Admin).await?;
+
+    let member = sqlx::query_as!(
+        WorkspaceMember,
+        r#"
+        WITH added AS (
+            INSERT INTO workspace_members (workspace_id, user_id, role, created_at)
+            SELECT $1, id, $3, $4 FROM users WHERE email = $2
+            ON CONFLICT (workspace_id, user_id) DO UPDATE SET role = EXCLUDED.role
+            RETURNING user_id, role
+        )
+        SELECT u.id AS user_id, u.email, u.display_name, added.role
+        FROM added JOIN users u ON u.id = added.user_id
+        "#,
+        id,
+        payload.email.trim().to_lowercase(),
+        payload.role.to_string(),
+        Utc::now()
+    )
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(db_err("add workspace member"))?
+    .ok_or_else(|| AppError::invalid_field("email", "no user with this email"))?;
+
+    Ok((StatusCode::CREATED, Json(member)))
+}
+
+/// Admins can remove anyone; every member can leave. The last admin cannot
+/// leave, so a workspace always has someone who can manage it.
+#[utoipa::path(
+    delete,
+    path = "/workspaces/{id}/members/{user_id}",
+    tag = "workspaces",
+    params(
+        ("id" = Uuid, Path, description = "Workspace ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 204, description = "The member was removed"),
+        (status = 400, description = "The last admin cannot leave", body = ErrorResponse),
+        (status = 403, description = "Not an admin of the workspace", body = ErrorResponse),
+        (status = 404, description = "Not found or not visible to the caller", body = ErrorResponse),
+    ),
+)]
+pub async fn remove_member(
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    Extension(ctx): Extension<AuthContext>,
+) -> Result<StatusCode, AppError> {
+    let min = if ctx.user_id == Some(user_id) {
+        WorkspaceRole::Member
+    } else {
+        WorkspaceRole::Admin
+    };
+    permissions::authorize_workspace(&state.pool, &ctx, id, min).await?;
+
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM workspace_members
+        WHERE workspace_id = $1 AND user_id = $2
+          AND (role <> 'Admin' OR EXISTS (
+              SELECT 1 FROM workspace_members
+              WHERE workspace_id = $1 AND user_id <> $2 AND role = 'Admin'
+          ))
+        "#,
+        id,
+        user_id
+    )
+    .execute(&state.pool)
+    .await
+    .map_err(db_err("remove workspace member"))?;
+
+    if result.rows_affected() == 0 {
+        let is_member = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM workspace_members WHERE workspace_id = $1 AND user_id = $2) AS "exists!""#,
+            id,
+            user_id
+        )
+        .fetch_one(&state.pool)
+        .await
+        .map_err(db_err("remove workspace member"))?;
+
+        return Err(if is_member {
+            AppError::invalid_field("user_id", "the last admin cannot leave the workspace")
+        } else {
+            AppError::NotFound
+        });
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,659 @@
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use chrono::Utc;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use utoipa::{
+    PartialSchema, ToSchema,
+    openapi::{ObjectBuilder, RefOr, Schema, Type},
+};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::todo::{Priority, Todo, TodoStatus},
+};
+
+/// How far ahead of the server's clock a client timestamp may be. Anything
+/// further would win every later merge.
+pub const MAX_DRIFT_MS: i64 = 60_000;
+
+/// Hybrid logical clock timestamp: wall-clock milliseconds, a counter
+/// ordering events within the same millisecond, and the ID of the node that
+/// issued it, which breaks ties between devices.
+///
+/// The string form, `<millis>-<counter>-<node>` with fixed-width fields,
+/// sorts the same way as the timestamps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hlc {
+    pub millis: i64,
+    pub counter: u32,
+    pub node: u64,
+}
+
+impl Hlc {
+    /// Older than any real edit. Used for values that predate the CRDT.
+    pub const ZERO: Hlc = Hlc {
+        millis: 0,
+        counter: 0,
+        node: 0,
+    };
+}
+
+impl fmt::Display for Hlc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:013}-{:08x}-{:016x}",
+            self.millis, self.counter, self.node
+        )
+    }
+}
+
+impl FromStr for Hlc {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid HLC timestamp: {s}");
+        let mut parts = s.split('-');
+        let (Some(millis), Some(counter), Some(node), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        if millis.len() != 13 || counter.len() != 8 || node.len() != 16 {
+            return Err(invalid());
+        }
+
+        Ok(Hlc {
+            millis: millis.parse().map_err(|_| invalid())?,
+            counter: u32::from_str_radix(counter, 16).map_err(|_| invalid())?,
+            node: u64::from_str_radix(node, 16).map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl Serialize for Hlc {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Hlc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl PartialSchema for Hlc {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .description(Some(
+                "Hybrid logical clock timestamp, `<millis>-<counter>-<node>`.",
+            ))
+            .examples([serde_json::json!("1760000000000-00000000-00a3f29c41d0e2b7")])
+            .into()
+    }
+}
+
+impl ToSchema for Hlc {}
+
+/// The server's hybrid logical clock. Timestamps it issues are unique and
+/// later than every timestamp it has issued or observed.
+#[derive(Clone)]
+pub struct HlcClock {
+    node: u64,
+    last: Arc<Mutex<Hlc>>,
+}
+
+impl Default for HlcClock {
+    fn default() -> Self {
+        Self {
+            node: rand::random(),
+            last: Arc::new(Mutex::new(Hlc::ZERO)),
+        }
+    }
+}
+
+impl HlcClock {
+    pub fn now(&self) -> Hlc {
+        let wall = Utc::now().timestamp_millis();
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        *last = if wall > last.millis {
+            Hlc {
+                millis: wall,
+                counter: 0,
+                node: self.node,
+            }
+        } else {
+            Hlc {
+                millis: last.millis,
+                counter: last.counter + 1,
+                node: self.node,
+            }
+        };
+        *last
+    }
+
+    /// Moves the clock past a timestamp received from a client, so that
+    /// server edits made afterwards win over it.
+    pub fn observe(&self, remote: Hlc) {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        if (remote.millis, remote.counter) > (last.millis, last.counter) {
+            last.millis = remote.millis;
+            last.counter = remote.counter;
+        }
+    }
+}
+
+/// Identifies one character of a text: the timestamp of the operation that
+/// inserted it and its offset within the inserted run. Written as
+/// `<hlc>.<offset>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CharId {
+    pub hlc: Hlc,
+    pub offset: u32,
+}
+
+impl fmt::Display for CharId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.hlc, self.offset)
+    }
+}
+
+impl FromStr for CharId {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (hlc, offset) = s
+            .rsplit_once('.')
+            .ok_or_else(|| format!("Invalid character ID: {s}"))?;
+        Ok(CharId {
+            hlc: hlc.parse()?,
+            offset: offset
+                .parse()
+                .map_err(|_| format!("Invalid character ID: {s}"))?,
+        })
+    }
+}
+
+impl Serialize for CharId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CharId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl PartialSchema for CharId {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .description(Some("Character ID, `<hlc>.<offset>`."))
+            .into()
+    }
+}
+
+impl ToSchema for CharId {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TextChar {
+    pub id: CharId,
+    pub value: char,
+    /// Deleted characters stay in place so that concurrent inserts next to
+    /// them still find their position.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+}
+
+/// Replicated text (an RGA sequence). Each character is inserted after a
+/// known character; concurrent inserts at the same spot are ordered by
+/// their IDs, newest first, so every replica ends up with the same text.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TextDoc {
+    chars: Vec<TextChar>,
+}
+
+impl TextDoc {
+    /// A text that predates the CRDT, as if inserted at `Hlc::ZERO`.
+    pub fn from_text(text: &str) -> Self {
+        let chars = text
+            .chars()
+            .enumerate()
+            .map(|(i, value)| TextChar {
+                id: CharId {
+                    hlc: Hlc::ZERO,
+                    offset: i as u32,
+                },
+                value,
+                deleted: false,
+            })
+            .collect();
+        Self { chars }
+    }
+
+    pub fn text(&self) -> String {
+        self.chars
+            .iter()
+            .filter(|c| !c.deleted)
+            .map(|c| c.value)
+            .collect()
+    }
+
+    /// IDs of the characters that have not been deleted, in order.
+    pub fn visible_ids(&self) -> Vec<CharId> {
+        self.chars
+            .iter()
+            .filter(|c| !c.deleted)
+            .map(|c| c.id)
+            .collect()
+    }
+
+    fn position(&self, id: CharId) -> Option<usize> {
+        self.chars.iter().position(|c| c.id == id)
+    }
+
+    /// Inserts `text` after the character `after`, or at the start. The
+    /// characters get the IDs `hlc.0`, `hlc.1`, ... Inserting the same run
+    /// twice has no effect.
+    pub fn insert(&mut self, after: Option<CharId>, hlc: Hlc, text: &str) -> Result<(), AppError> {
+        let mut after = after;
+        for (offset, value) in text.chars().enumerate() {
+            let id = CharId {
+                hlc,
+                offset: offset as u32,
+            };
+            if self.position(id).is_none() {
+                let mut index = match after {
+                    Some(after) => {
+                        self.position(after).ok_or_else(|| {
+                            AppError::invalid_field("ops", &format!("unknown character ID {after}"))
+                        })? + 1
+                    }
+                    None => 0,
+                };
+                // Skip inserts made concurrently at the same spot with newer
+                // IDs, along with everything inserted after them.
+                while index < self.chars.len() && self.chars[index].id > id {
+                    index += 1;
+                }
+                self.chars.insert(
+                    index,
+                    TextChar {
+                        id,
+                        value,
+                        deleted: false,
+                    },
+                );
+            }
+            after = Some(id);
+        }
+        Ok(())
+    }
+
+    /// Marks characters as deleted. Unknown IDs are ignored.
+    pub fn delete(&mut self, ids: &[CharId]) {
+        for c in &mut self.chars {
+            if ids.contains(&c.id) {
+                c.deleted = true;
+            }
+        }
+    }
+}
+
+/// A new value for one last-write-wins field.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "field", content = "value", rename_all = "snake_case")]
+pub enum FieldValue {
+    Title(String),
+    Status(TodoStatus),
+    Priority(Priority),
+    ProjectId(Option<Uuid>),
+}
+
+/// An edit to a todo. Every operation carries a unique timestamp; applying
+/// the same operations in any order gives the same todo.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Op {
+    pub hlc: Hlc,
+    #[serde(flatten)]
+    pub kind: OpKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OpKind {
+    /// Sets a field, unless it was set by a later operation.
+    Set(FieldValue),
+    /// Inserts text into the description after the given character, or at
+    /// the start.
+    InsertText { after: Option<CharId>, text: String },
+    /// Deletes characters from the description.
+    DeleteText { ids: Vec<CharId> },
+}
+
+impl Op {
+    /// Checks an operation received from a client against the server's clock.
+    pub fn validate(&self, clock: &HlcClock) -> Result<(), AppError> {
+        if self.hlc.millis > Utc::now().timestamp_millis() + MAX_DRIFT_MS {
+            return Err(AppError::invalid_field(
+                "ops",
+                &format!("timestamp {} is too far in the future", self.hlc),
+            ));
+        }
+        match &self.kind {
+            OpKind::Set(FieldValue::Title(title)) if title.is_empty() => {
+                return Err(AppError::invalid_field("title", "title cannot be empty"));
+            }
+            OpKind::InsertText { text, .. } if text.is_empty() => {
+                return Err(AppError::invalid_field("ops", "text cannot be empty"));
+            }
+            // A character can only be inserted after one that already
+            // existed, so it must have an older timestamp.
+            OpKind::InsertText {
+                after: Some(after), ..
+            } if after.hlc >= self.hlc => {
+                return Err(AppError::invalid_field(
+                    "ops",
+                    "text must be inserted after an older character",
+                ));
+            }
+            _ => {}
+        }
+
+        clock.observe(self.hlc);
+        Ok(())
+    }
+}
+
+/// Timestamp of the last write to each last-write-wins field.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct FieldClocks {
+    pub title: Hlc,
+    pub status: Hlc,
+    pub priority: Hlc,
+    pub project_id: Hlc,
+}
+
+/// Merge state of a todo: field timestamps and the description's characters,
+/// including deleted ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct CrdtState {
+    pub clocks: FieldClocks,
+    #[schema(value_type = Vec<TextChar>)]
+    pub description: TextDoc,
+}
+
+impl CrdtState {
+    /// State of a todo that has never been edited through operations.
+    pub fn from_todo(todo: &Todo) -> Self {
+        Self {
+            clocks: FieldClocks::default(),
+            description: TextDoc::from_text(todo.description.as_deref().unwrap_or_default()),
+        }
+    }
+
+    pub fn apply(&mut self, todo: &mut Todo, op: &Op) -> Result<(), AppError> {
+        match &op.kind {
+            OpKind::Set(value) => {
+                let clock = match value {
+                    FieldValue::Title(_) => &mut self.clocks.title,
+                    FieldValue::Status(_) => &mut self.clocks.status,
+                    FieldValue::Priority(_) => &mut self.clocks.priority,
+                    FieldValue::ProjectId(_) => &mut self.clocks.project_id,
+                };
+                if op.hlc <= *clock {
+                    return Ok(());
+                }
+                *clock = op.hlc;
+                match value.clone() {
+                    FieldValue::Title(title) => todo.title = title,
+                    FieldValue::Status(status) => todo.status = status,
+                    FieldValue::Priority(priority) => todo.priority = priority,
+                    FieldValue::ProjectId(project_id) => todo.project_id = project_id,
+                }
+            }
+            OpKind::InsertText { after, text } => {
+                self.description.insert(*after, op.hlc, text)?;
+                todo.description = Some(self.description.text()).filter(|d| !d.is_empty());
+            }
+            OpKind::DeleteText { ids } => {
+                self.description.delete(ids);
+                todo.description = Some(self.description.text()).filter(|d| !d.is_empty());
+            }
+        }
+        Ok(())
+    }
+
+    /// Operations replacing the whole description with `text`, as done by
+    /// the REST API.
+    pub fn replace_description(&self, clock: &HlcClock, text: &str) -> Vec<Op> {
+        let mut ops = Vec::new();
+        if self.description.text() == text {
+            return ops;
+        }
+        let ids = self.description.visible_ids();
+        if !ids.is_empty() {
+            ops.push(Op {
+                hlc: clock.now(),
+                kind: OpKind::DeleteText { ids },
+            });
+        }
+        if !text.is_empty() {
+            ops.push(Op {
+                hlc: clock.now(),
+                kind: OpKind::InsertText {
+                    after: None,
+                    text: text.to_string(),
+                },
+            });
+        }
+        ops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::TodoSource;
+
+    fn hlc(millis: i64, node: u64) -> Hlc {
+        Hlc {
+            millis,
+            counter: 0,
+            node,
+        }
+    }
+
+    fn char_id(hlc: Hlc, offset: u32) -> CharId {
+        CharId { hlc, offset }
+    }
+
+    fn set(hlc: Hlc, value: FieldValue) -> Op {
+        Op {
+            hlc,
+            kind: OpKind::Set(value),
+        }
+    }
+
+    fn insert(hlc: Hlc, after: Option<CharId>, text: &str) -> Op {
+        Op {
+            hlc,
+            kind: OpKind::InsertText {
+                after,
+                text: text.into(),
+            },
+        }
+    }
+
+    fn todo() -> Todo {
+        let now = chrono::DateTime::UNIX_EPOCH;
+        Todo {
+            id: Uuid::nil(),
+            title: "Buy milk".into(),
+            description: Some("ac".into()),
+            status: TodoStatus::Todo,
+            priority: Priority::Medium,
+            source: TodoSource::Manual,
+            owner_id: None,
+            project_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Applies `ops` in order to a fresh replica of [`todo`].
+    fn replica(ops: &[&Op]) -> (Todo, CrdtState) {
+        let mut todo = todo();
+        let mut state = CrdtState::from_todo(&todo);
+        for op in ops {
+            state.apply(&mut todo, op).unwrap();
+        }
+        (todo, state)
+    }
+
+    #[test]
+    fn clock_only_moves_forward() {
+        let clock = HlcClock::default();
+        let mut last = clock.now();
+        for _ in 0..1000 {
+            let next = clock.now();
+            assert!(next > last);
+            last = next;
+        }
+
+        let remote = Hlc {
+            millis: last.millis + 5_000,
+            counter: 7,
+            node: 0,
+        };
+        clock.observe(remote);
+        assert!(clock.now() > remote);
+    }
+
+    #[test]
+    fn equal_timestamps_are_broken_by_node() {
+        let a = set(hlc(10, 1), FieldValue::Title("From A".into()));
+        let b = set(hlc(10, 2), FieldValue::Title("From B".into()));
+
+        let (ab, _) = replica(&[&a, &b]);
+        let (ba, _) = replica(&[&b, &a]);
+        assert_eq!(ab.title, "From B");
+        assert_eq!(ba.title, "From B");
+    }
+
+    #[test]
+    fn concurrent_inserts_at_the_same_spot_are_ordered_by_id() {
+        let a = char_id(Hlc::ZERO, 0);
+        let from_one = insert(hlc(10, 1), Some(a), "b");
+        let from_two = insert(hlc(10, 2), Some(a), "xy");
+
+        let (first, _) = replica(&[&from_one, &from_two]);
+        let (second, _) = replica(&[&from_two, &from_one]);
+        // The newer insert comes first, whichever arrived first.
+        assert_eq!(first.description.as_deref(), Some("axybc"));
+        assert_eq!(second.description.as_deref(), Some("axybc"));
+    }
+
+    #[test]
+    fn replicas_converge_whatever_the_order() {
+        let inserted = insert(hlc(10, 1), Some(char_id(Hlc::ZERO, 1)), "ke");
+        let ops = [
+            set(hlc(20, 1), FieldValue::Title("Buy oat milk".into())),
+            set(hlc(15, 2), FieldValue::Title("Buy soy milk".into())),
+            set(hlc(12, 2), FieldValue::Status(TodoStatus::Done)),
+            set(hlc(11, 1), FieldValue::Priority(Priority::High)),
+            inserted.clone(),
+            Op {
+                hlc: hlc(30, 2),
+                kind: OpKind::DeleteText {
+                    ids: vec![char_id(Hlc::ZERO, 0), char_id(hlc(10, 1), 1)],
+                },
+            },
+            insert(hlc(31, 1), Some(char_id(hlc(10, 1), 0)), "!"),
+        ];
+
+        let in_order: Vec<&Op> = ops.iter().collect();
+        // Text operations still follow the inserts they depend on.
+        let shuffled: Vec<&Op> = [4, 6, 1, 3, 5, 0, 2].iter().map(|&i| &ops[i]).collect();
+        let (a, state_a) = replica(&in_order);
+        let (b, state_b) = replica(&shuffled);
+
+        assert_eq!(a, b);
+        assert_eq!(a.title, "Buy oat milk");
+        assert_eq!(a.status, TodoStatus::Done);
+        assert_eq!(a.priority, Priority::High);
+        assert_eq!(a.description.as_deref(), Some("ck!"));
+        assert_eq!(
+            serde_json::to_value(&state_a).unwrap(),
+            serde_json::to_value(&state_b).unwrap()
+        );
+    }
+
+    #[test]
+    fn replaying_an_op_changes_nothing() {
+        let ops = [
+            set(hlc(10, 1), FieldValue::Title("Buy oat milk".into())),
+            insert(hlc(11, 1), None, "x"),
+            Op {
+                hlc: hlc(12, 1),
+                kind: OpKind::DeleteText {
+                    ids: vec![char_id(Hlc::ZERO, 1)],
+                },
+            },
+        ];
+        let once: Vec<&Op> = ops.iter().collect();
+        let twice: Vec<&Op> = ops.iter().chain(&ops).collect();
+
+        let (a, state_a) = replica(&once);
+        let (b, state_b) = replica(&twice);
+        assert_eq!(a, b);
+        assert_eq!(a.description.as_deref(), Some("xa"));
+        assert_eq!(
+            serde_json::to_value(&state_a).unwrap(),
+            serde_json::to_value(&state_b).unwrap()
+        );
+    }
+
+    #[test]
+    fn validate_rejects_impossible_ops() {
+        let clock = HlcClock::default();
+        let now = clock.now();
+        let later = Hlc {
+            millis: now.millis + 1,
+            ..now
+        };
+
+        let future = Hlc {
+            millis: now.millis + MAX_DRIFT_MS + 10_000,
+            ..now
+        };
+        let rejected = [
+            set(future, FieldValue::Priority(Priority::Low)),
+            set(later, FieldValue::Title(String::new())),
+            insert(later, None, ""),
+            insert(later, Some(char_id(later, 0)), "x"),
+        ];
+        for op in &rejected {
+            assert!(
+                matches!(op.validate(&clock), Err(AppError::InvalidInput(_))),
+                "{op:?} was accepted"
+            );
+        }
+
+        let accepted = insert(later, Some(char_id(now, 0)), "x");
+        accepted.validate(&clock).unwrap();
+        assert!(clock.now() > later);
+    }
+}
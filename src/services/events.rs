@@ -0,0 +1,197 @@
+use sqlx::{PgConnection, PgPool, postgres::PgListener};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{
+        event::{TodoEvent, TodoEventKind},
+        todo::Todo,
+    },
+};
+
+/// Postgres notification channel carrying the workspace ID of each new event.
+const CHANNEL: &str = "todo_events";
+
+/// Fan-out of committed todo events to the streams of this process. Only the
+/// workspace is broadcast; streams read the events themselves from the log.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Uuid>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(256);
+        Self { sender }
+    }
+}
+
+impl EventBus {
+    pub fn subscribe(&self) -> broadcast::Receiver<Uuid> {
+        self.sender.subscribe()
+    }
+
+    /// Forwards notifications from Postgres, so events committed by any
+    /// server instance reach every stream.
+    pub fn spawn_listener(&self, pool: PgPool) -> tokio::task::JoinHandle<()> {
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let mut listener = match PgListener::connect_with(&pool).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::error!("Failed to connect event listener: {:?}", e);
+                    return;
+                }
+            };
+            if let Err(e) = listener.listen(CHANNEL).await {
+                tracing::error!("Failed to listen for todo events: {:?}", e);
+                return;
+            }
+
+            loop {
+                // `recv` reconnects on its own after a lost connection.
+                match listener.recv().await {
+                    Ok(notification) => {
+                        if let Ok(workspace_id) = notification.payload().parse() {
+                            // No receivers simply means nobody is streaming.
+                            let _ = sender.send(workspace_id);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Todo event listener error: {:?}", e);
+                        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Serializes writers to the current workspace's logs until the transaction
+/// ends. Sequence numbers are handed out when rows are inserted, not when
+/// they commit; holding the lock while inserting makes rows become visible
+/// in `seq` order, so a reader never skips past an uncommitted one.
+pub async fn lock_workspace(conn: &mut PgConnection) -> Result<(), AppError> {
+    sqlx::query!(
+        "SELECT pg_advisory_xact_lock(hashtextextended(current_setting('app.workspace_id'), 0))"
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to lock workspace: {:?}", e);
+        AppError::Internal("failed to lock workspace".into())
+    })?;
+    Ok(())
+}
+
+/// Appends a change to the event log. Must be called on the request's tenant
+/// transaction, before the row is deleted for `Deleted`. Listeners are
+/// notified when the transaction commits.
+pub async fn record(
+    conn: &mut PgConnection,
+    kind: TodoEventKind,
+    todo: &Todo,
+) -> Result<(), AppError> {
+    let db_err = |e: sqlx::Error| {
+        tracing::error!("Failed to record todo event: {:?}", e);
+        AppError::Internal("failed to record todo event".into())
+    };
+    let snapshot = serde_json::to_value(todo).map_err(|e| {
+        tracing::error!("Failed to serialize todo event: {:?}", e);
+        AppError::Internal("failed to record todo event".into())
+    })?;
+
+    lock_workspace(conn).await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_events (kind, todo_id, project_id, status, todo, visible_to, created_at)
+        VALUES ($1, $2, $3, $4, $5, todo_viewers($2), now())
+        "#,
+        kind.to_string(),
+        todo.id,
+        todo.project_id,
+        todo.status.to_string(),
+        snapshot
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    sqlx::query!(
+        "SELECT pg_notify($1, current_setting('app.workspace_id'))",
+        CHANNEL
+    )
+    .execute(&mut *conn)
+    .await
+    .map_err(db_err)?;
+
+    Ok(())
+}
+
+/// An event read back from the log, with the fields streams filter on.
+pub struct LoggedEvent {
+    pub event: TodoEvent,
+    pub project_id: Option<Uuid>,
+    pub status: String,
+}
+
+fn read_err(e: sqlx::Error) -> AppError {
+    tracing::error!("Failed to read event log: {:?}", e);
+    AppError::Internal("failed to read events".into())
+}
+
+/// Sequence number of the newest event in the current workspace, or 0.
+pub async fn latest_seq(conn: &mut PgConnection) -> Result<i64, AppError> {
+    sqlx::query_scalar!(r#"SELECT COALESCE(MAX(seq), 0) AS "seq!" FROM todo_events"#)
+        .fetch_one(conn)
+        .await
+        .map_err(read_err)
+}
+
+/// Reads up to `limit` events after `after` and returns the position to
+/// continue from along with the events `user_id` may see. Events hidden from
+/// the user still advance the position.
+pub async fn read_after(
+    conn: &mut PgConnection,
+    user_id: Option<Uuid>,
+    after: i64,
+    limit: i64,
+) -> Result<(i64, Vec<LoggedEvent>), AppError> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT seq, kind, todo_id, project_id, status, todo, created_at,
+               ($2::uuid IS NULL OR $2 = ANY (visible_to)) AS "visible!"
+        FROM todo_events
+        WHERE seq > $1
+        ORDER BY seq
+        LIMIT $3
+        "#,
+        after,
+        user_id,
+        limit
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(read_err)?;
+
+    let next = rows.last().map_or(after, |r| r.seq);
+    let events = rows
+        .into_iter()
+        .filter(|r| r.visible)
+        .map(|r| LoggedEvent {
+            event: TodoEvent {
+                seq: r.seq,
+                kind: r.kind.into(),
+                todo_id: r.todo_id,
+                todo: r.todo,
+                created_at: r.created_at,
+            },
+            project_id: r.project_id,
+            status: r.status,
+        })
+        .collect();
+
+    Ok((next, events))
+}
@@ -1,32 +1,57 @@
-use crate::error::AppError;
+use crate::{config::GeminiConfig, error::AppError, telemetry};
 use base64::{Engine as _, engine::general_purpose};
 use serde_json::json;
-use std::env;
 
 #[derive(Clone)]
 pub struct GeminiService {
     api_key: String,
+    model: String,
     client: reqwest::Client,
 }
 
 impl GeminiService {
-    pub fn new() -> Result<Self, AppError> {
-        let api_key = env::var("GOOGLE_API_KEY")
-            .map_err(|_| AppError::Internal("GOOGLE_API_KEY not set".to_string()))?;
+    pub fn new(config: &GeminiConfig) -> Result<Self, AppError> {
+        let api_key = config.api_key.clone().ok_or_else(|| {
+            AppError::Internal("gemini.api_key (GOOGLE_API_KEY) is required".to_string())
+        })?;
         Ok(Self {
             api_key,
+            model: config.model.clone(),
             client: reqwest::Client::new(),
         })
     }
 
+    /// Fetches the configured model's metadata, which checks that the API is
+    /// reachable and accepts the key without spending any quota.
+    #[tracing::instrument(name = "gemini check", skip_all, fields(gen_ai.request.model = %self.model))]
+    pub async fn check(&self) -> Result<(), AppError> {
+        let url = format!(
+            "https://generativelanguage.googleapis.com/v1beta/models/{}?key={}",
+            self.model, self.api_key
+        );
+        telemetry::send(self.client.get(url))
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            // The URL contains the API key.
+            .map_err(|e| {
+                AppError::Internal(format!("Gemini API check failed: {}", e.without_url()))
+            })?;
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        name = "gemini suggest_tasks",
+        skip_all,
+        fields(gen_ai.request.model = %self.model, audio.bytes = audio_data.len())
+    )]
     pub async fn suggest_tasks(
         &self,
         audio_data: Vec<u8>,
         mime_type: &str,
     ) -> Result<Vec<crate::models::todo::SuggestedTodo>, AppError> {
         let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-flash:generateContent?key={}",
-            self.api_key
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
+            self.model, self.api_key
         );
 
         let base64_audio = general_purpose::STANDARD.encode(audio_data);
@@ -48,23 +73,25 @@ impl GeminiService {
             }
         });
 
-        let resp = self
-            .client
-            .post(url)
-            .json(&payload)
-            .send()
+        // Errors from reqwest would otherwise include the URL, and with it the
+        // API key.
+        let resp = telemetry::send(self.client.post(url).json(&payload))
             .await
-            .map_err(|e| AppError::Internal(format!("Failed to call Gemini API: {e}")))?;
+            .map_err(|e| {
+                AppError::Internal(format!("Failed to call Gemini API: {}", e.without_url()))
+            })?;
 
         if !resp.status().is_success() {
             let err_text = resp.text().await.unwrap_or_default();
             return Err(AppError::Internal(format!("Gemini API error: {err_text}")));
         }
 
-        let gemini_resp: serde_json::Value = resp
-            .json()
-            .await
-            .map_err(|e| AppError::Internal(format!("Failed to parse Gemini response: {e}")))?;
+        let gemini_resp: serde_json::Value = resp.json().await.map_err(|e| {
+            AppError::Internal(format!(
+                "Failed to parse Gemini response: {}",
+                e.without_url()
+            ))
+        })?;
 
         let text = gemini_resp["candidates"][0]["content"]["parts"][0]["text"]
             .as_str()
@@ -0,0 +1,210 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::http::{Method, StatusCode};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder, exponential_buckets,
+};
+
+use crate::models::todo::TodoStatus;
+
+/// Prometheus collectors for the whole server, exposed at `/metrics`. Every
+/// name gets the `ai_todo_` prefix.
+#[derive(Clone)]
+pub struct Metrics {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    db_connections: IntGaugeVec,
+    db_max_connections: IntGauge,
+    gemini_requests: IntCounterVec,
+    gemini_duration: Histogram,
+    audio_upload_bytes: Histogram,
+    todos_suggested: IntCounter,
+    todos_confirmed: IntCounter,
+    todos: IntGaugeVec,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("ai_todo".to_string()), None)
+            .expect("the prefix is a valid metric name");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests handled"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time taken to handle HTTP requests",
+            ),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let db_connections = IntGaugeVec::new(
+            Opts::new(
+                "db_pool_connections",
+                "Open database connections by whether they are idle or in use",
+            ),
+            &["state"],
+        )
+        .unwrap();
+        let db_max_connections = IntGauge::new(
+            "db_pool_max_connections",
+            "Connections the database pool may open",
+        )
+        .unwrap();
+        let gemini_requests = IntCounterVec::new(
+            Opts::new("gemini_requests_total", "Calls to the Gemini API"),
+            &["outcome"],
+        )
+        .unwrap();
+        let gemini_duration = Histogram::with_opts(
+            HistogramOpts::new(
+                "gemini_request_duration_seconds",
+                "Time taken by calls to the Gemini API",
+            )
+            // 0.25s to 64s.
+            .buckets(exponential_buckets(0.25, 2.0, 9).unwrap()),
+        )
+        .unwrap();
+        let audio_upload_bytes = Histogram::with_opts(
+            HistogramOpts::new("audio_upload_bytes", "Size of uploaded recordings")
+                // 16 KiB to 256 MiB.
+                .buckets(exponential_buckets(16384.0, 4.0, 8).unwrap()),
+        )
+        .unwrap();
+        let todos_suggested = IntCounter::new(
+            "todos_suggested_total",
+            "Todos suggested by the model from recordings",
+        )
+        .unwrap();
+        let todos_confirmed = IntCounter::new(
+            "todos_confirmed_total",
+            "Suggested todos that were confirmed and created",
+        )
+        .unwrap();
+        let todos = IntGaugeVec::new(
+            Opts::new("todos", "Todos in every workspace by status"),
+            &["status"],
+        )
+        .unwrap();
+
+        // Registration only fails on duplicate names, which are fixed above.
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_duration.clone()),
+            Box::new(db_connections.clone()),
+            Box::new(db_max_connections.clone()),
+            Box::new(gemini_requests.clone()),
+            Box::new(gemini_duration.clone()),
+            Box::new(audio_upload_bytes.clone()),
+            Box::new(todos_suggested.clone()),
+            Box::new(todos_confirmed.clone()),
+            Box::new(todos.clone()),
+        ] {
+            registry.register(collector).unwrap();
+        }
+
+        Self {
+            inner: Arc::new(Inner {
+                registry,
+                http_requests,
+                http_duration,
+                db_connections,
+                db_max_connections,
+                gemini_requests,
+                gemini_duration,
+                audio_upload_bytes,
+                todos_suggested,
+                todos_confirmed,
+                todos,
+            }),
+        }
+    }
+
+    /// Records a handled request. `route` is the matched route pattern, such
+    /// as `/v1/todos/:id`, so that IDs do not each get their own series.
+    pub fn observe_request(
+        &self,
+        method: &Method,
+        route: &str,
+        status: StatusCode,
+        elapsed: Duration,
+    ) {
+        let labels = [method.as_str(), route, status.as_str()];
+        self.inner.http_requests.with_label_values(&labels).inc();
+        self.inner
+            .http_duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_gemini(&self, succeeded: bool, elapsed: Duration) {
+        let outcome = if succeeded { "success" } else { "error" };
+        self.inner
+            .gemini_requests
+            .with_label_values(&[outcome])
+            .inc();
+        self.inner.gemini_duration.observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_audio_upload(&self, bytes: usize) {
+        self.inner.audio_upload_bytes.observe(bytes as f64);
+    }
+
+    pub fn add_suggested(&self, count: usize) {
+        self.inner.todos_suggested.inc_by(count as u64);
+    }
+
+    pub fn add_confirmed(&self, count: usize) {
+        self.inner.todos_confirmed.inc_by(count as u64);
+    }
+
+    pub fn set_pool<DB: sqlx::Database>(&self, pool: &sqlx::Pool<DB>) {
+        let open = i64::from(pool.size());
+        let idle = pool.num_idle() as i64;
+        let connections = &self.inner.db_connections;
+        connections.with_label_values(&["idle"]).set(idle);
+        connections.with_label_values(&["in_use"]).set(open - idle);
+        self.inner
+            .db_max_connections
+            .set(i64::from(pool.options().get_max_connections()));
+    }
+
+    /// Replaces the todo counts. Statuses missing from `counts` are set to 0.
+    pub fn set_todos(&self, counts: &[(String, i64)]) {
+        for status in [TodoStatus::Todo, TodoStatus::Doing, TodoStatus::Done] {
+            let status = status.to_string();
+            let count = counts
+                .iter()
+                .filter(|(s, _)| *s == status)
+                .map(|(_, count)| count)
+                .sum();
+            self.inner.todos.with_label_values(&[&status]).set(count);
+        }
+    }
+
+    /// Every metric in the Prometheus text format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        // Encoding into a Vec only fails on invalid metrics, which the
+        // collectors above never produce.
+        TextEncoder::new()
+            .encode(&self.inner.registry.gather(), &mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
@@ -1 +1,9 @@
+pub mod crdt;
+pub mod events;
 pub mod gemini;
+pub mod metrics;
+pub mod oidc;
+pub mod rate_limit;
+pub mod realtime;
+pub mod totp;
+pub mod webhooks;
@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation,
+    jwk::{Jwk, JwkSet},
+};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use crate::{config::OidcConfig, error::AppError, telemetry};
+
+/// Subset of the discovery document (`/.well-known/openid-configuration`)
+/// that the authorization-code flow needs.
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Claims we rely on from a validated ID token.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+/// Values generated for a single login attempt. `state` and `nonce` are
+/// echoed back by the provider; `code_verifier` is the PKCE secret.
+pub struct LoginChallenge {
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+impl LoginChallenge {
+    pub fn generate() -> Self {
+        Self {
+            state: random_urlsafe(),
+            nonce: random_urlsafe(),
+            code_verifier: random_urlsafe(),
+        }
+    }
+
+    fn code_challenge(&self) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(self.code_verifier.as_bytes()))
+    }
+}
+
+fn random_urlsafe() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Asymmetric algorithms accepted for ID token signatures. HMAC and `none`
+/// are deliberately excluded.
+const ALLOWED_ALGORITHMS: [Algorithm; 7] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+];
+
+#[derive(Clone)]
+pub struct OidcService {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    issuer_url: String,
+    client_id: String,
+    client_secret: Option<String>,
+    redirect_url: String,
+    client: reqwest::Client,
+    metadata: RwLock<Option<ProviderMetadata>>,
+    jwks: RwLock<JwkSet>,
+}
+
+impl OidcService {
+    /// Builds the service, or returns `None` when `issuer_url` is unset,
+    /// i.e. SSO is disabled. [`Config::load`](crate::config::Config::load)
+    /// has already checked that the other required settings are present.
+    pub fn new(config: &OidcConfig) -> Option<Self> {
+        let issuer_url = config.issuer_url.as_deref()?;
+        Some(Self {
+            inner: Arc::new(Inner {
+                issuer_url: issuer_url.trim_end_matches('/').to_string(),
+                client_id: config.client_id.clone()?,
+                client_secret: config.client_secret.clone(),
+                redirect_url: config.redirect_url.clone()?,
+                client: reqwest::Client::new(),
+                metadata: RwLock::new(None),
+                jwks: RwLock::new(JwkSet { keys: Vec::new() }),
+            }),
+        })
+    }
+
+    /// Fetches the discovery document, bypassing the cache, to check that
+    /// the provider is reachable.
+    pub async fn check(&self) -> Result<(), AppError> {
+        let url = format!("{}/.well-known/openid-configuration", self.inner.issuer_url);
+        telemetry::send(self.inner.client.get(url))
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| AppError::Internal(format!("Failed to fetch OIDC discovery: {e}")))?;
+        Ok(())
+    }
+
+    async fn metadata(&self) -> Result<ProviderMetadata, AppError> {
+        if let Some(metadata) = self.inner.metadata.read().await.as_ref() {
+            return Ok(metadata.clone());
+        }
+
+        let url = format!("{}/.well-known/openid-configuration", self.inner.issuer_url);
+        let metadata: ProviderMetadata = telemetry::send(self.inner.client.get(url))
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| AppError::Internal(format!("Failed to fetch OIDC discovery: {e}")))?
+            .json()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to parse OIDC discovery: {e}")))?;
+
+        if metadata.issuer.trim_end_matches('/') != self.inner.issuer_url {
+            return Err(AppError::Internal(format!(
+                "OIDC discovery issuer mismatch: {}",
+                metadata.issuer
+            )));
+        }
+
+        *self.inner.metadata.write().await = Some(metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Finds the signing key for `kid`, refetching the JWKS once if the
+    /// provider has rotated keys since we last looked.
+    async fn signing_key(&self, kid: Option<&str>) -> Result<Jwk, AppError> {
+        let find = |set: &JwkSet| match kid {
+            Some(kid) => set.find(kid).cloned(),
+            None if set.keys.len() == 1 => set.keys.first().cloned(),
+            None => None,
+        };
+
+        if let Some(jwk) = find(&*self.inner.jwks.read().await) {
+            return Ok(jwk);
+        }
+
+        let metadata = self.metadata().await?;
+        let set: JwkSet = telemetry::send(self.inner.client.get(&metadata.jwks_uri))
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| AppError::Internal(format!("Failed to fetch OIDC JWKS: {e}")))?
+            .json()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to parse OIDC JWKS: {e}")))?;
+
+        let jwk = find(&set);
+        *self.inner.jwks.write().await = set;
+        jwk.ok_or(AppError::Unauthorized)
+    }
+
+    /// URL to send the user's browser to in order to start a login.
+    pub async fn authorization_url(&self, challenge: &LoginChallenge) -> Result<String, AppError> {
+        let metadata = self.metadata().await?;
+        let url = reqwest::Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.inner.client_id.as_str()),
+                ("redirect_uri", self.inner.redirect_url.as_str()),
+                ("scope", "openid email profile"),
+                ("state", challenge.state.as_str()),
+                ("nonce", challenge.nonce.as_str()),
+                ("code_challenge", challenge.code_challenge().as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| AppError::Internal(format!("Invalid authorization endpoint: {e}")))?;
+
+        Ok(url.into())
+    }
+
+    /// Redeems an authorization code and returns the validated ID token claims.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        expected_nonce: &str,
+    ) -> Result<IdTokenClaims, AppError> {
+        let metadata = self.metadata().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.inner.redirect_url.as_str()),
+            ("client_id", self.inner.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.inner.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+
+        let resp = telemetry::send(self.inner.client.post(&metadata.token_endpoint).form(&form))
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to call OIDC token endpoint: {e}")))?;
+
+        if !resp.status().is_success() {
+            let err_text = resp.text().await.unwrap_or_default();
+            tracing::warn!("OIDC code exchange rejected: {}", err_text);
+            return Err(AppError::Unauthorized);
+        }
+
+        let token: TokenResponse = resp
+            .json()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to parse OIDC token response: {e}")))?;
+
+        let claims = self.validate_id_token(&token.id_token, &metadata).await?;
+
+        if claims.nonce.as_deref() != Some(expected_nonce) {
+            tracing::warn!("OIDC ID token nonce mismatch");
+            return Err(AppError::Unauthorized);
+        }
+
+        Ok(claims)
+    }
+
+    async fn validate_id_token(
+        &self,
+        id_token: &str,
+        metadata: &ProviderMetadata,
+    ) -> Result<IdTokenClaims, AppError> {
+        let header = jsonwebtoken::decode_header(id_token).map_err(|e| {
+            tracing::warn!("Malformed OIDC ID token: {}", e);
+            AppError::Unauthorized
+        })?;
+
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            tracing::warn!(
+                "OIDC ID token signed with disallowed algorithm {:?}",
+                header.alg
+            );
+            return Err(AppError::Unauthorized);
+        }
+
+        let jwk = self.signing_key(header.kid.as_deref()).await?;
+        let key = DecodingKey::from_jwk(&jwk).map_err(|e| {
+            tracing::warn!("Unusable OIDC signing key: {}", e);
+            AppError::Unauthorized
+        })?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.inner.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        jsonwebtoken::decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| {
+                tracing::warn!("OIDC ID token rejected: {}", e);
+                AppError::Unauthorized
+            })
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use crate::config::RateLimitConfig;
+
+/// Buckets are only pruned once this many clients are being tracked.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Rate limits applied to every authenticated or public request.
+#[derive(Clone)]
+pub struct RateLimits {
+    /// Routes that call the AI model (`/audio/suggest`).
+    pub ai: RateLimiter,
+    /// Every other route.
+    pub api: RateLimiter,
+    /// Calls to the AI model each client may make per UTC day.
+    pub ai_daily_quota: i32,
+}
+
+impl RateLimits {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            ai: RateLimiter::per_minute(config.ai_per_minute),
+            api: RateLimiter::per_minute(config.per_minute),
+            ai_daily_quota: config.ai_daily_quota,
+        }
+    }
+}
+
+/// Outcome of taking a token from a client's bucket, in the shape of the
+/// `RateLimit-*` response headers.
+#[derive(Debug, Clone, Copy)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again.
+    pub reset: u64,
+    /// Seconds until the next request would be allowed; 0 if it already is.
+    pub retry_after: u64,
+}
+
+/// In-memory token-bucket limiter. Each client key gets a bucket of
+/// `capacity` tokens that refills continuously over one minute, so bursts of
+/// up to `capacity` requests are allowed.
+#[derive(Clone)]
+pub struct RateLimiter {
+    capacity: u32,
+    refill_per_sec: f64,
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn per_minute(capacity: u32) -> Self {
+        Self {
+            capacity,
+            refill_per_sec: f64::from(capacity) / 60.0,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Takes one token from `key`'s bucket if one is available.
+    pub fn check(&self, key: &str) -> Decision {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Decision {
+        let capacity = f64::from(self.capacity);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= PRUNE_THRESHOLD && !buckets.contains_key(key) {
+            // A full bucket is indistinguishable from a missing one.
+            buckets.retain(|_, b| self.refilled(b, now) < capacity);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        Decision {
+            allowed,
+            limit: self.capacity,
+            remaining: bucket.tokens.floor() as u32,
+            reset: self.seconds_until(capacity - bucket.tokens),
+            retry_after: if allowed {
+                0
+            } else {
+                self.seconds_until(1.0 - bucket.tokens)
+            },
+        }
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        (bucket.tokens + elapsed * self.refill_per_sec).min(f64::from(self.capacity))
+    }
+
+    fn seconds_until(&self, tokens: f64) -> u64 {
+        if tokens <= 0.0 || self.refill_per_sec <= 0.0 {
+            return 0;
+        }
+        (tokens / self.refill_per_sec).ceil() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn allows_a_burst_up_to_capacity() {
+        let limiter = RateLimiter::per_minute(3);
+        let now = Instant::now();
+
+        let remaining: Vec<u32> = (0..3)
+            .map(|_| limiter.check_at("a", now))
+            .inspect(|d| assert!(d.allowed))
+            .map(|d| d.remaining)
+            .collect();
+        assert_eq!(remaining, [2, 1, 0]);
+    }
+
+    #[test]
+    fn rejects_once_exhausted() {
+        let limiter = RateLimiter::per_minute(3);
+        let now = Instant::now();
+        for _ in 0..3 {
+            limiter.check_at("a", now);
+        }
+
+        let decision = limiter.check_at("a", now);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        // One token every 20 seconds.
+        assert_eq!(decision.retry_after, 20);
+        assert_eq!(decision.reset, 60);
+        // Other clients have their own bucket.
+        assert!(limiter.check_at("b", now).allowed);
+    }
+
+    #[test]
+    fn refills_over_a_minute() {
+        let limiter = RateLimiter::per_minute(3);
+        let now = Instant::now();
+        for _ in 0..3 {
+            limiter.check_at("a", now);
+        }
+
+        assert!(!limiter.check_at("a", now + Duration::from_secs(19)).allowed);
+        assert!(limiter.check_at("a", now + Duration::from_secs(20)).allowed);
+        assert!(!limiter.check_at("a", now + Duration::from_secs(21)).allowed);
+
+        // Never more than a full bucket, however long the client waits.
+        let later = now + Duration::from_secs(3600);
+        assert_eq!(limiter.check_at("a", later).remaining, 2);
+    }
+}
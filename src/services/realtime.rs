@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceState {
+    Viewing,
+    Editing,
+    /// The connection stopped looking at the todo, or disconnected.
+    Idle,
+}
+
+/// What one connection is doing with one todo.
+#[derive(Debug, Clone, Serialize)]
+pub struct Presence {
+    pub connection_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub display_name: Option<String>,
+    pub todo_id: Uuid,
+    pub project_id: Option<Uuid>,
+    pub state: PresenceState,
+    pub since: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Typing {
+    pub connection_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub display_name: Option<String>,
+    pub todo_id: Uuid,
+    pub project_id: Option<Uuid>,
+    /// The field being edited, e.g. `title`.
+    pub field: Option<String>,
+}
+
+/// Ephemeral activity relayed between the connections of a workspace.
+#[derive(Debug, Clone)]
+pub enum Signal {
+    Presence(Presence),
+    Typing(Typing),
+}
+
+impl Signal {
+    pub fn connection_id(&self) -> Uuid {
+        match self {
+            Signal::Presence(p) => p.connection_id,
+            Signal::Typing(t) => t.connection_id,
+        }
+    }
+
+    pub fn todo_id(&self) -> Uuid {
+        match self {
+            Signal::Presence(p) => p.todo_id,
+            Signal::Typing(t) => t.todo_id,
+        }
+    }
+
+    pub fn project_id(&self) -> Option<Uuid> {
+        match self {
+            Signal::Presence(p) => p.project_id,
+            Signal::Typing(t) => t.project_id,
+        }
+    }
+}
+
+/// Presence and typing state of the WebSocket connections to this server.
+/// It lives in memory, so clients connected to different instances do not
+/// see each other's presence.
+#[derive(Clone)]
+pub struct RealtimeHub {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    sender: broadcast::Sender<(Uuid, Signal)>,
+    /// Current presences by workspace.
+    presence: Mutex<HashMap<Uuid, Vec<Presence>>>,
+}
+
+impl Default for RealtimeHub {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        Self {
+            inner: Arc::new(Inner {
+                sender,
+                presence: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+}
+
+impl RealtimeHub {
+    /// Signals of every workspace, tagged with the workspace ID.
+    pub fn subscribe(&self) -> broadcast::Receiver<(Uuid, Signal)> {
+        self.inner.sender.subscribe()
+    }
+
+    /// Records `presence` and relays it. An `Idle` presence removes the
+    /// connection's entry for the todo.
+    pub fn set_presence(&self, workspace_id: Uuid, presence: Presence) {
+        {
+            let mut all = self
+                .inner
+                .presence
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let entries = all.entry(workspace_id).or_default();
+            entries.retain(|p| {
+                !(p.connection_id == presence.connection_id && p.todo_id == presence.todo_id)
+            });
+            if presence.state != PresenceState::Idle {
+                entries.push(presence.clone());
+            }
+            if entries.is_empty() {
+                all.remove(&workspace_id);
+            }
+        }
+
+        let _ = self
+            .inner
+            .sender
+            .send((workspace_id, Signal::Presence(presence)));
+    }
+
+    pub fn typing(&self, workspace_id: Uuid, typing: Typing) {
+        let _ = self
+            .inner
+            .sender
+            .send((workspace_id, Signal::Typing(typing)));
+    }
+
+    /// Removes every presence of a closed connection and tells the others.
+    pub fn disconnect(&self, workspace_id: Uuid, connection_id: Uuid) {
+        let removed: Vec<Presence> = {
+            let mut all = self
+                .inner
+                .presence
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let Some(entries) = all.get_mut(&workspace_id) else {
+                return;
+            };
+            let (removed, kept) = entries
+                .drain(..)
+                .partition(|p| p.connection_id == connection_id);
+            *entries = kept;
+            if entries.is_empty() {
+                all.remove(&workspace_id);
+            }
+            removed
+        };
+
+        let now = Utc::now();
+        for presence in removed {
+            let _ = self.inner.sender.send((
+                workspace_id,
+                Signal::Presence(Presence {
+                    state: PresenceState::Idle,
+                    since: now,
+                    ..presence
+                }),
+            ));
+        }
+    }
+
+    /// Current presences in a workspace.
+    pub fn presences(&self, workspace_id: Uuid) -> Vec<Presence> {
+        self.inner
+            .presence
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&workspace_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,75 @@
+use rand::RngCore;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::error::AppError;
+
+const ISSUER: &str = "AI-Todo";
+const DIGITS: usize = 6;
+const STEP_SECS: u64 = 30;
+/// Accept codes from one step either side of now to tolerate clock drift.
+const SKEW_STEPS: u64 = 1;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+fn totp(secret_base32: &str, account_name: &str) -> Result<TOTP, AppError> {
+    let secret = Secret::Encoded(secret_base32.to_string())
+        .to_bytes()
+        .map_err(|e| AppError::Internal(format!("Invalid TOTP secret: {e:?}")))?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        0,
+        STEP_SECS,
+        secret,
+        Some(ISSUER.to_string()),
+        account_name.replace(':', ""),
+    )
+    .map_err(|e| AppError::Internal(format!("Invalid TOTP parameters: {e}")))
+}
+
+/// Generates a new 160-bit secret, base32 encoded without padding as
+/// authenticator apps expect.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    match Secret::Raw(bytes.to_vec()).to_encoded() {
+        Secret::Encoded(s) => s,
+        Secret::Raw(_) => unreachable!("to_encoded always returns an encoded secret"),
+    }
+}
+
+/// `otpauth://` URI for enrolling the secret, suitable for rendering as a QR code.
+pub fn otpauth_uri(secret_base32: &str, account_name: &str) -> Result<String, AppError> {
+    Ok(totp(secret_base32, account_name)?.get_url())
+}
+
+/// Checks `code` against the secret at `unix_time` and returns the matching
+/// time step. Callers must reject steps at or before the last accepted one
+/// so a code cannot be replayed.
+pub fn verify(secret_base32: &str, code: &str, unix_time: u64) -> Result<Option<u64>, AppError> {
+    let totp = totp(secret_base32, "")?;
+    let code = code.trim();
+    let current = unix_time / STEP_SECS;
+
+    Ok((current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+        .find(|step| totp.generate(step * STEP_SECS) == code))
+}
+
+/// Generates single-use recovery codes such as `3f9c1-a07be-5d2e8-0c41f`.
+/// They carry 80 random bits each: they are stored with the same fast hash
+/// as API tokens, so they must be as infeasible to brute-force.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 10];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            let hex = hex::encode(bytes);
+            [&hex[..5], &hex[5..10], &hex[10..15], &hex[15..]].join("-")
+        })
+        .collect()
+}
+
+/// Canonical form of a user-entered recovery code, used before hashing.
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase().replace(' ', "")
+}
@@ -0,0 +1,430 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::{config::WebhookConfig, error::AppError, models::webhook::WebhookEvent, telemetry};
+
+/// Deliveries are given up on (status `Failed`) after this many attempts.
+pub const MAX_ATTEMPTS: i32 = 8;
+
+/// Delay before the first retry; doubled after every further failure.
+const INITIAL_BACKOFF_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 6 * 60 * 60;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const BATCH_SIZE: i64 = 20;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a claimed delivery is hidden from other workers. If the process
+/// dies mid-send, the delivery is retried once the lease runs out. A batch is
+/// sent concurrently, and each delivery spends at most [`REQUEST_TIMEOUT`]
+/// resolving its host and as long again sending, so the lease outlasts the
+/// batch with room to record the results.
+const CLAIM_LEASE_SECS: i64 = 60;
+
+/// Body sent to webhook endpoints.
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    id: Uuid,
+    #[serde(rename = "type")]
+    event: WebhookEvent,
+    created_at: chrono::DateTime<Utc>,
+    data: &'a T,
+}
+
+/// Queues `event` for every active webhook in the current workspace that
+/// subscribes to it. Must be called on the request's tenant transaction so the
+/// deliveries are only sent if the change commits.
+pub async fn enqueue<T: Serialize>(
+    conn: &mut PgConnection,
+    event: WebhookEvent,
+    data: &T,
+) -> Result<(), AppError> {
+    let now = Utc::now();
+    let envelope = Envelope {
+        id: Uuid::new_v4(),
+        event,
+        created_at: now,
+        data,
+    };
+    let payload = serde_json::to_value(&envelope).map_err(|e| {
+        tracing::error!("Failed to serialize webhook payload: {:?}", e);
+        AppError::Internal("failed to queue webhooks".into())
+    })?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO webhook_deliveries (id, webhook_id, event_id, event, payload, status, next_attempt_at, created_at)
+        SELECT gen_random_uuid(), id, $1, $2, $3, 'Pending', $4, $4
+        FROM webhooks
+        WHERE active AND $2 = ANY (events)
+        "#,
+        envelope.id,
+        event.to_string(),
+        payload,
+        now
+    )
+    .execute(conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to queue webhook deliveries: {:?}", e);
+        AppError::Internal("failed to queue webhooks".into())
+    })?;
+
+    Ok(())
+}
+
+/// Value of the `X-Webhook-Signature` header: the hex HMAC-SHA256 of
+/// `"{timestamp}.{body}"` keyed with the webhook secret.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+    format!(
+        "t={timestamp},v1={}",
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+/// Generates a signing secret for webhooks created without one.
+pub fn generate_secret() -> String {
+    let bytes: [u8; 24] = rand::random();
+    format!("whsec_{}", hex::encode(bytes))
+}
+
+/// Whether deliveries may go to `ip`. Private, loopback, link-local and other
+/// non-public addresses are refused, so that webhooks cannot be used to reach
+/// the server's own network; loopback only unless `allow_loopback` is set.
+pub fn is_allowed_target(ip: IpAddr, allow_loopback: bool) -> bool {
+    let ip = match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    };
+    if ip.is_loopback() {
+        return allow_loopback;
+    }
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            let shared = a == 100 && (64..128).contains(&b);
+            !(a == 0
+                || shared
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation())
+        }
+        IpAddr::V6(v6) => {
+            !(v6.is_unspecified()
+                || v6.is_multicast()
+                || v6.is_unique_local()
+                || v6.is_unicast_link_local())
+        }
+    }
+}
+
+/// Checks that `url` is an http(s) URL whose host resolves only to
+/// addresses [`is_allowed_target`] accepts. Returns a message for the
+/// webhook's owner otherwise.
+pub async fn check_target(url: &str, allow_loopback: bool) -> Result<(), String> {
+    let url = reqwest::Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .ok_or("url must be an absolute http or https URL")?;
+    let host = url
+        .host_str()
+        .ok_or("url must be an absolute http or https URL")?;
+
+    let addrs: Vec<IpAddr> = match host.trim_matches(['[', ']']).parse() {
+        Ok(ip) => vec![ip],
+        Err(_) => tokio::net::lookup_host((host, 0))
+            .await
+            .map_err(|_| format!("{host} could not be resolved"))?
+            .map(|addr| addr.ip())
+            .collect(),
+    };
+    if addrs.is_empty() {
+        return Err(format!("{host} could not be resolved"));
+    }
+    if !addrs
+        .iter()
+        .all(|ip| is_allowed_target(*ip, allow_loopback))
+    {
+        return Err("url must not point to a private, loopback or link-local address".into());
+    }
+    Ok(())
+}
+
+/// Resolves hosts for deliveries, leaving out the addresses they may not go
+/// to. A host that passed [`check_target`] could resolve elsewhere by the
+/// time the request is sent.
+struct PublicResolver {
+    allow_loopback: bool,
+}
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let allow_loopback = self.allow_loopback;
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_allowed_target(addr.ip(), allow_loopback))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no address webhooks may use", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Delay before attempt `attempts + 1`, given `attempts` failed attempts.
+fn backoff(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    let secs = INITIAL_BACKOFF_SECS.saturating_mul(1_i64 << exponent);
+    chrono::Duration::seconds(secs.min(MAX_BACKOFF_SECS))
+}
+
+/// Background task sending queued deliveries.
+pub struct WebhookWorker {
+    pool: PgPool,
+    client: reqwest::Client,
+    allow_loopback: bool,
+}
+
+struct ClaimedDelivery {
+    id: Uuid,
+    event: String,
+    payload: serde_json::Value,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+impl WebhookWorker {
+    pub fn new(pool: PgPool, config: &WebhookConfig) -> Self {
+        Self {
+            pool,
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .redirect(reqwest::redirect::Policy::none())
+                .dns_resolver(std::sync::Arc::new(PublicResolver {
+                    allow_loopback: config.allow_loopback,
+                }))
+                .build()
+                .expect("Failed to build HTTP client"),
+            allow_loopback: config.allow_loopback,
+        }
+    }
+
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move { self.run().await })
+    }
+
+    async fn run(self) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.tick().await {
+                tracing::error!("Webhook worker failed: {:?}", e);
+            }
+        }
+    }
+
+    async fn tick(&self) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+
+        // The worker is not tied to a workspace and relies on connecting as
+        // the table owner, which row-level security does not apply to.
+        let claimed = sqlx::query_as!(
+            ClaimedDelivery,
+            r#"
+            WITH due AS (
+                SELECT d.id
+                FROM webhook_deliveries d JOIN webhooks w ON w.id = d.webhook_id
+                WHERE d.status = 'Pending' AND d.next_attempt_at <= $1 AND w.active
+                ORDER BY d.next_attempt_at
+                LIMIT $2
+                FOR UPDATE OF d SKIP LOCKED
+            )
+            UPDATE webhook_deliveries d SET next_attempt_at = $3
+            FROM due, webhooks w
+            WHERE d.id = due.id AND w.id = d.webhook_id
+            RETURNING d.id, d.event, d.payload, d.attempts, w.url, w.secret
+            "#,
+            now,
+            BATCH_SIZE,
+            now + chrono::Duration::seconds(CLAIM_LEASE_SECS)
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        // A delivery that cannot be recorded is retried once its lease runs
+        // out; the rest of the batch goes ahead.
+        futures::future::join_all(claimed.into_iter().map(|delivery| async move {
+            let id = delivery.id;
+            if let Err(e) = self.deliver(delivery).await {
+                tracing::error!(delivery_id = %id, "Failed to record webhook delivery: {:?}", e);
+            }
+        }))
+        .await;
+
+        Ok(())
+    }
+
+    async fn deliver(&self, delivery: ClaimedDelivery) -> Result<(), sqlx::Error> {
+        let body = delivery.payload.to_string();
+        let signature = sign(&delivery.secret, Utc::now().timestamp(), &body);
+
+        // Checked again in case the webhook predates the check or its host
+        // now resolves elsewhere.
+        let checked = tokio::time::timeout(
+            REQUEST_TIMEOUT,
+            check_target(&delivery.url, self.allow_loopback),
+        )
+        .await
+        .unwrap_or_else(|_| Err("timed out resolving the url's host".to_string()));
+        let result = match checked {
+            Ok(()) => telemetry::send(
+                self.client
+                    .post(&delivery.url)
+                    .header("Content-Type", "application/json")
+                    .header("X-Webhook-Event", &delivery.event)
+                    .header("X-Webhook-Delivery", delivery.id.to_string())
+                    .header("X-Webhook-Signature", signature)
+                    .body(body),
+            )
+            .await
+            .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+
+        let (response_status, error) = match result {
+            Ok(response) if response.status().is_success() => {
+                (Some(i32::from(response.status().as_u16())), None)
+            }
+            Ok(response) => (
+                Some(i32::from(response.status().as_u16())),
+                Some(format!("endpoint responded with {}", response.status())),
+            ),
+            Err(e) => (None, Some(e)),
+        };
+
+        let now = Utc::now();
+        let attempts = delivery.attempts + 1;
+
+        match error {
+            None => {
+                sqlx::query!(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = 'Succeeded', attempts = $2, response_status = $3, last_error = NULL,
+                        next_attempt_at = NULL, delivered_at = $4
+                    WHERE id = $1
+                    "#,
+                    delivery.id,
+                    attempts,
+                    response_status,
+                    now
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            Some(error) => {
+                let (status, next_attempt_at) = if attempts >= MAX_ATTEMPTS {
+                    ("Failed", None)
+                } else {
+                    ("Pending", Some(now + backoff(attempts)))
+                };
+                tracing::warn!(
+                    delivery_id = %delivery.id,
+                    attempts,
+                    "Webhook delivery failed: {}",
+                    error
+                );
+
+                sqlx::query!(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = $2, attempts = $3, response_status = $4, last_error = $5,
+                        next_attempt_at = $6
+                    WHERE id = $1
+                    "#,
+                    delivery.id,
+                    status,
+                    attempts,
+                    response_status,
+                    error,
+                    next_attempt_at
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(ip: &str, allow_loopback: bool) -> bool {
+        is_allowed_target(ip.parse().unwrap(), allow_loopback)
+    }
+
+    #[test]
+    fn internal_addresses_are_refused() {
+        for ip in [
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "fd00::1",
+            "fe80::1",
+            "::",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(!allowed(ip, true), "{ip} was allowed");
+        }
+        assert!(allowed("93.184.216.34", false));
+        assert!(allowed("2606:2800:220:1::1", false));
+    }
+
+    #[test]
+    fn loopback_needs_the_flag() {
+        for ip in ["127.0.0.1", "::1", "::ffff:127.0.0.1"] {
+            assert!(!allowed(ip, false), "{ip} was allowed");
+            assert!(allowed(ip, true), "{ip} was refused");
+        }
+    }
+
+    #[tokio::test]
+    async fn urls_are_checked_by_address() {
+        assert!(
+            check_target("http://169.254.169.254/latest", true)
+                .await
+                .is_err()
+        );
+        assert!(check_target("http://[::1]:9100/hook", false).await.is_err());
+        assert!(check_target("http://[::1]:9100/hook", true).await.is_ok());
+        assert!(check_target("ftp://93.184.216.34/", false).await.is_err());
+        assert!(
+            check_target("https://93.184.216.34/hook", false)
+                .await
+                .is_ok()
+        );
+    }
+}
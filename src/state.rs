@@ -1,11 +1,135 @@
+use std::sync::Arc;
+
+use sqlx::{
+    PgConnection, PgPool, Postgres, SqlitePool, Transaction,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+use uuid::Uuid;
+
+use crate::{
+    auth::AuthContext,
+    config::Config,
+    error::AppError,
+    repositories::{TodoRepository, postgres::PgTodoRepository, sqlite::SqliteTodoRepository},
+};
+
+/// Database role that request transactions switch to so that row-level
+/// security applies even when the pool connects as a superuser.
+const TENANT_ROLE: &str = "ai_todo_tenant";
+
 #[derive(Clone)]
 pub struct AppState {
+    pub config: Arc<Config>,
+    /// Never connected when todos are stored in SQLite: the routes that need
+    /// PostgreSQL are not mounted then.
     pub pool: sqlx::PgPool,
+    /// Set when todos are stored in SQLite rather than PostgreSQL.
+    pub sqlite: Option<SqlitePool>,
+    /// Where the todo routes read and write todos.
+    pub todos: Arc<dyn TodoRepository>,
     pub gemini: crate::services::gemini::GeminiService,
+    /// `None` when SSO is not configured.
+    pub oidc: Option<crate::services::oidc::OidcService>,
+    /// Hash of the bootstrap `ADMIN_TOKEN`, which is granted every scope.
+    pub admin_token_hash: Option<String>,
+    pub rate_limits: crate::services::rate_limit::RateLimits,
+    pub metrics: crate::services::metrics::Metrics,
+    pub todo_events: crate::services::events::EventBus,
+    pub realtime: crate::services::realtime::RealtimeHub,
+    pub status_cache: crate::routes::health::StatusCache,
+    pub todo_counts: crate::routes::metrics::TodoCountCache,
+    /// Stamps CRDT operations made through the REST API.
+    pub clock: crate::services::crdt::HlcClock,
 }
 
 impl AppState {
-    pub fn new(pool: sqlx::PgPool, gemini: crate::services::gemini::GeminiService) -> Self {
-        Self { pool, gemini }
+    /// Builds the services `config` describes. Fails if a setting that is
+    /// only needed to serve, such as the Gemini API key, is missing.
+    pub fn new(pool: sqlx::PgPool, config: Config) -> Result<Self, AppError> {
+        let clock = crate::services::crdt::HlcClock::default();
+        Ok(Self {
+            todos: Arc::new(PgTodoRepository::new(pool.clone(), clock.clone())),
+            gemini: crate::services::gemini::GeminiService::new(&config.gemini)?,
+            oidc: crate::services::oidc::OidcService::new(&config.oidc),
+            admin_token_hash: config
+                .auth
+                .admin_token
+                .as_deref()
+                .map(crate::auth::hash_token),
+            rate_limits: crate::services::rate_limit::RateLimits::new(&config.rate_limits),
+            metrics: crate::services::metrics::Metrics::new(),
+            todo_events: crate::services::events::EventBus::default(),
+            realtime: crate::services::realtime::RealtimeHub::default(),
+            status_cache: crate::routes::health::StatusCache::default(),
+            todo_counts: crate::routes::metrics::TodoCountCache::default(),
+            clock,
+            config: Arc::new(config),
+            pool,
+            sqlite: None,
+        })
+    }
+
+    /// Like [`AppState::new`], for todos stored in SQLite.
+    pub fn new_sqlite(pool: SqlitePool, config: Config) -> Result<Self, AppError> {
+        // `.invalid` never resolves, so a stray query fails rather than
+        // reaching a PostgreSQL server on this host.
+        let unconnected = PgPoolOptions::new()
+            .connect_lazy_with(PgConnectOptions::new().host("postgres.invalid"));
+        Ok(Self {
+            todos: Arc::new(SqliteTodoRepository::new(pool.clone())),
+            sqlite: Some(pool),
+            ..Self::new(unconnected, config)?
+        })
+    }
+
+    /// Begins a transaction scoped to the caller's workspace. Tenant tables
+    /// (todos, projects, shares, invitations) must only be accessed through
+    /// it: their row-level security policies hide every other workspace, and
+    /// rows inserted through it default to the caller's workspace.
+    pub async fn tenant(
+        &self,
+        ctx: &AuthContext,
+    ) -> Result<Transaction<'static, Postgres>, AppError> {
+        begin_tenant(&self.pool, ctx).await
     }
+
+    /// Replaces the todo store, as tests do with an in-memory one.
+    pub fn with_todos(mut self, todos: impl TodoRepository + 'static) -> Self {
+        self.todos = Arc::new(todos);
+        self
+    }
+}
+
+/// [`AppState::tenant`], for code that holds only the pool.
+pub async fn begin_tenant(
+    pool: &PgPool,
+    ctx: &AuthContext,
+) -> Result<Transaction<'static, Postgres>, AppError> {
+    let db_err = |e: sqlx::Error| {
+        tracing::error!("Failed to begin tenant transaction: {:?}", e);
+        AppError::Internal("database unavailable".into())
+    };
+
+    let mut tx = pool.begin().await.map_err(db_err)?;
+    enter_tenant(&mut tx, ctx.workspace_id)
+        .await
+        .map_err(db_err)?;
+
+    Ok(tx)
+}
+
+/// Confines the rest of the current transaction to `workspace_id`, as
+/// [`AppState::tenant`] does for requests. Also used by the operator commands,
+/// which run outside any request.
+pub async fn enter_tenant(conn: &mut PgConnection, workspace_id: Uuid) -> Result<(), sqlx::Error> {
+    // `is_local = true` scopes both settings to this transaction, so they
+    // are gone when the connection returns to the pool.
+    sqlx::query!(
+        "SELECT set_config('role', $1, true) AS role, set_config('app.workspace_id', $2, true) AS workspace_id",
+        TENANT_ROLE,
+        workspace_id.to_string()
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok(())
 }
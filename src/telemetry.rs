@@ -0,0 +1,289 @@
+//! Logging, and OpenTelemetry trace export when an OTLP endpoint is
+//! configured. Exported traces cover HTTP requests, the database queries
+//! made while handling them and calls to Gemini and other services. W3C
+//! `traceparent` headers are read from incoming requests and added to
+//! outgoing ones.
+
+use std::time::{Duration, SystemTime};
+
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::{Request, Response},
+};
+use opentelemetry::{
+    Context, KeyValue, global,
+    trace::{Span as _, SpanKind, TraceContextExt, Tracer as _, TracerProvider as _},
+};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    Resource,
+    propagation::TraceContextPropagator,
+    trace::{SdkTracer, SdkTracerProvider},
+};
+use tower_http::trace::{DefaultOnResponse, OnResponse};
+use tracing::{Event, Instrument, Level, Span, Subscriber, field};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    EnvFilter, Layer, filter::Targets, layer::Context as LayerContext, prelude::*,
+    registry::LookupSpan,
+};
+
+use crate::{
+    config::{Backend, Config, ConfigError, LogFormat},
+    request_id,
+};
+
+/// Flushes exported spans when dropped.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("failed to flush traces: {e}");
+        }
+    }
+}
+
+/// Installs the global subscriber. Logs are written in `log.format`,
+/// filtered by `RUST_LOG` or else `default_level`. With `export` set and an
+/// OTLP endpoint configured, spans are also sent to the endpoint,
+/// independently of `RUST_LOG`.
+pub fn init(config: &Config, default_level: &str, export: bool) -> Result<Telemetry, ConfigError> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level));
+    let fmt = match config.log.format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        // Event fields at the top level; span fields, such as the request
+        // ID, under "span" and "spans".
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .boxed(),
+    }
+    .with_filter(filter);
+
+    let backend = config.database.backend();
+    let config = &config.telemetry;
+    let endpoint = config.otlp_endpoint.as_deref().filter(|_| export);
+    let Some(endpoint) = endpoint else {
+        tracing_subscriber::registry().with(fmt).init();
+        return Ok(Telemetry { provider: None });
+    };
+
+    // Like the standard OTEL_EXPORTER_OTLP_ENDPOINT, the setting is the
+    // collector's base URL.
+    let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(url)
+        .build()
+        .map_err(|e| {
+            ConfigError::Invalid(vec![format!(
+                "telemetry.otlp_endpoint (OTEL_EXPORTER_OTLP_ENDPOINT): {e}"
+            )])
+        })?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build();
+    let tracer = provider.tracer("ai-todo");
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let spans = tracing_opentelemetry::layer()
+        .with_tracer(tracer.clone())
+        .with_filter(Targets::new().with_default(Level::INFO));
+    // sqlx logs every query at debug level; only this layer sees them.
+    let queries = QuerySpans { tracer, backend }
+        .with_filter(Targets::new().with_target("sqlx::query", Level::DEBUG));
+    tracing_subscriber::registry()
+        .with(fmt)
+        .with(spans)
+        .with(queries)
+        .init();
+
+    Ok(Telemetry {
+        provider: Some(provider),
+    })
+}
+
+/// Span for an incoming HTTP request, continuing the trace in its
+/// `traceparent` header if there is one.
+pub fn http_span(req: &Request<Body>) -> Span {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(req.uri().path(), MatchedPath::as_str);
+    let request_id = req
+        .headers()
+        .get(&request_id::X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let span = tracing::info_span!(
+        "request",
+        request_id,
+        method = %req.method(),
+        uri = %req.uri(),
+        version = ?req.version(),
+        otel.name = format!("{} {route}", req.method()),
+        otel.kind = "server",
+        http.route = route,
+        http.response.status_code = field::Empty,
+        otel.status_code = field::Empty,
+    );
+
+    let parent = global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(req.headers())));
+    // Fails only when no OpenTelemetry layer is installed.
+    let _ = span.set_parent(parent);
+    span
+}
+
+/// Logs the response like tower-http does by default, and records its
+/// status on the request span.
+pub fn on_http_response(res: &Response<Body>, latency: Duration, span: &Span) {
+    span.record("http.response.status_code", res.status().as_u16());
+    if res.status().is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    DefaultOnResponse::new()
+        .level(Level::INFO)
+        .on_response(res, latency, span);
+}
+
+/// Sends an outgoing request in a client span, with a `traceparent` header
+/// so that the receiving service can continue the trace.
+pub async fn send(request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    // The full URL is left out: Gemini takes its API key in the query.
+    let span = tracing::info_span!(
+        "outgoing request",
+        otel.name = format!("{} {host}", request.method()),
+        otel.kind = "client",
+        http.request.method = %request.method(),
+        server.address = host,
+        http.response.status_code = field::Empty,
+        otel.status_code = field::Empty,
+    );
+    let cx = span.context();
+    global::get_text_map_propagator(|p| {
+        p.inject_context(&cx, &mut HeaderInjector(request.headers_mut()));
+    });
+
+    let response = client.execute(request).instrument(span.clone()).await;
+    match &response {
+        Ok(response) => {
+            span.record("http.response.status_code", response.status().as_u16());
+            if response.status().is_server_error() {
+                span.record("otel.status_code", "ERROR");
+            }
+        }
+        Err(_) => {
+            span.record("otel.status_code", "ERROR");
+        }
+    }
+    response
+}
+
+/// Turns the events sqlx logs after each query into client spans. Queries
+/// made outside any traced operation, such as the webhook worker polling,
+/// are not exported.
+struct QuerySpans {
+    tracer: SdkTracer,
+    backend: Backend,
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for QuerySpans {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        // The OpenTelemetry layer makes the entered span's context current.
+        let parent = Context::current();
+        if !parent.has_active_span() {
+            return;
+        }
+
+        let mut query = QueryFields::default();
+        event.record(&mut query);
+        let end = SystemTime::now();
+        let start = end - Duration::from_secs_f64(query.elapsed_secs);
+        // sqlx only logs the full statement when it differs from the summary.
+        let statement = if query.statement.trim().is_empty() {
+            query.summary.clone()
+        } else {
+            query.statement.trim().to_string()
+        };
+        let operation = statement
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_uppercase();
+
+        let mut span = self
+            .tracer
+            .span_builder(operation.clone())
+            .with_kind(SpanKind::Client)
+            .with_start_time(start)
+            .with_attributes([
+                KeyValue::new("db.system.name", db_system_name(self.backend)),
+                KeyValue::new("db.operation.name", operation),
+                KeyValue::new("db.query.text", statement),
+                KeyValue::new("db.response.returned_rows", query.rows_returned as i64),
+                KeyValue::new("db.response.affected_rows", query.rows_affected as i64),
+            ])
+            .start_with_context(&self.tracer, &parent);
+        span.end_with_timestamp(end);
+    }
+}
+
+/// The backend's `db.system.name` from the OpenTelemetry conventions.
+fn db_system_name(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Postgres => "postgresql",
+        Backend::Sqlite => "sqlite",
+    }
+}
+
+#[derive(Default)]
+struct QueryFields {
+    summary: String,
+    statement: String,
+    rows_returned: u64,
+    rows_affected: u64,
+    elapsed_secs: f64,
+}
+
+impl field::Visit for QueryFields {
+    fn record_str(&mut self, field: &field::Field, value: &str) {
+        match field.name() {
+            "summary" => self.summary = value.to_string(),
+            "db.statement" => self.statement = value.to_string(),
+            _ => {}
+        }
+    }
+
+    fn record_u64(&mut self, field: &field::Field, value: u64) {
+        match field.name() {
+            "rows_returned" => self.rows_returned = value,
+            "rows_affected" => self.rows_affected = value,
+            _ => {}
+        }
+    }
+
+    fn record_f64(&mut self, field: &field::Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.elapsed_secs = value;
+        }
+    }
+
+    fn record_debug(&mut self, _field: &field::Field, _value: &dyn std::fmt::Debug) {}
+}
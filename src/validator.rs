@@ -6,7 +6,7 @@ use axum::{
     extract::{FromRequest, Request},
 };
 use serde::de::DeserializeOwned;
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
 
 pub struct ValidatedJson<T>(pub T);
 
@@ -45,25 +45,28 @@ where
         };
 
         // 2. Validate logic and handle constraint errors
-        data.validate().map_err(|err| {
-            let field_errors = err
-                .field_errors()
-                .into_iter()
-                .map(|(field, errors)| {
-                    let msgs = errors
-                        .iter()
-                        .map(|e| {
-                            e.message
-                                .as_ref()
-                                .map_or_else(|| e.code.to_string(), ToString::to_string)
-                        })
-                        .collect();
-                    (field.to_string(), msgs)
-                })
-                .collect();
-            AppError::InvalidInput(field_errors)
-        })?;
+        data.validate().map_err(validation_error)?;
 
         Ok(ValidatedJson(data))
     }
 }
+
+/// Converts `validator` errors into the field-keyed `InvalidInput` error.
+pub fn validation_error(err: ValidationErrors) -> AppError {
+    let field_errors = err
+        .field_errors()
+        .into_iter()
+        .map(|(field, errors)| {
+            let msgs = errors
+                .iter()
+                .map(|e| {
+                    e.message
+                        .as_ref()
+                        .map_or_else(|| e.code.to_string(), ToString::to_string)
+                })
+                .collect();
+            (field.to_string(), msgs)
+        })
+        .collect();
+    AppError::InvalidInput(field_errors)
+}
@@ -0,0 +1,36 @@
+//! API versions. Each version is a router mounted under its own prefix
+//! (`/v1`, ...) by [`crate::app::create_app`]. The routes served before
+//! versioning was introduced remain available without a prefix as deprecated
+//! aliases of `/v1`.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+/// When the unprefixed routes were deprecated, as an RFC 9745 date
+/// (2026-10-19).
+const DEPRECATED_AT: &str = "@1792368000";
+
+/// When the unprefixed routes will be removed, as an RFC 8594 HTTP date.
+const SUNSET_AT: &str = "Mon, 19 Apr 2027 00:00:00 GMT";
+
+pub const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+pub const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// Marks a response from an unprefixed route as deprecated and links to the
+/// same route under `/v1`.
+pub async fn deprecated_alias(req: Request, next: Next) -> Response {
+    let successor = format!("</v1{}>; rel=\"successor-version\"", req.uri().path());
+    let mut response = next.run(req).await;
+
+    let headers = response.headers_mut();
+    headers.insert(DEPRECATION, HeaderValue::from_static(DEPRECATED_AT));
+    headers.insert(SUNSET, HeaderValue::from_static(SUNSET_AT));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.append(axum::http::header::LINK, link);
+    }
+    response
+}